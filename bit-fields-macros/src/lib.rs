@@ -1,8 +1,9 @@
 #![warn(clippy::pedantic)]
 use std::collections::HashSet;
 use std::fmt::Write;
+use std::iter::Peekable;
 
-use proc_macro::{Delimiter, Group, TokenStream, TokenTree};
+use proc_macro::{token_stream, Delimiter, Group, Ident, TokenStream, TokenTree};
 
 // TODO Allow writing rustdoc comments on bitfield structs
 
@@ -34,17 +35,47 @@ use proc_macro::{Delimiter, Group, TokenStream, TokenTree};
 /// └───────┴────────────┴───┴───────┴───────┴────────────┴───┴───────┴───────┴───┴────────────┴───┴───────┴───┘
 /// ```
 ///
+/// A number of adjacent equally sized bit ranges can be defined as a single array field by giving
+/// its length and the size of each element e.g. `descriptors[4]: 0..32 step 8`.
+///
+/// A field may be followed by the policy `GeneratedBitField::supports` compares it with, one of
+/// `superset`, `>=`, `<=`, `==` or `ignore` e.g. `max_size: 8..16 >=`. Bit flags default to
+/// `superset` and bit ranges to `ignore`, array policies apply to each element.
+///
+/// Fields may be annotated with the specification revision which introduced them and the vendors
+/// defining them e.g. `#[since("SDM-079")] #[vendor(intel|amd)] sse3: 0`, these are given by
+/// `bit_fields::BitField::FIELDS`.
+///
+/// With the `proptest` or `quickcheck` features of `bit-fields` enabled, `Arbitrary` is
+/// implemented for the generated structures. Arbitrary values with all reserved bits zero can be
+/// generated with `bit_fields::ArbitraryOptions` and `bit_fields::ReservedZero` respectively.
+///
+/// When the meaning of some bits depends on the value of a bit range, the layouts can be given
+/// following `match` and the bit range. Each layout, with or without fields, generates a structure
+/// holding all the bits (e.g. `GeneratedBitFieldInvalid` and `GeneratedBitFieldValid`) and a
+/// variant of `GeneratedBitFieldLayout`, which is returned by `GeneratedBitField::layout`.
+/// ```ignore
+/// #[rustfmt::skip]
+/// bit_fields::bitfield!(GeneratedBitField, u32, {
+///     kind: 0..4,
+/// }, match kind {
+///     0 => Invalid,
+///     1 => Valid {
+///         base: 12..32,
+///     },
+/// });
+/// ```
+///
 /// # Panics
 ///
 /// For a whole load of reason.
-#[allow(clippy::too_many_lines)]
 #[proc_macro]
 pub fn bitfield(item: TokenStream) -> TokenStream {
     const IDENT_ERR: &str = "1st token must be struct identifier";
     const TYPE_ERR: &str = "3rd token must be type identifier, options: [u8, u16, u32, u64, u128]";
-    const FIELDS_ERR: &str = "5th token must be an array of types and bit indexes, they must be \
-                              ordered non-overlapping, unique and within the bounds of the given \
-                              type. e.g. `[FlagOne: 2, FlagTwo: 3, FlagThree: 7, FlagFour: 11]`";
+    const MATCH_ERR: &str = "7th token must be `match` followed by the identifier of a bit range \
+                             and a group of layouts e.g. `match kind { 0 => Invalid, 1 => Valid \
+                             { flag: 4 } }`";
 
     // eprintln!("item: {:#?}",item);
     // panic!("stop here");
@@ -67,17 +98,169 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
         _ => panic!("{}", TYPE_ERR),
     };
 
-    let mut struct_bits = String::new();
-    let mut struct_new_bits = String::new();
-    let mut bit_index = String::new();
-    let bits_len = match struct_data_type.as_str() {
+    // Skip seperator
+    let group = match token_stream_iter.nth(1) {
+        Some(TokenTree::Group(group)) => group,
+        None => Group::new(Delimiter::None, TokenStream::new()),
+        Some(other) => {
+            return diagnostic(
+                other.span(),
+                "5th token should be group of bit flags and bit ranges",
+            )
+        }
+    };
+
+    let struct_name = struct_name.to_string();
+    let (mut layout, fields) = bitfield_struct(&struct_name, &struct_data_type, &group);
+
+    // Skip seperator
+    match token_stream_iter.nth(1) {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "match" => {
+            let (Some(TokenTree::Ident(discriminant)), Some(TokenTree::Group(variants))) =
+                (token_stream_iter.next(), token_stream_iter.next())
+            else {
+                return diagnostic(ident.span(), MATCH_ERR);
+            };
+            layout.push_str(&bitfield_variants(
+                &struct_name,
+                &struct_data_type,
+                &fields,
+                &discriminant,
+                &variants,
+            ));
+        }
+        None => {}
+        Some(other) => return diagnostic(other.span(), MATCH_ERR),
+    }
+
+    // eprintln!("layout: {}", layout);
+    // "fn answer() -> u32 { 42 }".parse().unwrap()
+    layout.parse().unwrap()
+}
+
+/// The bits covered by a generated field.
+struct FieldBits {
+    ident: String,
+    start: u8,
+    end: u8,
+    /// Whether the field is a bit range (as opposed to a bit flag or bit range array).
+    range: bool,
+}
+
+/// Parses the comparison policy which may follow the position of a field, returning the
+/// identifier of the matching `bit_fields::Policy` variant, `None` for `ignore` or `default` if no
+/// policy is given.
+fn parse_policy(
+    fields_iter: &mut Peekable<token_stream::IntoIter>,
+    default: Option<&'static str>,
+) -> Option<&'static str> {
+    const POLICY_ERR: &str = "Policy must be one of `superset`, `>=`, `<=`, `==` or `ignore`";
+
+    let policy = match fields_iter.peek() {
+        Some(TokenTree::Ident(ident)) => match ident.to_string().as_str() {
+            "superset" => Some("Superset"),
+            "ignore" => None,
+            _ => return diagnostic(ident.span(), POLICY_ERR),
+        },
+        Some(TokenTree::Punct(punct)) if matches!(punct.as_char(), '>' | '<' | '=') => {
+            let first = punct.clone();
+            fields_iter.next();
+            match fields_iter.peek() {
+                Some(TokenTree::Punct(second)) if second.as_char() == '=' => {}
+                _ => return diagnostic(first.span(), POLICY_ERR),
+            }
+            match first.as_char() {
+                '>' => Some("GreaterOrEqual"),
+                '<' => Some("LessOrEqual"),
+                _ => Some("Equal"),
+            }
+        }
+        _ => return default,
+    };
+    fields_iter.next();
+    policy
+}
+
+/// Generates the `Arbitrary` implementations of the enabled property testing features.
+fn arbitrary(struct_name: &str, struct_data_type: &str) -> String {
+    let mut arbitrary = String::new();
+    if cfg!(feature = "proptest") {
+        write!(
+            &mut arbitrary,
+            "
+        impl bit_fields::proptest::arbitrary::Arbitrary for {struct_name} {{
+            type Parameters = bit_fields::ArbitraryOptions;
+            type Strategy = bit_fields::proptest::strategy::BoxedStrategy<Self>;
+            fn arbitrary_with(options: Self::Parameters) -> Self::Strategy {{
+                bit_fields::arbitrary_strategy(options)
+            }}
+        }}
+        "
+        )
+        .unwrap();
+    }
+    if cfg!(feature = "quickcheck") {
+        write!(
+            &mut arbitrary,
+            "
+        impl bit_fields::quickcheck::Arbitrary for {struct_name} {{
+            fn arbitrary(g: &mut bit_fields::quickcheck::Gen) -> Self {{
+                Self::from(<{struct_data_type} as bit_fields::quickcheck::Arbitrary>::arbitrary(g))
+            }}
+            fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {{
+                Box::new(bit_fields::quickcheck::Arbitrary::shrink(&self.data).map(Self::from))
+            }}
+        }}
+        "
+        )
+        .unwrap();
+    }
+    arbitrary
+}
+
+/// Returns the identifier of the `const` mask of the given field e.g. `SSE3_MASK` for `sse3`.
+fn mask_ident(field_ident: &str) -> String {
+    format!("{}_MASK", field_ident.to_uppercase())
+}
+
+/// Returns the maximum value storable in the given number of bits.
+fn max_value(bits: u8) -> u128 {
+    u128::MAX
+        .checked_shr(128 - u32::from(bits))
+        .unwrap_or_default()
+}
+
+/// Returns the number of bits in the given underlying data type.
+fn bits_len(data_type: &str) -> u8 {
+    match data_type {
         "u8" => 8,
         "u16" => 16,
         "u32" => 32,
         "u64" => 64,
         "u128" => 128,
         _ => unreachable!(),
-    };
+    }
+}
+
+/// Generates the bit field structure `struct_name` from the given group of bit flags and bit
+/// ranges, returning the generated source and the bits covered by each field.
+#[allow(clippy::too_many_lines)]
+fn bitfield_struct(
+    struct_name: &str,
+    struct_data_type: &str,
+    group: &Group,
+) -> (String, Vec<FieldBits>) {
+    const FIELDS_ERR: &str = "5th token must be an array of types and bit indexes, they must be \
+                              ordered non-overlapping, unique and within the bounds of the given \
+                              type. e.g. `[FlagOne: 2, FlagTwo: 3, FlagThree: 7, FlagFour: 11]`";
+    const ARRAY_ERR: &str = "Array field must be followed by a bit range and step, the range must \
+                             be the length of the array multiplied by the step. e.g. \
+                             `descriptors[4]: 0..32 step 8`";
+
+    let mut struct_bits = String::new();
+    let mut struct_new_bits = String::new();
+    let mut bit_index = String::new();
+    let bits_len = bits_len(struct_data_type);
     for i in 0u8..bits_len {
         write!(&mut struct_bits, "bit_fields::Bit<{struct_data_type},{i}>,").unwrap();
         struct_new_bits.push_str("bit_fields::Bit(std::marker::PhantomData),");
@@ -106,6 +289,14 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
     let mut fields_disjoint_fn = String::from("false");
     let mut fields_intersection_fn = String::new();
     let mut fields_union_fn = String::new();
+    let mut fields_supports_fn = String::new();
+    let mut fields_supports_len = 0usize;
+    let mut fields_definitions = String::new();
+    // The key, first bit and maximum value of each value in the `HashMap<String, u128>` conversions
+    let mut map_values = String::new();
+    let mut map_values_len = 0usize;
+    // The `const` masks and getters of the fields
+    let mut fields_const_fns = String::new();
     let mut struct_bit_range_definitions = String::new();
     let mut struct_doc_table_layout =
         String::from("///     <tr><th>Bit/s</th><th>Identifier</th><th>Descripton</th></tr>\n");
@@ -129,25 +320,16 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
         String::from("└───────┴"),
         String::new(),
     ];
-    // Skip seperator
-    let group = match token_stream_iter.nth(1) {
-        Some(TokenTree::Group(group)) => group,
-        None => Group::new(Delimiter::None, TokenStream::new()),
-        Some(other) => {
-            return diagnostic(
-                other.span(),
-                "5th token should be group of bit flags and bit ranges",
-            )
-        }
-    };
-
     let fields_stream = group.stream();
     let mut fields_iter = fields_stream.into_iter().peekable();
     let mut pos = 0;
     // eprintln!("got here?");
 
     let mut pre_existing = HashSet::new();
+    let mut fields = Vec::new();
     let mut rustdoc = String::new();
+    let mut since = None;
+    let mut vendors = Vec::new();
     loop {
         // dbg!("rustdoc: {}", rustdoc);
         let next = fields_iter.next();
@@ -157,28 +339,15 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
                 // dbg!("doc_comment_punct: {:?}", doc_comment_punct);
                 if let Some(TokenTree::Group(doc_group)) = fields_iter.next() {
                     // dbg!("doc_group: {:?}", doc_group);
-                    if let Some(TokenTree::Literal(doc_comment_comment)) =
-                        doc_group.stream().into_iter().nth(2)
-                    {
-                        // dbg!("doc_comment_comment: {:?}", doc_comment_comment);
-                        let temp = doc_comment_comment.to_string();
-                        // Remove " from start and end (TODO Do this better)
-                        let temp = temp
-                            .chars()
-                            .skip(1)
-                            .take(temp.len() - 2)
-                            .collect::<String>();
-                        // Trim space of front e.g. `/// abcde` produces `" abcde"` and we want
-                        // `abcde`
-                        let temp = temp.trim_start();
-                        rustdoc.push_str(temp);
-                        rustdoc.push(' ');
-                        continue;
+                    match field_attribute(&doc_group) {
+                        FieldAttribute::Doc => {
+                            rustdoc.push_str(&rustdoc_comment(&doc_group));
+                            rustdoc.push(' ');
+                        }
+                        FieldAttribute::Since(revision) => since = Some(revision),
+                        FieldAttribute::Vendor(field_vendors) => vendors = field_vendors,
                     }
-                    return diagnostic(
-                        doc_group.span(),
-                        "expected rustdoc comment within `#` group",
-                    );
+                    continue;
                 }
                 return diagnostic(
                     doc_comment_punct.span(),
@@ -197,7 +366,33 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
             None => break,
         };
         // dbg!("field_ident: {:?}", field_ident);
+        let field_annotations = format!(
+            "since: {}, vendors: &[{}]",
+            since.take().unwrap_or_else(|| String::from("None")),
+            vendors
+                .drain(..)
+                .map(|vendor| format!("bit_fields::Vendor::{vendor}"))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        // An array field has its number of elements in brackets following its identifier e.g.
+        // `descriptors[4]: 0..32 step 8`.
+        let array_len = match fields_iter.peek() {
+            Some(TokenTree::Group(len_group)) if len_group.delimiter() == Delimiter::Bracket => {
+                let len = match len_group.stream().into_iter().next() {
+                    Some(TokenTree::Literal(len)) => match len.to_string().parse::<u8>() {
+                        Ok(len) if len > 0 => len,
+                        _ => return diagnostic(len.span(), "Array length must be a non-zero u8"),
+                    },
+                    _ => return diagnostic(len_group.span(), "Array length missing"),
+                };
+                fields_iter.next();
+                Some(len)
+            }
+            _ => None,
+        };
         // Punct,Literal,Punct,Punct,Literal == Range
+        // Punct,Literal,Punct,Punct,Literal,Ident,Literal == Array
         // Punct,Literal,Punct,Literal
         let field_start_pos = match fields_iter.nth(1) {
             Some(TokenTree::Literal(field_start)) => {
@@ -238,18 +433,47 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
         let mut add_bit_flags = || {
             // Set display string
             let start = field_start_pos.to_string().parse::<u8>().unwrap();
+            fields.push(FieldBits {
+                ident: field_ident.to_string(),
+                start,
+                end: start + 1,
+                range: false,
+            });
+            write!(
+                &mut fields_definitions,
+                "bit_fields::Field {{ ident: \"{field_ident}\", start: {start}, end: {}, kind: \
+                 bit_fields::FieldKind::Flag, {field_annotations} }},",
+                start + 1
+            )
+            .unwrap();
+            write!(&mut map_values, "(\"{field_ident}\", {start}, 1),").unwrap();
+            map_values_len += 1;
+            let mask = mask_ident(&field_ident.to_string());
+            write!(
+                &mut fields_const_fns,
+                "
+                /// Mask of the bit of `{field_ident}`.
+                pub const {mask}: {struct_data_type} = {:#x};
+                /// Returns the value of `{field_ident}`.
+                pub const fn {field_ident}(&self) -> bool {{
+                    self.data & Self::{mask} != 0
+                }}
+            ",
+                1u128 << start
+            )
+            .unwrap();
             let more = start < bits_len - 1;
             let cropped = field_ident.to_string().chars().take(4).collect::<String>();
             let border = "───────";
             display_string[0].push_str(border);
             display_string[0].push(if more { '┬' } else { '┐' });
-            write!(&mut display_string[1], "    {start:02} │",).unwrap();
+            write!(&mut display_string[1], "    {start:02} │").unwrap();
             display_string[2].push_str(border);
             display_string[2].push(if more { '┼' } else { '┤' });
             write!(&mut display_string[3], " {cropped:>5} │").unwrap();
             display_string[4].push_str(border);
             display_string[4].push(if more { '┼' } else { '┤' });
-            write!(&mut display_string[5], " {{:>5}} │",).unwrap();
+            write!(&mut display_string[5], " {{:>5}} │").unwrap();
             display_string[6].push_str(border);
             display_string[6].push(if more { '┴' } else { '┘' });
             write!(&mut display_string[7], "self.{field_ident}.to_string(),").unwrap();
@@ -263,8 +487,7 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
 
             writeln!(
                 &mut struct_doc_table_layout,
-                "///     <tr><td>{start:02}</td><td>{}</td><td>{}</td></tr>",
-                field_ident, rustdoc
+                "///     <tr><td>{start:02}</td><td>{field_ident}</td><td>{rustdoc}</td></tr>"
             )
             .unwrap();
             rustdoc.clear();
@@ -337,88 +560,273 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
             )
             .unwrap();
         };
+        // The policy comparing the field in `supports`, `None` when the field is ignored
+        let policy;
+        // The identifiers and value expressions (with `{0}` in place of the bit field) compared by
+        // the policy
+        let mut supports_values = Vec::new();
         // To check whether the field is a bit flag or bit field we check if the next token is `.`
         // (which indicates a range)
-        match fields_iter.peek() {
+        match (fields_iter.peek(), array_len) {
+            // The bit range array case
+            (Some(TokenTree::Punct(punct)), Some(len)) if punct.as_char() == '.' => {
+                // Skip what we already checked by peeking
+                fields_iter.next();
+                let (end, step) = match (
+                    fields_iter.next(),
+                    fields_iter.next(),
+                    fields_iter.next(),
+                    fields_iter.next(),
+                ) {
+                    (
+                        Some(TokenTree::Punct(punct2)),
+                        Some(TokenTree::Literal(field_end_pos)),
+                        Some(TokenTree::Ident(step_ident)),
+                        Some(TokenTree::Literal(field_step)),
+                    ) if punct2.as_char() == '.' && step_ident.to_string() == "step" => (
+                        field_end_pos.to_string().parse::<u8>().unwrap(),
+                        field_step.to_string().parse::<u8>().unwrap(),
+                    ),
+                    _ => return diagnostic(field_ident.span(), ARRAY_ERR),
+                };
+                policy = parse_policy(&mut fields_iter, None);
+                let start = field_start_pos.to_string().parse::<u8>().unwrap();
+                if end < start {
+                    return diagnostic(field_ident.span(), "end < start");
+                }
+                if end > bits_len {
+                    return diagnostic(field_ident.span(), "end > bits_len");
+                }
+                if u16::from(end - start) != u16::from(len) * u16::from(step) {
+                    return diagnostic(field_ident.span(), "end - start != length * step");
+                }
+                // Elements cover all bits up to `end`
+                pos = end;
+                fields.push(FieldBits {
+                    ident: field_ident.to_string(),
+                    start,
+                    end,
+                    range: false,
+                });
+                write!(
+                    &mut fields_definitions,
+                    "bit_fields::Field {{ ident: \"{field_ident}\", start: {start}, end: {end}, \
+                     kind: bit_fields::FieldKind::Array {{ len: {len}, step: {step} }}, \
+                     {field_annotations} }},"
+                )
+                .unwrap();
+                for i in 0..len {
+                    write!(
+                        &mut map_values,
+                        "(\"{field_ident}[{i}]\", {}, {}),",
+                        start + i * step,
+                        max_value(step)
+                    )
+                    .unwrap();
+                    map_values_len += 1;
+                }
+                let mask = mask_ident(&field_ident.to_string());
+                write!(
+                    &mut fields_const_fns,
+                    "
+                    /// Mask of the bits of `{field_ident}`.
+                    pub const {mask}: {struct_data_type} = {:#x};
+                    /// Returns the value of the element of `{field_ident}` at `index`, `None` if \
+                     `index` is out of bounds.
+                    pub const fn {field_ident}(&self, index: usize) -> Option<{struct_data_type}> {{
+                        if index < {len} {{
+                            Some((self.data >> ({start} + index * {step})) & {:#x})
+                        }} else {{
+                            None
+                        }}
+                    }}
+                ",
+                    max_value(end - start) << start,
+                    max_value(step)
+                )
+                .unwrap();
+
+                // Set display string, with a column for each element
+                for i in 0..len {
+                    let more = i + 1 < len || fields_iter.peek().is_some();
+                    display_range(
+                        &mut display_string,
+                        start + i * step,
+                        start + (i + 1) * step,
+                        &format!("{field_ident}[{i}]"),
+                        &format!("self.{field_ident}.get({i}).unwrap().to_string(),"),
+                        more,
+                    );
+                }
+
+                // Add bit range array implementations
+                let type_str =
+                    format!("bit_fields::BitRangeArray<{struct_data_type},{start},{step},{len}>");
+                writeln!(
+                    &mut struct_member_fields,
+                    "/// {rustdoc}\npub {field_ident}: {type_str},"
+                )
+                .unwrap();
+
+                writeln!(
+                    &mut struct_doc_table_layout,
+                    "///     <tr><td>{:02}..={:02}</td><td>{field_ident}[{len}]</td><td>{rustdoc}</td></tr>",
+                    start,
+                    end - 1,
+                )
+                .unwrap();
+                rustdoc.clear();
+
+                writeln!(
+                    &mut struct_member_fields_initialization,
+                    "{field_ident}: bit_fields::BitRangeArray(std::marker::PhantomData),"
+                )
+                .unwrap();
+
+                write!(&mut struct_bit_range_definitions, "{type_str},").unwrap();
+
+                for i in 0..len {
+                    supports_values.push((
+                        format!("{field_ident}[{i}]"),
+                        format!("{{0}}.{field_ident}.get({i}).unwrap()"),
+                    ));
+                }
+            }
+            (_, Some(_)) => return diagnostic(field_ident.span(), ARRAY_ERR),
             // The bit range case
-            Some(TokenTree::Punct(punct)) if punct.as_char() == '.' => {
+            (Some(TokenTree::Punct(punct)), None) if punct.as_char() == '.' => {
                 // Skip what we already checked by peeking
                 fields_iter.next();
                 match (fields_iter.next(), fields_iter.next()) {
-                    (Some(TokenTree::Punct(punct2)), Some(TokenTree::Literal(field_end_pos))) => {
-                        if punct2.as_char() == '.' {
-                            let start = field_start_pos.to_string().parse::<u8>().unwrap();
-                            let end = field_end_pos.to_string().parse::<u8>().unwrap();
-                            if end < start {
-                                return diagnostic(field_ident.span(), "end < start");
-                            }
-                            if end > bits_len {
-                                return diagnostic(field_ident.span(), "end > bits_len");
-                            }
-
-                            // Set display string
-                            // TODO With 1 bitrange defined in struct, print will not work
-                            // correctly, fix that.
-                            let more = fields_iter.peek().is_some();
-                            let cropped =
-                                field_ident.to_string().chars().take(10).collect::<String>();
-                            let border = "────────────";
-                            display_string[0].push_str(border);
-                            display_string[0].push(if more { '┬' } else { '┐' });
-                            write!(
-                                &mut display_string[1],
-                                "    {:02}..={:02} │",
-                                start,
-                                end - 1
-                            )
-                            .unwrap();
-                            display_string[2].push_str(border);
-                            display_string[2].push(if more { '┼' } else { '┤' });
-                            write!(&mut display_string[3], " {cropped:>10} │").unwrap();
-                            display_string[4].push_str(border);
-                            display_string[4].push(if more { '┼' } else { '┤' });
-                            write!(&mut display_string[5], " {{:>10}} │",).unwrap();
-                            display_string[6].push_str(border);
-                            display_string[6].push(if more { '┴' } else { '┘' });
-                            write!(&mut display_string[7], "self.{field_ident}.to_string(),")
-                                .unwrap();
-
-                            // Add bit range implementations
-                            let type_str =
-                                format!("bit_fields::BitRange<{struct_data_type},{start},{end}>");
-                            writeln!(
-                                &mut struct_member_fields,
-                                "/// {rustdoc}\npub {field_ident}: {type_str},"
-                            )
-                            .unwrap();
-
-                            writeln!(
-                                &mut struct_doc_table_layout,
-                                "///     <tr><td>{:02}..={:02}</td><td>{}</td><td>{}</td></tr>",
-                                start,
-                                end - 1,
-                                field_ident,
-                                rustdoc
-                            )
-                            .unwrap();
-                            rustdoc.clear();
-
-                            writeln!(
-                                &mut struct_member_fields_initialization,
-                                "{field_ident}: bit_fields::BitRange(std::marker::PhantomData),"
-                            )
-                            .unwrap();
-
-                            write!(&mut struct_bit_range_definitions, "{type_str},").unwrap();
+                    (Some(TokenTree::Punct(punct2)), Some(TokenTree::Literal(field_end_pos)))
+                        if punct2.as_char() == '.' =>
+                    {
+                        let start = field_start_pos.to_string().parse::<u8>().unwrap();
+                        let end = field_end_pos.to_string().parse::<u8>().unwrap();
+                        policy = parse_policy(&mut fields_iter, None);
+                        if end < start {
+                            return diagnostic(field_ident.span(), "end < start");
+                        }
+                        if end > bits_len {
+                            return diagnostic(field_ident.span(), "end > bits_len");
                         }
+                        fields.push(FieldBits {
+                            ident: field_ident.to_string(),
+                            start,
+                            end,
+                            range: true,
+                        });
+                        write!(
+                            &mut fields_definitions,
+                            "bit_fields::Field {{ ident: \"{field_ident}\", start: {start}, end: \
+                             {end}, kind: bit_fields::FieldKind::Range, {field_annotations} }},"
+                        )
+                        .unwrap();
+                        write!(
+                            &mut map_values,
+                            "(\"{field_ident}\", {start}, {}),",
+                            max_value(end - start)
+                        )
+                        .unwrap();
+                        map_values_len += 1;
+                        let mask = mask_ident(&field_ident.to_string());
+                        write!(
+                            &mut fields_const_fns,
+                            "
+                            /// Mask of the bits of `{field_ident}`.
+                            pub const {mask}: {struct_data_type} = {:#x};
+                            /// Returns the value of `{field_ident}`.
+                            pub const fn {field_ident}(&self) -> {struct_data_type} {{
+                                (self.data & Self::{mask}) >> {start}
+                            }}
+                        ",
+                            max_value(end - start) << start
+                        )
+                        .unwrap();
+
+                        // Set display string
+                        // TODO With 1 bitrange defined in struct, print will not work
+                        // correctly, fix that.
+                        display_range(
+                            &mut display_string,
+                            start,
+                            end,
+                            &field_ident.to_string(),
+                            &format!("self.{field_ident}.to_string(),"),
+                            fields_iter.peek().is_some(),
+                        );
+
+                        // Add bit range implementations
+                        let type_str =
+                            format!("bit_fields::BitRange<{struct_data_type},{start},{end}>");
+                        writeln!(
+                            &mut struct_member_fields,
+                            "/// {rustdoc}\npub {field_ident}: {type_str},"
+                        )
+                        .unwrap();
+
+                        writeln!(
+                            &mut struct_doc_table_layout,
+                            "///     <tr><td>{:02}..={:02}</td><td>{}</td><td>{}</td></tr>",
+                            start,
+                            end - 1,
+                            field_ident,
+                            rustdoc
+                        )
+                        .unwrap();
+                        rustdoc.clear();
+
+                        writeln!(
+                            &mut struct_member_fields_initialization,
+                            "{field_ident}: bit_fields::BitRange(std::marker::PhantomData),"
+                        )
+                        .unwrap();
+
+                        write!(&mut struct_bit_range_definitions, "{type_str},").unwrap();
+
+                        supports_values.push((
+                            field_ident.to_string(),
+                            format!("{struct_data_type}::from(&{{0}}.{field_ident})"),
+                        ));
                     }
                     _ => return diagnostic(field_ident.span(), "Bit range badly formed"),
                 }
             }
             // The bit flag case
-            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => add_bit_flags(),
-            None => add_bit_flags(),
-            _ => return diagnostic(field_ident.span(), FIELDS_ERR),
+            (_, None) => {
+                policy = parse_policy(&mut fields_iter, Some("Superset"));
+                add_bit_flags();
+                supports_values.push((
+                    field_ident.to_string(),
+                    format!("bool::from(&{{0}}.{field_ident})"),
+                ));
+            }
+        }
+        // Add the comparisons of the field's values
+        if let Some(policy) = policy {
+            for (name, value) in supports_values {
+                let value = |side: &str| {
+                    let value = value.replace("{0}", side);
+                    if struct_data_type == "u128" && !value.starts_with("bool") {
+                        value
+                    } else {
+                        format!("u128::from({value})")
+                    }
+                };
+                write!(
+                    &mut fields_supports_fn,
+                    "bit_fields::Policy::{policy}.check(\"{name}\",{},{}),",
+                    value("self"),
+                    value("other")
+                )
+                .unwrap();
+                fields_supports_len += 1;
+            }
+        }
+        match fields_iter.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            None => {}
+            Some(other) => return diagnostic(other.span(), FIELDS_ERR),
         }
         // We skip the punctuation for the next iteration.
         fields_iter.next();
@@ -450,10 +858,13 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
         /// </table>
         #[cfg_attr(feature = \"serde\", derive(serde::Serialize,serde::Deserialize))]
         #[derive(Clone)]
+        // The zero-sized members access `data` by casting their own address, so they must all sit
+        // at offset 0, which `repr(C)` only guarantees when they precede `data`.
+        #[repr(C)]
         pub struct {struct_name} {{
-            pub data: {struct_data_type},
             pub bits: ({struct_bits}),
             {struct_member_fields}
+            pub data: {struct_data_type},
         }}
         
         // We cannot derive [`std::fmt::Debug`] as `self.bits` has too many elements.
@@ -469,35 +880,68 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
                 write!(f,\"{display_full_string}\",{display_full_string_fmt_values})
             }}
         }}
+        // Bit fields are equal when their underlying data is equal.
+        impl std::cmp::PartialEq for {struct_name} {{
+            fn eq(&self, other: &Self) -> bool {{
+                self.data == other.data
+            }}
+        }}
+        impl std::cmp::Eq for {struct_name} {{}}
         impl std::fmt::Binary for {struct_name} {{
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{
                 std::fmt::Binary::fmt(&self.data, f)
             }}
         }}
-        impl<T:std::fmt::Display> std::convert::TryFrom<std::collections::HashSet<T>> for {struct_name} {{
-            type Error = &'static str;
-            fn try_from(set: std::collections::HashSet<T>) -> Result<Self,Self::Error> {{
-                let mut base = Self::from(0);
-                for key in set.into_iter() {{
-                    match key.to_string().as_str() {{
-                        {field_matching_from_hashset}
-                        _ => return Err(\"Non-specified flag found in given set\")
-                    }};
+        {hashset_conversions}
+        impl std::convert::TryFrom<std::collections::HashMap<String, u128>> for {struct_name} {{
+            type Error = bit_fields::FromMapErr;
+            fn try_from(mut map: std::collections::HashMap<String, u128>) -> Result<Self, Self::Error> {{
+                let values: [(&'static str, u8, u128); {map_values_len}] = [{map_values}];
+                let mut data = 0u128;
+                for (key, start, max) in values {{
+                    let value = map.remove(key).ok_or(bit_fields::FromMapErr::MissingField(key))?;
+                    if value > max {{
+                        return Err(bit_fields::FromMapErr::Overflow {{ field: key, value }});
+                    }}
+                    data |= value << start;
                 }}
-                Ok(base)
+                if let Some(key) = map.into_keys().next() {{
+                    return Err(bit_fields::FromMapErr::UnknownField(key));
+                }}
+                Ok(Self::from({map_data}))
+            }}
+        }}
+        impl std::convert::From<{struct_name}> for std::collections::HashMap<String, u128> {{
+            fn from(bit_field: {struct_name}) -> Self {{
+                let values: [(&'static str, u8, u128); {map_values_len}] = [{map_values}];
+                let data = {bit_field_data};
+                values
+                    .into_iter()
+                    .map(|(key, start, max)| (String::from(key), (data >> start) & max))
+                    .collect()
             }}
         }}
-        
-        {into_hashset}
         /// Constructs `self` with the given internal value.
         impl std::convert::From<{struct_data_type}> for {struct_name} {{
             fn from(data: {struct_data_type}) -> Self {{
+                Self::from_bits(data)
+            }}
+        }}
+        #[allow(non_snake_case)]
+        impl {struct_name} {{
+            /// Constructs `self` with the given internal value.
+            pub const fn from_bits(data: {struct_data_type}) -> Self {{
                 Self {{
                     data,
                     bits: ({struct_new_bits}),
                     {struct_member_fields_initialization}
                 }}
             }}
+            /// Returns the internal value.
+            pub const fn bits(&self) -> {struct_data_type} {{
+                self.data
+            }}
+            {fields_const_fns}
         }}
         impl {struct_name} {{
             
@@ -530,6 +974,22 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
                 base
             }}
             
+            /// Returns if `self` supports `other`, that is if every field of `self` satisfies its
+            /// comparison policy against the same field of `other`.
+            ///
+            /// # Errors
+            ///
+            /// When any fields do not satisfy their policies, returning these fields.
+            pub fn supports(&self, other: &Self) -> Result<(), Vec<bit_fields::FieldMismatch>> {{
+                let checks: [Option<bit_fields::FieldMismatch>; {fields_supports_len}] = [{fields_supports_fn}];
+                let mismatches = checks.into_iter().flatten().collect::<Vec<_>>();
+                if mismatches.is_empty() {{
+                    Ok(())
+                }} else {{
+                    Err(mismatches)
+                }}
+            }}
+
             /// Returns a reference to the `N`th bit.
             pub fn bit<const N: u8>(&self) -> &bit_fields::Bit<{struct_data_type},N>
             where
@@ -545,8 +1005,62 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
                 <Self as bit_fields::BitIndexMut<{struct_data_type},N>>::bit_mut(self)
             }}
         }}
+        impl bit_fields::BitField for {struct_name} {{
+            type Storage = {struct_data_type};
+            const BITS: u8 = {bits_len};
+            const FIELDS: &'static [bit_fields::Field] = &[{fields_definitions}];
+            fn from_storage(storage: {struct_data_type}) -> Self {{
+                Self::from(storage)
+            }}
+            fn to_storage(&self) -> {struct_data_type} {{
+                self.data
+            }}
+            fn superset(&self, other: &Self) -> bool {{
+                {struct_name}::superset(self, other)
+            }}
+            fn subset(&self, other: &Self) -> bool {{
+                {struct_name}::subset(self, other)
+            }}
+            fn disjoint(&self, other: &Self) -> bool {{
+                {struct_name}::disjoint(self, other)
+            }}
+            fn intersection(&self, other: &Self) -> Self {{
+                {struct_name}::intersection(self, other)
+            }}
+            fn union(&self, other: &Self) -> Self {{
+                {struct_name}::union(self, other)
+            }}
+            fn supports(&self, other: &Self) -> Result<(), Vec<bit_fields::FieldMismatch>> {{
+                {struct_name}::supports(self, other)
+            }}
+        }}
+        {arbitrary}
         {bit_index}
-        ", into_hashset = if struct_bit_range_definitions.is_empty() { format!("
+        ", arbitrary = arbitrary(struct_name, struct_data_type), map_data = if struct_data_type == "u128" {
+            String::from("data")
+        } else {
+            format!("{struct_data_type}::try_from(data).unwrap()")
+        }, bit_field_data = if struct_data_type == "u128" {
+            String::from("bit_field.data")
+        } else {
+            String::from("u128::from(bit_field.data)")
+        }, hashset_conversions = if struct_bit_range_definitions.is_empty() {
+            // A set of flags cannot hold the values of range or array fields, so bit fields with
+            // them only convert with `HashMap<String, u128>`.
+            format!("
+            impl<T:std::fmt::Display> std::convert::TryFrom<std::collections::HashSet<T>> for {struct_name} {{
+                type Error = &'static str;
+                fn try_from(set: std::collections::HashSet<T>) -> Result<Self,Self::Error> {{
+                    let mut base = Self::from(0);
+                    for key in set.into_iter() {{
+                        match key.to_string().as_str() {{
+                            {field_matching_from_hashset}
+                            _ => return Err(\"Non-specified flag found in given set\")
+                        }};
+                    }}
+                    Ok(base)
+                }}
+            }}
             // TODO Make this into a `From` implementation
             #[allow(clippy::from_over_into)]
             impl std::convert::Into<std::collections::HashSet<String>> for {struct_name} {{
@@ -557,14 +1071,306 @@ pub fn bitfield(item: TokenStream) -> TokenStream {
                 }}
             }}
         ")} else { String::new() },display_full_string = {
-            display_string.into_iter().map(|s|format!("\n{}",s)).collect::<String>()
+            display_string.into_iter().fold(String::new(), |mut acc, s| {
+                write!(acc, "\n{s}").unwrap();
+                acc
+            })
         }
     );
-    // eprintln!("layout: {}", layout);
-    // "fn answer() -> u32 { 42 }".parse().unwrap()
-    layout.parse().unwrap()
+    (layout, fields)
+}
+/// Generates the layouts selected by the bit range `discriminant` of `struct_name`, with a
+/// `{struct_name}Layout` enum to match against and conversions between the two.
+#[allow(clippy::too_many_lines)]
+fn bitfield_variants(
+    struct_name: &str,
+    struct_data_type: &str,
+    fields: &[FieldBits],
+    discriminant: &Ident,
+    variants: &Group,
+) -> String {
+    const VARIANT_ERR: &str = "Layouts must be a discriminant value followed by `=>` and an \
+                               identifier, optionally followed by a group of bit flags and bit \
+                               ranges. e.g. `1 => Valid { flag: 4 }`";
+
+    let discriminant_bits = match fields
+        .iter()
+        .find(|field| field.ident == discriminant.to_string())
+    {
+        Some(field) if field.range => field,
+        Some(_) => return diagnostic(discriminant.span(), "Discriminant must be a bit range"),
+        None => return diagnostic(discriminant.span(), "Discriminant not found"),
+    };
+    let discriminant_len = discriminant_bits.end - discriminant_bits.start;
+    let discriminant_mask = max_value(discriminant_len) << discriminant_bits.start;
+
+    let mut layout_structs = String::new();
+    let mut layout_variants = String::new();
+    let mut layout_from_arms = String::new();
+    let mut from_layout_arms = String::new();
+
+    let mut variants_iter = variants.stream().into_iter().peekable();
+    let mut pre_existing_values = HashSet::new();
+    let mut pre_existing_idents = HashSet::new();
+    let mut rustdoc = String::new();
+    loop {
+        let value = match variants_iter.next() {
+            Some(TokenTree::Punct(doc_comment_punct)) if doc_comment_punct.as_char() == '#' => {
+                if let Some(TokenTree::Group(doc_group)) = variants_iter.next() {
+                    rustdoc.push_str(&rustdoc_comment(&doc_group));
+                    rustdoc.push(' ');
+                    continue;
+                }
+                return diagnostic(
+                    doc_comment_punct.span(),
+                    "expected rustdoc comment following `#`",
+                );
+            }
+            Some(TokenTree::Literal(value)) => {
+                let Some(x) = parse_literal(&value.to_string()) else {
+                    return diagnostic(value.span(), "Discriminant value must be an integer");
+                };
+                // If value is not storable in the discriminant
+                if x > discriminant_mask >> discriminant_bits.start {
+                    return diagnostic(value.span(), "Discriminant value out of range");
+                }
+                // If value already used
+                if !pre_existing_values.insert(x) {
+                    return diagnostic(value.span(), "Discriminant value already used");
+                }
+                x
+            }
+            Some(wrong_value) => return diagnostic(wrong_value.span(), VARIANT_ERR),
+            None => break,
+        };
+        let variant_ident = match (
+            variants_iter.next(),
+            variants_iter.next(),
+            variants_iter.next(),
+        ) {
+            (
+                Some(TokenTree::Punct(eq)),
+                Some(TokenTree::Punct(gt)),
+                Some(TokenTree::Ident(variant_ident)),
+            ) if eq.as_char() == '=' && gt.as_char() == '>' => variant_ident,
+            _ => return diagnostic(discriminant.span(), VARIANT_ERR),
+        };
+        // If ident already used
+        if !pre_existing_idents.insert(variant_ident.to_string()) {
+            return diagnostic(variant_ident.span(), "Identifier already used");
+        }
+
+        // A layout without fields still generates a structure, so converting it back keeps the
+        // bits outside the discriminant.
+        let variant_fields = match variants_iter.peek() {
+            // The layout with fields case
+            Some(TokenTree::Group(variant_fields))
+                if variant_fields.delimiter() != Delimiter::None =>
+            {
+                let variant_fields = variant_fields.clone();
+                variants_iter.next();
+                variant_fields
+            }
+            // The layout without fields case
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {
+                Group::new(Delimiter::Brace, TokenStream::new())
+            }
+            None => Group::new(Delimiter::Brace, TokenStream::new()),
+            _ => return diagnostic(variant_ident.span(), VARIANT_ERR),
+        };
+        let layout_struct_name = format!("{struct_name}{variant_ident}");
+        let (layout_struct, layout_fields) =
+            bitfield_struct(&layout_struct_name, struct_data_type, &variant_fields);
+        // If a field overlaps the discriminant
+        if let Some(field) = layout_fields.iter().find(|field| {
+            field.start < discriminant_bits.end && discriminant_bits.start < field.end
+        }) {
+            return diagnostic(
+                variant_fields.span(),
+                &format!("`{}` overlaps discriminant", field.ident),
+            );
+        }
+        layout_structs.push_str(&layout_struct);
+        writeln!(
+            &mut layout_variants,
+            "/// {rustdoc}\n{variant_ident}({layout_struct_name}),"
+        )
+        .unwrap();
+        writeln!(
+            &mut layout_from_arms,
+            "{value} => Ok({struct_name}Layout::{variant_ident}({layout_struct_name}::from(self.data))),"
+        )
+        .unwrap();
+        writeln!(
+            &mut from_layout_arms,
+            "{struct_name}Layout::{variant_ident}(layout) => (layout.data, {value}),"
+        )
+        .unwrap();
+        rustdoc.clear();
+        // We skip the punctuation for the next iteration.
+        variants_iter.next();
+    }
+
+    format!(
+        "
+        {layout_structs}
+        /// The layouts of [`{struct_name}`] selected by [`{struct_name}::{discriminant}`].
+        #[derive(Debug, Clone)]
+        pub enum {struct_name}Layout {{
+            {layout_variants}
+        }}
+        impl {struct_name} {{
+            /// Returns the layout of `self` selected by [`Self::{discriminant}`].
+            ///
+            /// # Errors
+            ///
+            /// When [`Self::{discriminant}`] does not select any known layout.
+            pub fn layout(&self) -> Result<{struct_name}Layout, bit_fields::UnknownDiscriminantErr<{struct_data_type}>> {{
+                match {struct_data_type}::from(&self.{discriminant}) {{
+                    {layout_from_arms}
+                    unknown => Err(bit_fields::UnknownDiscriminantErr(unknown)),
+                }}
+            }}
+        }}
+        /// Constructs `self` from the given layout, setting [`{struct_name}::{discriminant}`] to the
+        /// discriminant of the layout.
+        impl std::convert::From<{struct_name}Layout> for {struct_name} {{
+            fn from(layout: {struct_name}Layout) -> Self {{
+                let (data, discriminant): ({struct_data_type}, {struct_data_type}) = match layout {{
+                    {from_layout_arms}
+                }};
+                Self::from((data & !{discriminant_mask}) | (discriminant << {discriminant_start}))
+            }}
+        }}
+        ",
+        discriminant_start = discriminant_bits.start,
+    )
+}
+/// Parses an integer literal e.g. `12`, `0xC`, `0b1100` or `0b_1100_u8`.
+fn parse_literal(literal: &str) -> Option<u128> {
+    let literal = literal.replace('_', "");
+    // Remove any type suffix e.g. `u8`
+    let literal = literal.split(['u', 'i']).next()?;
+    let (digits, radix) = if let Some(hex) = literal.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(bin) = literal.strip_prefix("0b") {
+        (bin, 2)
+    } else if let Some(oct) = literal.strip_prefix("0o") {
+        (oct, 8)
+    } else {
+        (literal, 10)
+    };
+    u128::from_str_radix(digits, radix).ok()
+}
+/// Adds a column for a bit range to the display table.
+fn display_range(
+    display_string: &mut [String],
+    start: u8,
+    end: u8,
+    ident: &str,
+    value: &str,
+    more: bool,
+) {
+    let cropped = ident.chars().take(10).collect::<String>();
+    let border = "────────────";
+    display_string[0].push_str(border);
+    display_string[0].push(if more { '┬' } else { '┐' });
+    write!(&mut display_string[1], "    {start:02}..={:02} │", end - 1).unwrap();
+    display_string[2].push_str(border);
+    display_string[2].push(if more { '┼' } else { '┤' });
+    write!(&mut display_string[3], " {cropped:>10} │").unwrap();
+    display_string[4].push_str(border);
+    display_string[4].push(if more { '┼' } else { '┤' });
+    display_string[5].push_str(" {:>10} │");
+    display_string[6].push_str(border);
+    display_string[6].push(if more { '┴' } else { '┘' });
+    display_string[7].push_str(value);
+}
+/// An attribute of a field, parsed by [`field_attribute`].
+enum FieldAttribute {
+    /// A rustdoc comment.
+    Doc,
+    /// The specification revision which introduced the field e.g. `#[since("SDM-079")]`, as a
+    /// string literal.
+    Since(String),
+    /// The vendors defining the field e.g. `#[vendor(intel|amd)]`, as `bit_fields::Vendor`
+    /// variants.
+    Vendor(Vec<&'static str>),
+}
+
+/// Parses the group following `#` on a field.
+fn field_attribute(attribute_group: &Group) -> FieldAttribute {
+    const SINCE_ERR: &str = "`since` must be followed by a string literal e.g. \
+                             `#[since(\"SDM-079\")]`";
+    const VENDOR_ERR: &str = "`vendor` must be followed by vendors separated by `|` e.g. \
+                              `#[vendor(intel|amd)]`";
+
+    let mut attribute_iter = attribute_group.stream().into_iter();
+    let Some(TokenTree::Ident(ident)) = attribute_iter.next() else {
+        return FieldAttribute::Doc;
+    };
+    let arguments = match (ident.to_string().as_str(), attribute_iter.next()) {
+        ("since" | "vendor", Some(TokenTree::Group(arguments)))
+            if arguments.delimiter() == Delimiter::Parenthesis =>
+        {
+            arguments
+        }
+        ("since", _) => return diagnostic(ident.span(), SINCE_ERR),
+        ("vendor", _) => return diagnostic(ident.span(), VENDOR_ERR),
+        _ => return FieldAttribute::Doc,
+    };
+    if ident.to_string() == "since" {
+        let mut arguments_iter = arguments.stream().into_iter();
+        match (arguments_iter.next(), arguments_iter.next()) {
+            (Some(TokenTree::Literal(revision)), None) if revision.to_string().starts_with('"') => {
+                FieldAttribute::Since(format!("Some({revision})"))
+            }
+            _ => diagnostic(arguments.span(), SINCE_ERR),
+        }
+    } else {
+        let mut vendors = Vec::new();
+        for (i, token) in arguments.stream().into_iter().enumerate() {
+            match token {
+                TokenTree::Ident(vendor) if i % 2 == 0 => {
+                    vendors.push(match vendor.to_string().as_str() {
+                        "intel" => "Intel",
+                        "amd" => "Amd",
+                        _ => return diagnostic(vendor.span(), VENDOR_ERR),
+                    });
+                }
+                TokenTree::Punct(separator) if i % 2 == 1 && separator.as_char() == '|' => {}
+                other => return diagnostic(other.span(), VENDOR_ERR),
+            }
+        }
+        if vendors.is_empty() {
+            return diagnostic(arguments.span(), VENDOR_ERR);
+        }
+        FieldAttribute::Vendor(vendors)
+    }
+}
+
+/// Returns the text of a rustdoc comment from its `#` group e.g. `[doc = " abcde"]` produces
+/// `abcde`.
+fn rustdoc_comment(doc_group: &Group) -> String {
+    if let Some(TokenTree::Literal(doc_comment_comment)) = doc_group.stream().into_iter().nth(2) {
+        // dbg!("doc_comment_comment: {:?}", doc_comment_comment);
+        let temp = doc_comment_comment.to_string();
+        // Remove " from start and end (TODO Do this better)
+        let temp = temp
+            .chars()
+            .skip(1)
+            .take(temp.len() - 2)
+            .collect::<String>();
+        // Trim space of front e.g. `/// abcde` produces `" abcde"` and we want
+        // `abcde`
+        return temp.trim_start().to_string();
+    }
+    diagnostic(
+        doc_group.span(),
+        "expected rustdoc comment within `#` group",
+    )
 }
-fn diagnostic(_span: proc_macro::Span, message: &str) -> proc_macro::TokenStream {
+fn diagnostic<T>(_span: proc_macro::Span, message: &str) -> T {
     // It is preferable to use`proc_macro::Diagnostic` we should switch this when
     // `proc_macro::Diagnostic` is stabilized.
     // proc_macro::Diagnostic::spanned($span, proc_macro:: $message).emit();
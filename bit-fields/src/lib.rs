@@ -12,14 +12,20 @@
 //!     SSE4, 18
 //! ]);
 //! ```
-//! When `#![feature(const_mut_refs)]` is stabilized all functions under `impl Type { ... }` can
-//! become `const fn`s.
+//! Bit fields can be constructed and read in `const` contexts through the generated `from_bits`,
+//! `bits` and per field getters and masks (e.g. `ExampleBitField::SSE_MASK`). When
+//! `#![feature(const_mut_refs)]` is stabilized all functions under `impl Type { ... }` can become
+//! `const fn`s.
 #![warn(clippy::pedantic)]
 use std::cmp::{Ord, Ordering, PartialOrd};
 use std::fmt;
 use std::marker::PhantomData;
 
 pub use bit_fields_macros::*;
+#[cfg(feature = "proptest")]
+pub use proptest;
+#[cfg(feature = "quickcheck")]
+pub use quickcheck;
 
 pub trait BitIndex<T, const P: u8> {
     fn bit(&self) -> &Bit<T, P>;
@@ -28,6 +34,216 @@ pub trait BitIndexMut<T, const P: u8> {
     fn bit_mut(&mut self) -> &mut Bit<T, P>;
 }
 
+/// The kind of a field within a bit field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// A single bit, a [`Bit`].
+    Flag,
+    /// A range of bits, a [`BitRange`].
+    Range,
+    /// A number of adjacent equally sized ranges of bits, a [`BitRangeArray`].
+    Array {
+        /// The number of elements.
+        len: u8,
+        /// The number of bits in each element.
+        step: u8,
+    },
+}
+/// A CPU vendor, which may define a field differently to other vendors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Vendor {
+    /// Intel.
+    Intel,
+    /// AMD.
+    Amd,
+}
+impl fmt::Display for Vendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Intel => write!(f, "Intel"),
+            Self::Amd => write!(f, "AMD"),
+        }
+    }
+}
+/// A description of a field within a bit field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    /// Identifier of the field.
+    pub ident: &'static str,
+    /// The first bit of the field.
+    pub start: u8,
+    /// The bit following the last bit of the field.
+    pub end: u8,
+    /// The kind of the field.
+    pub kind: FieldKind,
+    /// The specification revision which introduced the field e.g. `SDM-079`, given by
+    /// `#[since("SDM-079")]`.
+    pub since: Option<&'static str>,
+    /// The vendors defining the field, given by e.g. `#[vendor(intel)]`, empty when the field is
+    /// defined by all vendors.
+    pub vendors: &'static [Vendor],
+}
+impl Field {
+    /// Returns a mask of the bits covered by the field.
+    #[must_use]
+    pub const fn mask(&self) -> u128 {
+        let len = (self.end - self.start) as u32;
+        match u128::MAX.checked_shr(128 - len) {
+            Some(mask) => mask << self.start,
+            None => 0,
+        }
+    }
+
+    /// Returns if the field is defined by `vendor`.
+    #[must_use]
+    pub fn defined_by(&self, vendor: Vendor) -> bool {
+        self.vendors.is_empty() || self.vendors.contains(&vendor)
+    }
+}
+/// The interface shared by all bit fields generated by [`bitfield`].
+pub trait BitField: Sized {
+    /// The underlying data type.
+    type Storage: Copy + Eq + fmt::Binary + Into<u128> + TryFrom<u128>;
+    /// The number of bits in the underlying data type.
+    const BITS: u8;
+    /// The fields of the bit field, ordered by position.
+    const FIELDS: &'static [Field];
+    /// Constructs the bit field with the given underlying data.
+    fn from_storage(storage: Self::Storage) -> Self;
+    /// Returns the underlying data.
+    fn to_storage(&self) -> Self::Storage;
+    /// Returns if `self` is a [`superset`](https://en.wikipedia.org/wiki/Subset) of `other`.
+    fn superset(&self, other: &Self) -> bool;
+    /// Returns if `self` is a [`subset`](https://en.wikipedia.org/wiki/Subset) of `other`.
+    fn subset(&self, other: &Self) -> bool;
+    /// Returns if `self` and `other` are [`disjoint sets`](https://en.wikipedia.org/wiki/Disjoint_sets).
+    fn disjoint(&self, other: &Self) -> bool;
+    /// Returns the [`intersection`](https://en.wikipedia.org/wiki/Intersection_(set_theory)) of `self` and `other`.
+    #[must_use]
+    fn intersection(&self, other: &Self) -> Self;
+    /// Returns the [`union`](https://en.wikipedia.org/wiki/Union_(set_theory)) of `self` and `other`.
+    #[must_use]
+    fn union(&self, other: &Self) -> Self;
+    /// Returns if `self` supports `other` according to the comparison policies of its fields.
+    ///
+    /// # Errors
+    ///
+    /// When any fields do not satisfy their policies, returning these fields.
+    fn supports(&self, other: &Self) -> Result<(), Vec<FieldMismatch>>;
+    /// Returns a mask of the bits covered by the fields, the other bits are reserved.
+    #[must_use]
+    fn fields_mask() -> u128 {
+        Self::FIELDS
+            .iter()
+            .fold(0, |mask, field| mask | field.mask())
+    }
+    /// Returns the fields which are not zero.
+    fn set_fields(&self) -> Vec<&'static Field> {
+        let storage: u128 = self.to_storage().into();
+        Self::FIELDS
+            .iter()
+            .filter(|field| storage & field.mask() != 0)
+            .collect()
+    }
+    /// Returns the specification revisions which introduced the fields which are not zero, sorted
+    /// (numbers numerically, e.g. `SDM 9` before `SDM 10`) and deduplicated.
+    fn revisions(&self) -> Vec<&'static str> {
+        let mut revisions = self
+            .set_fields()
+            .into_iter()
+            .filter_map(|field| field.since)
+            .collect::<Vec<_>>();
+        revisions.sort_unstable_by_key(|revision| revision_key(revision));
+        revisions.dedup();
+        revisions
+    }
+    /// Returns the fields which are not zero and not defined by `vendor`.
+    fn vendor_mismatches(&self, vendor: Vendor) -> Vec<&'static Field> {
+        self.set_fields()
+            .into_iter()
+            .filter(|field| !field.defined_by(vendor))
+            .collect()
+    }
+}
+
+/// Returns the runs of non-digits of a specification revision each with the number following it,
+/// so revisions sort numerically e.g. `SDM-075` gives `[("SDM-", 75)]`.
+fn revision_key(revision: &str) -> Vec<(&str, u64)> {
+    let mut key = Vec::new();
+    let mut rest = revision;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (text, tail) = rest.split_at(digits);
+        let end = tail
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(tail.len());
+        let (number, tail) = tail.split_at(end);
+        key.push((text, number.parse().unwrap_or(u64::MAX)));
+        rest = tail;
+    }
+    key
+}
+
+/// Returns `storage` with the reserved bits of `T` cleared.
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+fn clear_reserved<T: BitField>(storage: T::Storage) -> T::Storage {
+    match T::Storage::try_from(storage.into() & T::fields_mask()) {
+        Ok(cleared) => cleared,
+        // Clearing bits cannot increase the value.
+        Err(_) => unreachable!(),
+    }
+}
+
+/// Options for generating arbitrary bit fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArbitraryOptions {
+    /// Whether the bits not covered by any field are kept zero.
+    pub reserved_zero: bool,
+}
+
+/// Returns a strategy generating arbitrary bit fields, used by the generated
+/// `proptest::arbitrary::Arbitrary` implementations.
+#[cfg(feature = "proptest")]
+pub fn arbitrary_strategy<T>(options: ArbitraryOptions) -> proptest::strategy::BoxedStrategy<T>
+where
+    T: BitField + fmt::Debug + 'static,
+    T::Storage: proptest::arbitrary::Arbitrary,
+{
+    use proptest::strategy::Strategy;
+
+    proptest::arbitrary::any::<T::Storage>()
+        .prop_map(move |storage| {
+            T::from_storage(if options.reserved_zero {
+                clear_reserved::<T>(storage)
+            } else {
+                storage
+            })
+        })
+        .boxed()
+}
+
+/// A bit field with all reserved bits zero, when generated by `quickcheck`.
+#[cfg(feature = "quickcheck")]
+#[derive(Debug, Clone)]
+pub struct ReservedZero<T>(pub T);
+#[cfg(feature = "quickcheck")]
+impl<T: BitField + quickcheck::Arbitrary> quickcheck::Arbitrary for ReservedZero<T> {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let bit_field = T::arbitrary(g);
+        Self(T::from_storage(clear_reserved::<T>(bit_field.to_storage())))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(
+            self.0.shrink().map(|bit_field| {
+                Self(T::from_storage(clear_reserved::<T>(bit_field.to_storage())))
+            }),
+        )
+    }
+}
+
 /// A type interface for a range of bits.
 #[derive(Debug, Clone, Copy)]
 pub struct BitRange<T, const START: u8, const END: u8>(pub PhantomData<T>);
@@ -36,31 +252,31 @@ pub struct BitRange<T, const START: u8, const END: u8>(pub PhantomData<T>);
 impl<const START: u8, const END: u8> fmt::Display for BitRange<u128, START, END> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: u128 = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 impl<const START: u8, const END: u8> fmt::Display for BitRange<u64, START, END> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: u64 = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 impl<const START: u8, const END: u8> fmt::Display for BitRange<u32, START, END> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: u32 = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 impl<const START: u8, const END: u8> fmt::Display for BitRange<u16, START, END> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: u16 = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 impl<const START: u8, const END: u8> fmt::Display for BitRange<u8, START, END> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: u8 = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 
@@ -98,12 +314,12 @@ impl<const START: u8, const END: u8> BitRange<u128, START, END> {
     const MAX: u128 = 2u128.pow((END - START) as u32);
 
     fn data(&self) -> *const u128 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u128>()
     }
 
     fn data_mut(&mut self) -> *mut u128 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u128>()
     }
 
@@ -162,7 +378,7 @@ impl<const START: u8, const END: u8> BitRange<u128, START, END> {
         if x < Self::MAX {
             let shift = x << START;
             unsafe {
-                *self.data_mut() = shift;
+                *self.data_mut() = (*self.data() & !Self::MASK) | shift;
             }
             Ok(())
         } else {
@@ -176,12 +392,12 @@ impl<const START: u8, const END: u8> BitRange<u64, START, END> {
     const MAX: u64 = 2u64.pow((END - START) as u32);
 
     fn data(&self) -> *const u64 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u64>()
     }
 
     fn data_mut(&mut self) -> *mut u64 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u64>()
     }
 
@@ -240,7 +456,7 @@ impl<const START: u8, const END: u8> BitRange<u64, START, END> {
         if x < Self::MAX {
             let shift = x << START;
             unsafe {
-                *self.data_mut() = shift;
+                *self.data_mut() = (*self.data() & !Self::MASK) | shift;
             }
             Ok(())
         } else {
@@ -254,12 +470,12 @@ impl<const START: u8, const END: u8> BitRange<u32, START, END> {
     const MAX: u32 = 2u32.pow((END - START) as u32);
 
     fn data(&self) -> *const u32 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u32>()
     }
 
     fn data_mut(&mut self) -> *mut u32 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u32>()
     }
 
@@ -318,7 +534,7 @@ impl<const START: u8, const END: u8> BitRange<u32, START, END> {
         if x < Self::MAX {
             let shift = x << START;
             unsafe {
-                *self.data_mut() = shift;
+                *self.data_mut() = (*self.data() & !Self::MASK) | shift;
             }
             Ok(())
         } else {
@@ -332,12 +548,12 @@ impl<const START: u8, const END: u8> BitRange<u16, START, END> {
     const MAX: u16 = 2u16.pow((END - START) as u32);
 
     fn data(&self) -> *const u16 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u16>()
     }
 
     fn data_mut(&mut self) -> *mut u16 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u16>()
     }
 
@@ -396,7 +612,7 @@ impl<const START: u8, const END: u8> BitRange<u16, START, END> {
         if x < Self::MAX {
             let shift = x << START;
             unsafe {
-                *self.data_mut() = shift;
+                *self.data_mut() = (*self.data() & !Self::MASK) | shift;
             }
             Ok(())
         } else {
@@ -410,12 +626,12 @@ impl<const START: u8, const END: u8> BitRange<u8, START, END> {
     const MAX: u8 = 2u8.pow((END - START) as u32);
 
     fn data(&self) -> *const u8 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u8>()
     }
 
     fn data_mut(&mut self) -> *mut u8 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u8>()
     }
 
@@ -474,7 +690,7 @@ impl<const START: u8, const END: u8> BitRange<u8, START, END> {
         if x < Self::MAX {
             let shift = x << START;
             unsafe {
-                *self.data_mut() = shift;
+                *self.data_mut() = (*self.data() & !Self::MASK) | shift;
             }
             Ok(())
         } else {
@@ -600,13 +816,13 @@ impl<const START: u8, const END: u8> PartialOrd<u128> for BitRange<u128, START,
 }
 impl<const START: u8, const END: u8> PartialOrd for BitRange<u128, START, END> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let (a, b): (u128, u128) = (self.into(), other.into());
-        Some(a.cmp(&b))
+        Some(self.cmp(other))
     }
 }
 impl<const START: u8, const END: u8> Ord for BitRange<u128, START, END> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        let (a, b): (u128, u128) = (self.into(), other.into());
+        a.cmp(&b)
     }
 }
 impl<const START: u8, const END: u8> PartialOrd<u64> for BitRange<u64, START, END> {
@@ -617,13 +833,13 @@ impl<const START: u8, const END: u8> PartialOrd<u64> for BitRange<u64, START, EN
 }
 impl<const START: u8, const END: u8> PartialOrd for BitRange<u64, START, END> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let (a, b): (u64, u64) = (self.into(), other.into());
-        Some(a.cmp(&b))
+        Some(self.cmp(other))
     }
 }
 impl<const START: u8, const END: u8> Ord for BitRange<u64, START, END> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        let (a, b): (u64, u64) = (self.into(), other.into());
+        a.cmp(&b)
     }
 }
 impl<const START: u8, const END: u8> PartialOrd<u32> for BitRange<u32, START, END> {
@@ -634,13 +850,13 @@ impl<const START: u8, const END: u8> PartialOrd<u32> for BitRange<u32, START, EN
 }
 impl<const START: u8, const END: u8> PartialOrd for BitRange<u32, START, END> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let (a, b): (u32, u32) = (self.into(), other.into());
-        Some(a.cmp(&b))
+        Some(self.cmp(other))
     }
 }
 impl<const START: u8, const END: u8> Ord for BitRange<u32, START, END> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        let (a, b): (u32, u32) = (self.into(), other.into());
+        a.cmp(&b)
     }
 }
 impl<const START: u8, const END: u8> PartialOrd<u16> for BitRange<u16, START, END> {
@@ -651,13 +867,13 @@ impl<const START: u8, const END: u8> PartialOrd<u16> for BitRange<u16, START, EN
 }
 impl<const START: u8, const END: u8> PartialOrd for BitRange<u16, START, END> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let (a, b): (u16, u16) = (self.into(), other.into());
-        Some(a.cmp(&b))
+        Some(self.cmp(other))
     }
 }
 impl<const START: u8, const END: u8> Ord for BitRange<u16, START, END> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        let (a, b): (u16, u16) = (self.into(), other.into());
+        a.cmp(&b)
     }
 }
 impl<const START: u8, const END: u8> PartialOrd<u8> for BitRange<u8, START, END> {
@@ -668,13 +884,13 @@ impl<const START: u8, const END: u8> PartialOrd<u8> for BitRange<u8, START, END>
 }
 impl<const START: u8, const END: u8> PartialOrd for BitRange<u8, START, END> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let (a, b): (u8, u8) = (self.into(), other.into());
-        Some(a.cmp(&b))
+        Some(self.cmp(other))
     }
 }
 impl<const START: u8, const END: u8> Ord for BitRange<u8, START, END> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        let (a, b): (u8, u8) = (self.into(), other.into());
+        a.cmp(&b)
     }
 }
 
@@ -686,31 +902,31 @@ pub struct Bit<T, const P: u8>(pub PhantomData<T>);
 impl<const P: u8> fmt::Display for Bit<u128, P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: bool = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 impl<const P: u8> fmt::Display for Bit<u64, P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: bool = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 impl<const P: u8> fmt::Display for Bit<u32, P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: bool = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 impl<const P: u8> fmt::Display for Bit<u16, P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: bool = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 impl<const P: u8> fmt::Display for Bit<u8, P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let a: bool = self.into();
-        write!(f, "{}", a)
+        write!(f, "{a}")
     }
 }
 
@@ -729,12 +945,12 @@ impl<const P: u8> Bit<u128, P> {
     }
 
     fn data(&self) -> *const u128 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u128>()
     }
 
     fn data_mut(&mut self) -> *mut u128 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u128>()
     }
 }
@@ -752,12 +968,12 @@ impl<const P: u8> Bit<u64, P> {
     }
 
     fn data(&self) -> *const u64 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u64>()
     }
 
     fn data_mut(&mut self) -> *mut u64 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u64>()
     }
 }
@@ -775,12 +991,12 @@ impl<const P: u8> Bit<u32, P> {
     }
 
     fn data(&self) -> *const u32 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u32>()
     }
 
     fn data_mut(&mut self) -> *mut u32 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u32>()
     }
 }
@@ -798,12 +1014,12 @@ impl<const P: u8> Bit<u16, P> {
     }
 
     fn data(&self) -> *const u16 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u16>()
     }
 
     fn data_mut(&mut self) -> *mut u16 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u16>()
     }
 }
@@ -821,12 +1037,12 @@ impl<const P: u8> Bit<u8, P> {
     }
 
     fn data(&self) -> *const u8 {
-        let a = self as *const Self;
+        let a = std::ptr::from_ref::<Self>(self);
         a.cast::<u8>()
     }
 
     fn data_mut(&mut self) -> *mut u8 {
-        let a = self as *mut Self;
+        let a = std::ptr::from_mut::<Self>(self);
         a.cast::<u8>()
     }
 }
@@ -933,6 +1149,259 @@ impl<const P: u8> Eq for Bit<u32, P> {}
 impl<const P: u8> Eq for Bit<u16, P> {}
 impl<const P: u8> Eq for Bit<u8, P> {}
 
+/// Unknown discriminant error type, returned when the discriminant of a bit field with multiple
+/// layouts does not select any known layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownDiscriminantErr<T>(pub T);
+impl<T: fmt::Display> fmt::Display for UnknownDiscriminantErr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Discriminant {} does not select any known layout",
+            self.0
+        )
+    }
+}
+
+/// Error type for `TryFrom<HashMap<String, u128>>` implementations of bit fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromMapErr {
+    /// The map contains a key which is not a field.
+    UnknownField(String),
+    /// The map does not contain a field.
+    MissingField(&'static str),
+    /// The value of a field is greater than the maximum storable value in the field.
+    Overflow {
+        /// Identifier of the field.
+        field: &'static str,
+        /// The given value.
+        value: u128,
+    },
+}
+impl fmt::Display for FromMapErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownField(field) => write!(f, "Unknown field `{field}`"),
+            Self::MissingField(field) => write!(f, "Missing field `{field}`"),
+            Self::Overflow { field, value } => {
+                write!(f, "Value {value} overflows field `{field}`")
+            }
+        }
+    }
+}
+
+/// A policy comparing the value of a field in one bit field against its value in another, used
+/// by the generated `supports` functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// All 1 bits in the other value must also be 1s in this value.
+    Superset,
+    /// This value must be greater than or equal to the other value.
+    GreaterOrEqual,
+    /// This value must be less than or equal to the other value.
+    LessOrEqual,
+    /// This value must equal the other value.
+    Equal,
+}
+impl Policy {
+    /// Returns a [`FieldMismatch`] if `this` does not satisfy `self` against `other`.
+    #[must_use]
+    pub fn check(self, field: &'static str, this: u128, other: u128) -> Option<FieldMismatch> {
+        let satisfied = match self {
+            Self::Superset => other & !this == 0,
+            Self::GreaterOrEqual => this >= other,
+            Self::LessOrEqual => this <= other,
+            Self::Equal => this == other,
+        };
+        (!satisfied).then_some(FieldMismatch {
+            field,
+            policy: self,
+            this,
+            other,
+        })
+    }
+}
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Superset => write!(f, "superset"),
+            Self::GreaterOrEqual => write!(f, ">="),
+            Self::LessOrEqual => write!(f, "<="),
+            Self::Equal => write!(f, "=="),
+        }
+    }
+}
+/// A field which does not satisfy its [`Policy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMismatch {
+    /// Identifier of the field.
+    pub field: &'static str,
+    /// The policy which is not satisfied.
+    pub policy: Policy,
+    /// Value of the field in the bit field checked.
+    pub this: u128,
+    /// Value of the field in the bit field checked against.
+    pub other: u128,
+}
+impl fmt::Display for FieldMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}`: {:#x} {} {:#x} does not hold",
+            self.field, self.this, self.policy, self.other
+        )
+    }
+}
+
+/// A type interface for a number of adjacent equally sized ranges of bits.
+///
+/// Element `i` covers the bits `START + i * STEP..START + (i + 1) * STEP`.
+#[derive(Debug, Clone, Copy)]
+pub struct BitRangeArray<T, const START: u8, const STEP: u8, const LEN: usize>(pub PhantomData<T>);
+
+/// Checked indexed assign error type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckedIndexAssignErr {
+    /// Given index is not less than the number of elements in the array.
+    IndexOutOfBounds,
+    /// Given value is more than maximum value storable in an element.
+    OutOfRange,
+}
+impl fmt::Display for CheckedIndexAssignErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IndexOutOfBounds => write!(f, "Given index is outside the bounds of the array"),
+            Self::OutOfRange => write!(
+                f,
+                "Given value is greater than maximum storable value in an element"
+            ),
+        }
+    }
+}
+
+macro_rules! bit_range_array_impl {
+    ($t:ty, $mask:ident) => {
+        // Struct impl
+        impl<const START: u8, const STEP: u8, const LEN: usize>
+            BitRangeArray<$t, START, STEP, LEN>
+        {
+            /// The mask of a single element when shifted to the least significant bit.
+            const ELEMENT_MASK: $t = $mask(0, STEP);
+
+            /// Returns the number of elements in the array.
+            #[must_use]
+            pub const fn len(&self) -> usize {
+                LEN
+            }
+
+            /// Returns `true` if the array contains no elements.
+            #[must_use]
+            pub const fn is_empty(&self) -> bool {
+                LEN == 0
+            }
+
+            /// Returns the value of the element at `index` or `None` if `index` is out of bounds.
+            #[must_use]
+            pub fn get(&self, index: usize) -> Option<$t> {
+                let shift = Self::shift(index)?;
+                Some((unsafe { *self.data() } >> shift) & Self::ELEMENT_MASK)
+            }
+
+            /// Sets the element at `index` to `x`.
+            ///
+            /// # Errors
+            ///
+            /// 1. When `index` is not less than the number of elements in the array.
+            /// 2. When `x` is greater than the maximum value storable in an element.
+            pub fn checked_assign(
+                &mut self,
+                index: usize,
+                x: $t,
+            ) -> Result<(), CheckedIndexAssignErr> {
+                let shift = Self::shift(index).ok_or(CheckedIndexAssignErr::IndexOutOfBounds)?;
+                if x > Self::ELEMENT_MASK {
+                    return Err(CheckedIndexAssignErr::OutOfRange);
+                }
+                let data = self.data_mut();
+                unsafe {
+                    *data = (*data & !(Self::ELEMENT_MASK << shift)) | (x << shift);
+                }
+                Ok(())
+            }
+
+            /// Yields an iterator across the values of the elements.
+            pub fn iter(&self) -> impl Iterator<Item = $t> + '_ {
+                (0..LEN).filter_map(|i| self.get(i))
+            }
+
+            /// Returns the offset of the element at `index`.
+            fn shift(index: usize) -> Option<u32> {
+                if index < LEN {
+                    let index = u32::try_from(index).ok()?;
+                    Some(u32::from(START) + index * u32::from(STEP))
+                } else {
+                    None
+                }
+            }
+
+            fn data(&self) -> *const $t {
+                let a = std::ptr::from_ref::<Self>(self);
+                a.cast::<$t>()
+            }
+
+            fn data_mut(&mut self) -> *mut $t {
+                let a = std::ptr::from_mut::<Self>(self);
+                a.cast::<$t>()
+            }
+        }
+        // Into<[uint; LEN]> impl
+        impl<const START: u8, const STEP: u8, const LEN: usize>
+            From<&BitRangeArray<$t, START, STEP, LEN>> for [$t; LEN]
+        {
+            fn from(this: &BitRangeArray<$t, START, STEP, LEN>) -> Self {
+                let mut array = [0; LEN];
+                for (a, b) in array.iter_mut().zip(this.iter()) {
+                    *a = b;
+                }
+                array
+            }
+        }
+        // Display impl
+        impl<const START: u8, const STEP: u8, const LEN: usize> fmt::Display
+            for BitRangeArray<$t, START, STEP, LEN>
+        {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let a: [$t; LEN] = self.into();
+                write!(f, "{a:?}")
+            }
+        }
+        // Eq impl
+        impl<const START: u8, const STEP: u8, const LEN: usize> PartialEq<[$t; LEN]>
+            for BitRangeArray<$t, START, STEP, LEN>
+        {
+            fn eq(&self, other: &[$t; LEN]) -> bool {
+                self.iter().eq(other.iter().copied())
+            }
+        }
+        impl<const START: u8, const STEP: u8, const LEN: usize> PartialEq
+            for BitRangeArray<$t, START, STEP, LEN>
+        {
+            fn eq(&self, other: &Self) -> bool {
+                self.iter().eq(other.iter())
+            }
+        }
+        impl<const START: u8, const STEP: u8, const LEN: usize> Eq
+            for BitRangeArray<$t, START, STEP, LEN>
+        {
+        }
+    };
+}
+bit_range_array_impl!(u128, mask_u128);
+bit_range_array_impl!(u64, mask_u64);
+bit_range_array_impl!(u32, mask_u32);
+bit_range_array_impl!(u16, mask_u16);
+bit_range_array_impl!(u8, mask_u8);
+
 // Mask functions
 const fn mask_u128(start: u8, end: u8) -> u128 {
     // Since we can't define a const closure
@@ -1021,17 +1490,314 @@ mod test {
             18
         ]
     );
+    bitfield!(GeneratedArrayBitField, u32, [
+        FLAG,
+        0,
+        BYTES[3],
+        8..32 step 8
+    ]);
+    #[rustfmt::skip]
+    bitfield!(GeneratedLayoutBitField, u8, [
+        KIND, 0..2,
+        FLAG, 7
+    ], match KIND [
+        0 => EMPTY,
+        0b10 => PAIR [
+            LOW, 2..4,
+            HIGH, 4..6
+        ]
+    ]);
+    #[rustfmt::skip]
+    bitfield!(GeneratedPolicyBitField, u16, [
+        FLAG, 0,
+        IGNORED, 1 ignore,
+        MIN, 2..5 >=,
+        MAX, 5..8 <=,
+        EXACT, 8..10 ==,
+        MASK, 10..13 superset,
+        PAIR[2], 13..15 step 1 ==,
+        OTHER, 15..16
+    ]);
+    #[test]
+    fn supports() {
+        let bitfield = GeneratedPolicyBitField::from(0x12_89);
+        assert_eq!(bitfield.supports(&bitfield), Ok(()));
+        assert_eq!(
+            bitfield.supports(&GeneratedPolicyBitField::from(0x92_E6)),
+            Ok(())
+        );
+
+        let mismatches = bitfield
+            .supports(&GeneratedPolicyBitField::from(0x3B_51))
+            .unwrap_err();
+        let mismatch = |field, policy, this, other| FieldMismatch {
+            field,
+            policy,
+            this,
+            other,
+        };
+        assert_eq!(
+            mismatches,
+            [
+                mismatch("MIN", Policy::GreaterOrEqual, 2, 4),
+                mismatch("MAX", Policy::LessOrEqual, 4, 2),
+                mismatch("EXACT", Policy::Equal, 2, 3),
+                mismatch("MASK", Policy::Superset, 0b100, 0b110),
+                mismatch("PAIR[0]", Policy::Equal, 0, 1),
+            ]
+        );
+        assert_eq!(mismatches[0].to_string(), "`MIN`: 0x2 >= 0x4 does not hold");
+        assert_eq!(
+            GeneratedPolicyBitField::from(0).supports(&GeneratedPolicyBitField::from(1)),
+            Err(vec![mismatch("FLAG", Policy::Superset, 0, 1)])
+        );
+    }
+    #[test]
+    fn bit_field() {
+        fn masked<T: BitField>(bit_field: &T, ident: &str) -> u128 {
+            let field = T::FIELDS.iter().find(|field| field.ident == ident).unwrap();
+            bit_field.to_storage().into() & field.mask()
+        }
+
+        assert_eq!(<GeneratedArrayBitField as BitField>::BITS, 32);
+        assert_eq!(
+            GeneratedArrayBitField::FIELDS,
+            [
+                Field {
+                    ident: "FLAG",
+                    start: 0,
+                    end: 1,
+                    kind: FieldKind::Flag,
+                    since: None,
+                    vendors: &[]
+                },
+                Field {
+                    ident: "BYTES",
+                    start: 8,
+                    end: 32,
+                    kind: FieldKind::Array { len: 3, step: 8 },
+                    since: None,
+                    vendors: &[]
+                },
+            ]
+        );
+        assert_eq!(GeneratedBitField::FIELDS[5].ident, "SSE3");
+        assert_eq!(GeneratedBitField::FIELDS[6].kind, FieldKind::Range);
+
+        let bitfield = GeneratedArrayBitField::from_storage(0x1234_5601);
+        assert_eq!(bitfield.to_storage(), 0x1234_5601);
+        assert_eq!(masked(&bitfield, "FLAG"), 1);
+        assert_eq!(masked(&bitfield, "BYTES"), 0x1234_5600);
+
+        let (a, b) = (
+            GeneratedBitField::from_storage(0b0100_0000_0110),
+            GeneratedBitField::from_storage(0b0000_0000_0100),
+        );
+        assert!(BitField::superset(&a, &b));
+        assert!(BitField::subset(&b, &a));
+        assert_eq!(BitField::intersection(&a, &b).to_storage(), 0b0100);
+        assert_eq!(BitField::union(&a, &b).to_storage(), 0b0100_0000_0100);
+    }
+    #[test]
+    fn map() {
+        use std::collections::HashMap;
+
+        let bitfield = GeneratedArrayBitField::from(0x1234_5601);
+        let map = HashMap::<String, u128>::from(bitfield.clone());
+        let expected = [
+            ("FLAG", 1),
+            ("BYTES[0]", 0x56),
+            ("BYTES[1]", 0x34),
+            ("BYTES[2]", 0x12),
+        ]
+        .into_iter()
+        .map(|(key, value)| (String::from(key), value))
+        .collect::<HashMap<_, _>>();
+        assert_eq!(map, expected);
+        let round_trip = GeneratedArrayBitField::try_from(map.clone()).unwrap();
+        assert_eq!(round_trip.data, bitfield.data);
+
+        let mut overflow = map.clone();
+        overflow.insert(String::from("BYTES[1]"), 0x100);
+        assert_eq!(
+            GeneratedArrayBitField::try_from(overflow).unwrap_err(),
+            FromMapErr::Overflow {
+                field: "BYTES[1]",
+                value: 0x100
+            }
+        );
+        let mut missing = map.clone();
+        missing.remove("FLAG");
+        assert_eq!(
+            GeneratedArrayBitField::try_from(missing).unwrap_err(),
+            FromMapErr::MissingField("FLAG")
+        );
+        let mut unknown = map;
+        unknown.insert(String::from("OTHER"), 0);
+        assert_eq!(
+            GeneratedArrayBitField::try_from(unknown).unwrap_err(),
+            FromMapErr::UnknownField(String::from("OTHER"))
+        );
+
+        let bitfield = GeneratedBitField::from(23548);
+        let map = HashMap::<String, u128>::from(bitfield.clone());
+        assert_eq!(map["RANGE2"], 3);
+        assert_eq!(map["SSE3"], 0);
+        assert_eq!(map.len(), 8);
+        let round_trip = GeneratedBitField::try_from(map).unwrap();
+        // Bits not covered by any field are lost.
+        assert_eq!(round_trip.data, 0x5BFC & 0b0100_0111_0110_0011_1101);
+    }
+    bitfield!(GeneratedFlagBitField, u8, [FIRST, 0, SECOND, 1]);
+    #[test]
+    fn set() {
+        use std::collections::HashSet;
+
+        // Only bit fields of just flags convert with sets, others convert with maps (see `map`).
+        let bitfield = GeneratedFlagBitField::try_from(HashSet::from(["SECOND"])).unwrap();
+        assert_eq!(bitfield.data, 0b10);
+        let set: HashSet<String> = bitfield.into();
+        assert_eq!(set, HashSet::from([String::from("SECOND")]));
+        assert_eq!(
+            GeneratedFlagBitField::try_from(HashSet::from(["THIRD"])).unwrap_err(),
+            "Non-specified flag found in given set"
+        );
+    }
+    #[test]
+    fn constant() {
+        const BITFIELD: GeneratedBitField =
+            GeneratedBitField::from_bits(GeneratedBitField::SSE_MASK | 0b0101_0000_0000_0000);
+        static BITFIELDS: [GeneratedArrayBitField; 2] = [
+            GeneratedArrayBitField::from_bits(0x1234_5601),
+            GeneratedArrayBitField::from_bits(0),
+        ];
+        const RANGE3: u32 = BITFIELD.RANGE3();
+
+        assert_eq!(BITFIELD.bits(), 0b0101_0000_0000_0100);
+        const { assert!(BITFIELD.SSE()) };
+        assert!(!BITFIELD.SSE1());
+        assert_eq!(RANGE3, 0b101);
+        assert_eq!(GeneratedBitField::RANGE3_MASK, 0b0111_0000_0000_0000);
+        assert_eq!(GeneratedBitField::SSE4_MASK, 1 << 18);
+
+        assert_eq!(GeneratedArrayBitField::BYTES_MASK, 0xFFFF_FF00);
+        assert!(BITFIELDS[0].FLAG());
+        assert_eq!(BITFIELDS[0].BYTES(2), Some(0x12));
+        assert_eq!(BITFIELDS[0].BYTES(3), None);
+        assert_eq!(BITFIELDS[1].BYTES(0), Some(0));
+    }
+    #[rustfmt::skip]
+    bitfield!(GeneratedAnnotatedBitField, u8, [
+        /// Defined by all vendors since the first revision.
+        COMMON, 0,
+        /// Only defined by Intel.
+        #[since("SDM-075")]
+        #[vendor(intel)]
+        INTEL, 1,
+        #[since("APM-3.33")]
+        #[vendor(amd)]
+        AMD, 2..4,
+        #[since("SDM-079")]
+        #[vendor(intel | amd)]
+        BOTH, 4
+    ]);
+    #[test]
+    fn annotations() {
+        let fields = GeneratedAnnotatedBitField::FIELDS;
+        assert_eq!(fields[0].since, None);
+        assert_eq!(fields[0].vendors, []);
+        assert_eq!(fields[1].since, Some("SDM-075"));
+        assert_eq!(fields[1].vendors, [Vendor::Intel]);
+        assert_eq!(fields[2].vendors, [Vendor::Amd]);
+        assert_eq!(fields[3].vendors, [Vendor::Intel, Vendor::Amd]);
+        assert!(fields[0].defined_by(Vendor::Amd));
+        assert!(!fields[1].defined_by(Vendor::Amd));
+
+        let bitfield = GeneratedAnnotatedBitField::from(0b0001_1011);
+        assert_eq!(
+            bitfield
+                .set_fields()
+                .into_iter()
+                .map(|field| field.ident)
+                .collect::<Vec<_>>(),
+            ["COMMON", "INTEL", "AMD", "BOTH"]
+        );
+        assert_eq!(bitfield.revisions(), ["APM-3.33", "SDM-075", "SDM-079"]);
+        let mut revisions = ["SDM 10", "SDM 9", "APM 3.33", "SDM 9a"];
+        revisions.sort_unstable_by_key(|revision| revision_key(revision));
+        assert_eq!(revisions, ["APM 3.33", "SDM 9", "SDM 9a", "SDM 10"]);
+        assert_eq!(bitfield.vendor_mismatches(Vendor::Intel), [&fields[2]]);
+        assert_eq!(bitfield.vendor_mismatches(Vendor::Amd), [&fields[1]]);
+        assert_eq!(
+            GeneratedAnnotatedBitField::from(0b0001_0001).vendor_mismatches(Vendor::Amd),
+            Vec::<&Field>::new()
+        );
+    }
+    #[test]
+    fn array() {
+        let mut bitfield = GeneratedArrayBitField::from(0x1234_5601);
+        println!("bitfield: {bitfield}");
+        assert_eq!(size_of::<u32>(), size_of::<GeneratedArrayBitField>());
+        assert_eq!(bitfield.FLAG, true);
+        assert_eq!(bitfield.BYTES.len(), 3);
+        assert_eq!(bitfield.BYTES.get(0), Some(0x56));
+        assert_eq!(bitfield.BYTES.get(2), Some(0x12));
+        assert_eq!(bitfield.BYTES.get(3), None);
+        assert_eq!(bitfield.BYTES, [0x56, 0x34, 0x12]);
+        assert_eq!(
+            bitfield.BYTES.iter().collect::<Vec<_>>(),
+            [0x56, 0x34, 0x12]
+        );
+
+        assert_eq!(bitfield.BYTES.checked_assign(1, 0xAB), Ok(()));
+        assert_eq!(bitfield.data, 0x12AB_5601);
+        assert_eq!(
+            bitfield.BYTES.checked_assign(3, 0),
+            Err(CheckedIndexAssignErr::IndexOutOfBounds)
+        );
+        assert_eq!(
+            bitfield.BYTES.checked_assign(0, 0x100),
+            Err(CheckedIndexAssignErr::OutOfRange)
+        );
+        assert_eq!(bitfield.data, 0x12AB_5601);
+    }
+    #[test]
+    fn layout() {
+        let bitfield = GeneratedLayoutBitField::from(0b1011_0110);
+        match bitfield.layout() {
+            Ok(GeneratedLayoutBitFieldLayout::PAIR(pair)) => {
+                assert_eq!(pair.LOW, 1);
+                assert_eq!(pair.HIGH, 3);
+            }
+            layout => panic!("{layout:?}"),
+        }
+        match GeneratedLayoutBitField::from(0b1000_0000).layout() {
+            Ok(GeneratedLayoutBitFieldLayout::EMPTY(empty)) => assert_eq!(empty.data, 0b1000_0000),
+            layout => panic!("{layout:?}"),
+        }
+        assert_eq!(
+            GeneratedLayoutBitField::from(0b0000_0011)
+                .layout()
+                .unwrap_err(),
+            UnknownDiscriminantErr(3)
+        );
+
+        let pair = GeneratedLayoutBitFieldPAIR::from(0b0010_0100);
+        let bitfield = GeneratedLayoutBitField::from(GeneratedLayoutBitFieldLayout::PAIR(pair));
+        assert_eq!(bitfield.data, 0b0010_0110);
+        // The bits outside the discriminant are kept, also for layouts without fields.
+        let empty = GeneratedLayoutBitFieldEMPTY::from(0b1000_0011);
+        let bitfield = GeneratedLayoutBitField::from(GeneratedLayoutBitFieldLayout::EMPTY(empty));
+        assert_eq!(bitfield.data, 0b1000_0000);
+    }
     #[test]
     fn main() {
         println!("started");
         let mut bitfield = GeneratedBitField::from(23548);
-        println!("bitfield ptr: {:?}", &bitfield as *const GeneratedBitField);
-        println!(
-            "bitfield: {:032b} | {:?} | {}",
-            bitfield, bitfield, bitfield
-        );
+        println!("bitfield ptr: {:?}", &raw const bitfield);
+        println!("bitfield: {bitfield:032b} | {bitfield:?} | {bitfield}");
         assert_eq!(size_of::<u32>(), size_of::<GeneratedBitField>(),);
-        println!("bitfield ptr: {:?}", &bitfield as *const GeneratedBitField);
+        println!("bitfield ptr: {:?}", &raw const bitfield);
 
         assert_eq!(mask_u16(0, 16), 0b1111_1111_1111_1111);
         assert_eq!(
@@ -1074,10 +1840,7 @@ mod test {
             Err(CheckedAddAssignErr::OutOfRange)
         );
 
-        println!(
-            "bitfield: {:032b} | {:?} | {}",
-            bitfield, bitfield, bitfield
-        );
+        println!("bitfield: {bitfield:032b} | {bitfield:?} | {bitfield}");
 
         assert_eq!(bitfield.RANGE1.checked_sub_assign(1), Ok(()));
         assert_eq!(
@@ -1090,9 +1853,81 @@ mod test {
         );
         assert_eq!(bitfield.RANGE2.checked_sub_assign(1), Ok(()));
 
-        println!(
-            "bitfield: {:032b} | {:?} | {}",
-            bitfield, bitfield, bitfield
-        );
+        println!("bitfield: {bitfield:032b} | {bitfield:?} | {bitfield}");
+    }
+
+    // The bits of the flags of `GeneratedBitField`.
+    const FLAGS: u32 = 0b0100_0000_0110_0000_1100;
+    // The bits of `GeneratedBitField::RANGE3`.
+    const RANGE3: u32 = 0b0111_0000_0000_0000;
+
+    proptest::proptest! {
+        #[test]
+        fn from_round_trip(data: u32) {
+            let bitfield = GeneratedBitField::from(data);
+            proptest::prop_assert_eq!(bitfield.data, data);
+            proptest::prop_assert_eq!(GeneratedBitField::from_storage(data).to_storage(), data);
+        }
+        #[test]
+        fn checked_ops(data: u32, x in 0..10u32) {
+            let cur = (data & RANGE3) >> 12;
+
+            let mut bitfield = GeneratedBitField::from(data);
+            let assigned = bitfield.RANGE3.checked_assign(x);
+            proptest::prop_assert_eq!(assigned.is_ok(), x < 8);
+            if x < 8 {
+                proptest::prop_assert_eq!(bitfield.data, (data & !RANGE3) | (x << 12));
+            } else {
+                proptest::prop_assert_eq!(bitfield.data, data);
+            }
+
+            let mut bitfield = GeneratedBitField::from(data);
+            let added = bitfield.RANGE3.checked_add_assign(x);
+            proptest::prop_assert_eq!(added.is_ok(), cur + x < 8);
+            if cur + x < 8 {
+                proptest::prop_assert_eq!(bitfield.data, data + (x << 12));
+            } else {
+                proptest::prop_assert_eq!(bitfield.data, data);
+            }
+
+            let mut bitfield = GeneratedBitField::from(data);
+            let subtracted = bitfield.RANGE3.checked_sub_assign(x);
+            proptest::prop_assert_eq!(subtracted.is_ok(), x <= cur);
+            if x <= cur {
+                proptest::prop_assert_eq!(bitfield.data, data - (x << 12));
+            } else {
+                proptest::prop_assert_eq!(bitfield.data, data);
+            }
+        }
+        #[test]
+        fn set_ops(a: u32, b: u32) {
+            let (x, y) = (GeneratedBitField::from(a), GeneratedBitField::from(b));
+            proptest::prop_assert_eq!(x.superset(&y), b & !a & FLAGS == 0);
+            proptest::prop_assert_eq!(x.subset(&y), a & !b & FLAGS == 0);
+            proptest::prop_assert_eq!(x.union(&y).data, (a | b) & FLAGS);
+            proptest::prop_assert_eq!(x.intersection(&y).data, a & b & FLAGS);
+        }
+    }
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn arbitrary_reserved_zero(
+            bitfield in proptest::arbitrary::any_with::<GeneratedBitField>(ArbitraryOptions {
+                reserved_zero: true,
+            })
+        ) {
+            proptest::prop_assert_eq!(
+                u128::from(bitfield.data) & !GeneratedBitField::fields_mask(),
+                0
+            );
+        }
+    }
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn quickcheck_reserved_zero() {
+        fn reserved_zero(ReservedZero(bitfield): ReservedZero<GeneratedBitField>) -> bool {
+            u128::from(bitfield.data) & !GeneratedBitField::fields_mask() == 0
+        }
+        quickcheck::quickcheck(reserved_zero as fn(ReservedZero<GeneratedBitField>) -> bool);
     }
 }
@@ -1,4 +1,4 @@
-use crate::bitflags_serde;
+use crate::{bitfield_serde, bitflags_serde};
 
 bitflags_serde!(Leaf0x1_SubLeaf0_Ecx, a);
 bitflags_serde!(Leaf0x1_SubLeaf0_Edx, b);
@@ -69,112 +69,46 @@ macro_rules! bitflags_serde {
     };
 }
 
-pub mod processor_version_information_mod {
-    use std::collections::HashMap;
-
-    use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
-
-    use crate::ProcessorVersionInformation;
-    type Flags = ProcessorVersionInformation;
-
-    pub fn serialize<S>(date: &Flags, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let map = [
-            ("stepping_id", date.stepping_id()),
-            ("model", date.model()),
-            ("family_id", date.family_id()),
-            ("processor_type", date.processor_type()),
-            ("extended_model_id", date.extended_model_id()),
-            ("extended_family_id", date.extended_family_id()),
-        ]
-        .into_iter()
-        .collect::<HashMap<&str, u8>>();
-        map.serialize(serializer)
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Flags, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let raw = HashMap::<&str, u8>::deserialize(deserializer)?;
-        ProcessorVersionInformation::try_from(raw)
-            .map_err(|_| serde::de::Error::custom("Unexpected flags value {:?}"))
-    }
-}
-
-pub mod leaf0x8000_0008_sub_leaf0_eax_mod {
-    use std::collections::HashMap;
-
-    use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
-
-    use crate::Leaf0x8000_0008_SubLeaf0_Eax;
-    type Flags = Leaf0x8000_0008_SubLeaf0_Eax;
-
-    pub fn serialize<S>(date: &Flags, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let map = [
-            (
-                "number_of_physical_address_bits",
-                date.number_of_physical_address_bits(),
-            ),
-            (
-                "number_of_linear_address_bits",
-                date.number_of_linear_address_bits(),
-            ),
-        ]
-        .into_iter()
-        .collect::<HashMap<&str, u8>>();
-        map.serialize(serializer)
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Flags, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let raw = HashMap::<&str, u8>::deserialize(deserializer)?;
-        Leaf0x8000_0008_SubLeaf0_Eax::try_from(raw)
-            .map_err(|_| serde::de::Error::custom("Unexpected flags value {:?}"))
-    }
-}
-
-pub mod leaf0x8000_0008_sub_leaf0_ecx_mod {
-    use std::collections::HashMap;
-
-    use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+bitfield_serde!(ProcessorVersionInformation, processor_version_information_mod);
+// `guest_physical_address_size` was added after templates were first written.
+bitfield_serde!(
+    Leaf0x8000_0008_SubLeaf0_Eax,
+    leaf0x8000_0008_sub_leaf0_eax_mod,
+    "guest_physical_address_size"
+);
+bitfield_serde!(Leaf0x8000_0008_SubLeaf0_Ecx, leaf0x8000_0008_sub_leaf0_ecx_mod);
+
+/// Internal macro for serde bit field implementations, (de)serializing the value of each field.
+///
+/// The fields listed after the module are optional, deserializing as 0 when absent.
+#[macro_export]
+macro_rules! bitfield_serde {
+    ( $x:ident, $mod:ident $(, $optional:literal)* ) => {
+        pub mod $mod {
+            use std::collections::HashMap;
 
-    use crate::Leaf0x8000_0008_SubLeaf0_Ecx;
-    type Flags = Leaf0x8000_0008_SubLeaf0_Ecx;
+            use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+            use $crate::$x;
+            type Fields = $x;
 
-    pub fn serialize<S>(date: &Flags, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let map = [
-            (
-                "number_of_physical_cores_minus_1",
-                date.number_of_physical_cores_minus_1(),
-            ),
-            ("log2_of_maximum_apic_id", date.log2_of_maximum_apic_id()),
-            (
-                "performance_timestamp_counter_size",
-                date.performance_timestamp_counter_size(),
-            ),
-        ]
-        .into_iter()
-        .collect::<HashMap<&str, u8>>();
-        map.serialize(serializer)
-    }
+            pub fn serialize<S>(date: &Fields, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                HashMap::<String, u128>::from(date.clone()).serialize(serializer)
+            }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Flags, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let raw = HashMap::<&str, u8>::deserialize(deserializer)?;
-        Leaf0x8000_0008_SubLeaf0_Ecx::try_from(raw)
-            .map_err(|_| serde::de::Error::custom("Unexpected flags value {:?}"))
-    }
+            pub fn deserialize<'de, D>(deserializer: D) -> Result<Fields, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                #[allow(unused_mut)]
+                let mut raw = HashMap::<String, u128>::deserialize(deserializer)?;
+                $(
+                    raw.entry(String::from($optional)).or_default();
+                )*
+                Fields::try_from(raw).map_err(serde::de::Error::custom)
+            }
+        }
+    };
 }
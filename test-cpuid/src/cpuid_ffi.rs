@@ -1,50 +1,290 @@
-use std::alloc::Layout;
+use std::arch::x86_64::CpuidResult;
+use std::collections::HashSet;
 use std::fmt;
-use std::ops::Index;
+use std::ops::{Index, IndexMut};
+
+use bitflags::bitflags;
+use kvm_bindings::{
+    KVM_CPUID_FLAG_SIGNIFCANT_INDEX, KVM_CPUID_FLAG_STATEFUL_FUNC, KVM_CPUID_FLAG_STATE_READ_NEXT,
+    KVM_MAX_CPUID_ENTRIES,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 // Stuff to use for interaction with ffi.
 
-fn vec_into_raw_parts<T>(v: Vec<T>) -> (*mut T, usize, usize) {
-    let mut me = std::mem::ManuallyDrop::new(v);
-    (me.as_mut_ptr(), me.len(), me.capacity())
-}
+/// The vendor ID strings (`ebx`, `edx`, `ecx` of leaf 0) of vendors which return zeros for
+/// out-of-range leaves.
+const ZERO_OUT_OF_RANGE_VENDORS: [[u32; 3]; 2] = [
+    // "AuthenticAMD"
+    [0x6874_7541, 0x6974_6e65, 0x444d_4163],
+    // "HygonGenuine"
+    [0x6f67_7948, 0x6e65_476e, 0x656e_6975],
+];
+
+/// The number of `u32`s making up the `kvm_cpuid2` header (`nent` and `padding`).
+const HEADER_LEN: usize = 2;
+/// The number of `u32`s making up a [`RawCpuidEntry`].
+const ENTRY_LEN: usize = std::mem::size_of::<RawCpuidEntry>() / std::mem::size_of::<u32>();
 
 /// A rusty mimic of
 /// [`kvm_cpuid`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/include/uapi/asm/kvm.h#L226)
 /// .
 ///
-/// [`RawCpuid`] has an identical memory layout to
+/// [`RawCpuid`] owns its entries, storing them after the header in a single buffer with an
+/// identical memory layout to
 /// [`kvm_cpuid`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/include/uapi/asm/kvm.h#L226)
-/// .
+/// , a pointer to which is available through [`RawCpuid::as_ffi_ptr`].
 ///
 /// This allows [`RawCpuid`] to function as a simpler replacement for [`kvm_bindings::CpuId`]. In
 /// the future it may replace [`kvm_bindings::CpuId`] fully.
-#[derive(Debug)]
-#[repr(C)]
+///
+/// As it holds no raw pointers [`RawCpuid`] is [`Clone`], [`Send`] and [`Sync`], so templates can
+/// be built on worker threads.
+#[derive(Clone, PartialEq, Eq)]
 pub struct RawCpuid {
-    /// Number of entries
-    pub nent: u32,
-    padding: u32,
-    // Pointer to entries
-    pub entries: *mut RawCpuidEntry,
+    /// `nent`, `padding` and then `nent` entries.
+    buffer: Vec<u32>,
 }
 impl RawCpuid {
-    /// Yields an iterator across the entries.
+    /// Constructs a [`RawCpuid`] with no entries.
     #[must_use]
-    pub fn iter(&'_ self) -> RawCpuidIter<'_> {
-        RawCpuidIter {
-            cpuid: self,
-            count: 0,
+    pub fn new() -> Self {
+        Self {
+            buffer: vec![0; HEADER_LEN],
         }
     }
 
+    /// Number of entries.
+    #[must_use]
+    pub fn nent(&self) -> u32 {
+        self.buffer[0]
+    }
+
+    /// Number of entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        (self.buffer.len() - HEADER_LEN) / ENTRY_LEN
+    }
+
+    /// Returns `true` if there are no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the entries as a slice.
+    #[must_use]
+    pub fn as_slice(&self) -> &[RawCpuidEntry] {
+        // SAFETY: `RawCpuidEntry` is `#[repr(C)]` and composed solely of `u32`s, so it has the
+        // alignment of `u32` and any bit pattern is valid. `self.buffer` always holds exactly
+        // `self.len()` entries after the header.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.buffer.as_ptr().add(HEADER_LEN).cast::<RawCpuidEntry>(),
+                self.len(),
+            )
+        }
+    }
+
+    /// Returns the entries as a mutable slice.
+    #[must_use]
+    pub fn as_mut_slice(&mut self) -> &mut [RawCpuidEntry] {
+        let len = self.len();
+        // SAFETY: See `RawCpuid::as_slice`.
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                self.buffer
+                    .as_mut_ptr()
+                    .add(HEADER_LEN)
+                    .cast::<RawCpuidEntry>(),
+                len,
+            )
+        }
+    }
+
+    /// Returns a pointer to a
+    /// [`kvm_cpuid2`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/include/uapi/asm/kvm.h#L226)
+    /// holding the entries, for passing to the `KVM_SET_CPUID2` ioctl.
+    ///
+    /// The pointer is valid until `self` is mutated or dropped.
+    #[must_use]
+    pub fn as_ffi_ptr(&self) -> *const kvm_bindings::kvm_cpuid2 {
+        self.buffer.as_ptr().cast::<kvm_bindings::kvm_cpuid2>()
+    }
+
+    /// Returns a borrowed view of the entries.
+    #[must_use]
+    pub fn view(&self) -> RawCpuidRef<'_> {
+        RawCpuidRef {
+            entries: self.as_slice(),
+        }
+    }
+
+    /// Yields an iterator across the entries.
+    pub fn iter(&self) -> std::slice::Iter<'_, RawCpuidEntry> {
+        self.as_slice().iter()
+    }
+
     /// Returns an entry for a given lead (function) and sub-leaf (index).
     ///
+    /// Follows KVM's matching rules (see [`RawCpuidEntry::matches`]), so any sub-leaf matches an
+    /// entry without [`KvmCpuidFlags::SIGNIFICANT_INDEX`].
+    ///
     /// Returning `None` if it is not present.
     #[must_use]
     pub fn get(&self, leaf: u32, sub_leaf: u32) -> Option<&RawCpuidEntry> {
+        self.view().get(leaf, sub_leaf)
+    }
+
+    /// Returns the vendor ID (`ebx`, `edx` and `ecx` of leaf 0), zeros when leaf 0 is missing.
+    #[must_use]
+    pub fn vendor(&self) -> [u32; 3] {
+        self.get(0, 0)
+            .map_or([0; 3], |leaf| [leaf.ebx, leaf.edx, leaf.ecx])
+    }
+
+    /// Returns a mutable reference to an entry for a given leaf (function) and sub-leaf (index).
+    ///
+    /// Follows the same matching rules as [`RawCpuid::get`].
+    ///
+    /// Returning `None` if it is not present.
+    pub fn get_mut(&mut self, leaf: u32, sub_leaf: u32) -> Option<&mut RawCpuidEntry> {
+        self.as_mut_slice()
+            .iter_mut()
+            .find(|entry| entry.matches(leaf, sub_leaf))
+    }
+
+    /// Returns the position of the entry for a given leaf (function) and sub-leaf (index).
+    fn position(&self, leaf: u32, sub_leaf: u32) -> Option<usize> {
         self.iter()
-            .find(|entry| entry.function == leaf && entry.index == sub_leaf)
+            .position(|entry| entry.function == leaf && entry.index == sub_leaf)
+    }
+
+    /// Appends an entry, returning a mutable reference to it.
+    ///
+    /// # Errors
+    ///
+    /// When there are already [`KVM_MAX_CPUID_ENTRIES`] entries.
+    fn push(&mut self, entry: &RawCpuidEntry) -> Result<&mut RawCpuidEntry, CapacityError> {
+        let len = self.len();
+        if len >= KVM_MAX_CPUID_ENTRIES {
+            return Err(CapacityError);
+        }
+        self.buffer.extend_from_slice(&entry.to_words());
+        self.buffer[0] += 1;
+        Ok(&mut self.as_mut_slice()[len])
+    }
+
+    /// Inserts an entry, replacing any existing entry with the same leaf (function) and sub-leaf
+    /// (index) in place and otherwise appending it.
+    ///
+    /// Returns the replaced entry.
+    ///
+    /// # Errors
+    ///
+    /// When appending would exceed [`KVM_MAX_CPUID_ENTRIES`] entries.
+    pub fn insert(&mut self, entry: RawCpuidEntry) -> Result<Option<RawCpuidEntry>, CapacityError> {
+        match self.position(entry.function, entry.index) {
+            Some(index) => Ok(Some(std::mem::replace(&mut self[index], entry))),
+            None => self.push(&entry).map(|_| None),
+        }
+    }
+
+    /// Removes the entry for a given leaf (function) and sub-leaf (index), preserving the order
+    /// of the remaining entries.
+    ///
+    /// Returning `None` if it is not present.
+    pub fn remove(&mut self, leaf: u32, sub_leaf: u32) -> Option<RawCpuidEntry> {
+        let index = self.position(leaf, sub_leaf)?;
+        let entry = self[index].clone();
+        let start = HEADER_LEN + index * ENTRY_LEN;
+        self.buffer.drain(start..start + ENTRY_LEN);
+        self.buffer[0] -= 1;
+        Some(entry)
+    }
+
+    /// Retains only the entries for which `f` returns `true`, preserving their order.
+    pub fn retain<F: FnMut(&RawCpuidEntry) -> bool>(&mut self, mut f: F) {
+        *self = self.iter().filter(|entry| f(entry)).cloned().collect();
+    }
+
+    /// Sorts the entries by leaf (function) then sub-leaf (index).
+    pub fn sort(&mut self) {
+        self.as_mut_slice()
+            .sort_by_key(|entry| (entry.function, entry.index));
+    }
+
+    /// Removes all but the first entry for each leaf (function) and sub-leaf (index).
+    pub fn dedup(&mut self) {
+        let mut seen = HashSet::new();
+        self.retain(|entry| seen.insert((entry.function, entry.index)));
+    }
+
+    /// Returns what the `CPUID` instruction would return for a given `eax` (leaf) and `ecx`
+    /// (sub-leaf) in a guest using these entries.
+    ///
+    /// Mirrors `kvm_cpuid` in
+    /// [`arch/x86/kvm/cpuid.c`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/kvm/cpuid.c):
+    /// - Leaves beyond the limit of their range (from `eax` of leaf 0 for basic leaves,
+    ///   `0x8000_0000` for extended leaves and `0x4000_0000` for hypervisor leaves) return the
+    ///   highest basic leaf on Intel, and zeros on AMD.
+    /// - Missing sub-leaves of leaves within their range return zeros, except leaves 0xB and 0x1F
+    ///   which still return `ecx[7:0]` and the x2APIC ID in `edx`.
+    #[must_use]
+    pub fn query(&self, eax: u32, ecx: u32) -> CpuidResult {
+        self.view().query(eax, ecx)
+    }
+
+    /// Gets the entry for a given leaf (function) and sub-leaf (index) for in-place manipulation.
+    pub fn entry(&mut self, leaf: u32, sub_leaf: u32) -> Entry<'_> {
+        match self.position(leaf, sub_leaf) {
+            Some(index) => Entry::Occupied(OccupiedEntry { cpuid: self, index }),
+            None => Entry::Vacant(VacantEntry {
+                cpuid: self,
+                leaf,
+                sub_leaf,
+            }),
+        }
+    }
+}
+impl Default for RawCpuid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl fmt::Debug for RawCpuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawCpuid")
+            .field("nent", &self.nent())
+            .field("entries", &self.as_slice())
+            .finish()
+    }
+}
+/// Serializes as a sequence of entries.
+impl Serialize for RawCpuid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for RawCpuid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<RawCpuidEntry>::deserialize(deserializer).map(Self::from)
+    }
+}
+impl From<Vec<RawCpuidEntry>> for RawCpuid {
+    fn from(entries: Vec<RawCpuidEntry>) -> Self {
+        let mut buffer = Vec::with_capacity(HEADER_LEN + entries.len() * ENTRY_LEN);
+        buffer.push(u32::try_from(entries.len()).unwrap());
+        buffer.push(0);
+        for entry in entries {
+            buffer.extend_from_slice(&entry.to_words());
+        }
+        Self { buffer }
+    }
+}
+impl FromIterator<RawCpuidEntry> for RawCpuid {
+    fn from_iter<I: IntoIterator<Item = RawCpuidEntry>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
     }
 }
 impl Index<usize> for RawCpuid {
@@ -52,47 +292,352 @@ impl Index<usize> for RawCpuid {
 
     /// Indexes across the entries.
     fn index(&self, index: usize) -> &Self::Output {
-        assert!(u32::try_from(index).unwrap() < self.nent);
-        unsafe { &*self.entries.add(index) }
+        &self.as_slice()[index]
     }
 }
-// We implement custom drop which drops all entries using `self.nent`
-impl Drop for RawCpuid {
-    fn drop(&mut self) {
-        unsafe {
-            std::alloc::dealloc(
-                self.entries.cast::<u8>(),
-                Layout::array::<RawCpuidEntry>(self.nent as usize).unwrap(),
-            );
+impl IndexMut<usize> for RawCpuid {
+    /// Mutably indexes across the entries.
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.as_mut_slice()[index]
+    }
+}
+impl<'a> IntoIterator for &'a RawCpuid {
+    type IntoIter = std::slice::Iter<'a, RawCpuidEntry>;
+    type Item = &'a RawCpuidEntry;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl AsRef<[RawCpuidEntry]> for RawCpuid {
+    fn as_ref(&self) -> &[RawCpuidEntry] {
+        self.as_slice()
+    }
+}
+
+/// A borrowed view of CPUID entries, e.g. those of a [`RawCpuid`], [`kvm_bindings::CpuId`] or
+/// [`kvm_bindings::kvm_cpuid2`], which reads them without copying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawCpuidRef<'a> {
+    entries: &'a [RawCpuidEntry],
+}
+// `kvm_cpuid_entry2` and `RawCpuidEntry` must have identical layouts to view one as the other.
+const _: () = assert!(
+    std::mem::size_of::<kvm_bindings::kvm_cpuid_entry2>() == std::mem::size_of::<RawCpuidEntry>()
+        && std::mem::align_of::<kvm_bindings::kvm_cpuid_entry2>()
+            == std::mem::align_of::<RawCpuidEntry>()
+);
+impl<'a> RawCpuidRef<'a> {
+    /// Constructs a view of the entries of a
+    /// [`kvm_cpuid2`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/include/uapi/asm/kvm.h#L226)
+    /// .
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a `kvm_cpuid2` followed by `nent` entries, which remain valid and
+    /// unmodified for `'a`.
+    #[must_use]
+    pub unsafe fn from_ffi_ptr(ptr: *const kvm_bindings::kvm_cpuid2) -> Self {
+        let nent = (*ptr).nent as usize;
+        Self {
+            entries: std::slice::from_raw_parts(
+                (*ptr).entries.as_ptr().cast::<RawCpuidEntry>(),
+                nent,
+            ),
+        }
+    }
+
+    /// Number of entries.
+    #[must_use]
+    pub fn len(self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Yields an iterator across the entries.
+    pub fn iter(self) -> std::slice::Iter<'a, RawCpuidEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns an entry for a given leaf (function) and sub-leaf (index).
+    ///
+    /// See [`RawCpuid::get`].
+    #[must_use]
+    pub fn get(self, leaf: u32, sub_leaf: u32) -> Option<&'a RawCpuidEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.matches(leaf, sub_leaf))
+    }
+
+    /// Returns what the `CPUID` instruction would return for a given `eax` (leaf) and `ecx`
+    /// (sub-leaf).
+    ///
+    /// See [`RawCpuid::query`].
+    #[must_use]
+    pub fn query(self, eax: u32, ecx: u32) -> CpuidResult {
+        if let Some(entry) = self.get(eax, ecx).or_else(|| self.out_of_range(eax, ecx)) {
+            return CpuidResult::from(entry);
+        }
+        let mut result = CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: 0,
+            edx: 0,
+        };
+        if eax == 0xB || eax == 0x1F {
+            if let Some(entry) = self.get(eax, 1) {
+                result.ecx = ecx & 0xFF;
+                result.edx = entry.edx;
+            }
         }
+        result
+    }
+
+    /// Returns the entry used for a leaf (function) beyond the limit of its range.
+    fn out_of_range(self, leaf: u32, sub_leaf: u32) -> Option<&'a RawCpuidEntry> {
+        let basic = self.get(0, 0)?;
+        if ZERO_OUT_OF_RANGE_VENDORS.contains(&[basic.ebx, basic.edx, basic.ecx]) {
+            return None;
+        }
+        let class = match leaf {
+            0x4000_0000..=0x4FFF_FFFF => leaf & 0xFFFF_FF00,
+            0xC000_0000.. => 0xC000_0000,
+            _ => leaf & 0x8000_0000,
+        };
+        if self.get(class, 0).is_some_and(|class| leaf <= class.eax) {
+            return None;
+        }
+        self.get(basic.eax, sub_leaf)
     }
 }
-pub struct RawCpuidIter<'a> {
-    cpuid: &'a RawCpuid,
-    count: usize,
+impl<'a> From<&'a RawCpuid> for RawCpuidRef<'a> {
+    fn from(cpuid: &'a RawCpuid) -> Self {
+        cpuid.view()
+    }
 }
-impl<'a> Iterator for RawCpuidIter<'a> {
+impl<'a> From<&'a kvm_bindings::CpuId> for RawCpuidRef<'a> {
+    fn from(cpuid: &'a kvm_bindings::CpuId) -> Self {
+        let entries = cpuid.as_slice();
+        // SAFETY: `kvm_cpuid_entry2` and `RawCpuidEntry` have identical layouts, and any bit
+        // pattern is a valid `RawCpuidEntry`.
+        Self {
+            entries: unsafe {
+                std::slice::from_raw_parts(entries.as_ptr().cast::<RawCpuidEntry>(), entries.len())
+            },
+        }
+    }
+}
+impl AsRef<[RawCpuidEntry]> for RawCpuidRef<'_> {
+    fn as_ref(&self) -> &[RawCpuidEntry] {
+        self.entries
+    }
+}
+impl Index<usize> for RawCpuidRef<'_> {
+    type Output = RawCpuidEntry;
+
+    /// Indexes across the entries.
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.entries[index]
+    }
+}
+impl<'a> IntoIterator for RawCpuidRef<'a> {
+    type IntoIter = std::slice::Iter<'a, RawCpuidEntry>;
     type Item = &'a RawCpuidEntry;
 
-    // next() is the only required method
-    fn next(&mut self) -> Option<Self::Item> {
-        // Increment our count. This is why we started at zero.
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl From<RawCpuidRef<'_>> for RawCpuid {
+    fn from(cpuid: RawCpuidRef<'_>) -> Self {
+        cpuid.iter().cloned().collect()
+    }
+}
+
+/// Error returned when adding an entry to a [`RawCpuid`] already holding
+/// [`KVM_MAX_CPUID_ENTRIES`] entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError;
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot exceed the maximum of {KVM_MAX_CPUID_ENTRIES} CPUID entries"
+        )
+    }
+}
+impl std::error::Error for CapacityError {}
 
-        let rtn = if self.count == self.cpuid.nent as usize {
-            None
-        } else {
-            Some(unsafe { &*self.cpuid.entries.add(self.count) })
-        };
-        self.count += 1;
-        rtn
+/// A view into a single entry of a [`RawCpuid`], which may either be vacant or occupied.
+///
+/// Constructed by [`RawCpuid::entry`].
+#[derive(Debug)]
+pub enum Entry<'a> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'a>),
+}
+impl<'a> Entry<'a> {
+    /// Ensures a value is in the entry by inserting one with the given registers if vacant,
+    /// returning a mutable reference to it.
+    ///
+    /// # Errors
+    ///
+    /// When inserting would exceed [`KVM_MAX_CPUID_ENTRIES`] entries.
+    pub fn or_insert(
+        self,
+        eax: u32,
+        ebx: u32,
+        ecx: u32,
+        edx: u32,
+    ) -> Result<&'a mut RawCpuidEntry, CapacityError> {
+        match self {
+            Self::Occupied(entry) => Ok(entry.into_mut()),
+            Self::Vacant(entry) => entry.insert(eax, ebx, ecx, edx),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting one with zeroed registers if vacant,
+    /// returning a mutable reference to it.
+    ///
+    /// # Errors
+    ///
+    /// When inserting would exceed [`KVM_MAX_CPUID_ENTRIES`] entries.
+    pub fn or_default(self) -> Result<&'a mut RawCpuidEntry, CapacityError> {
+        self.or_insert(0, 0, 0, 0)
+    }
+
+    /// Provides in-place mutable access to an occupied entry.
+    #[must_use]
+    pub fn and_modify<F: FnOnce(&mut RawCpuidEntry)>(self, f: F) -> Self {
+        match self {
+            Self::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Self::Occupied(entry)
+            }
+            Self::Vacant(entry) => Self::Vacant(entry),
+        }
+    }
+}
+
+/// A view into an occupied entry of a [`RawCpuid`].
+#[derive(Debug)]
+pub struct OccupiedEntry<'a> {
+    cpuid: &'a mut RawCpuid,
+    index: usize,
+}
+impl<'a> OccupiedEntry<'a> {
+    /// Gets a reference to the entry.
+    #[must_use]
+    pub fn get(&self) -> &RawCpuidEntry {
+        &self.cpuid[self.index]
+    }
+
+    /// Gets a mutable reference to the entry.
+    pub fn get_mut(&mut self) -> &mut RawCpuidEntry {
+        &mut self.cpuid[self.index]
+    }
+
+    /// Converts into a mutable reference to the entry, with the lifetime of the [`RawCpuid`].
+    #[must_use]
+    pub fn into_mut(self) -> &'a mut RawCpuidEntry {
+        &mut self.cpuid[self.index]
+    }
+
+    /// Removes the entry, returning it.
+    #[allow(clippy::missing_panics_doc, clippy::must_use_candidate)]
+    pub fn remove(self) -> RawCpuidEntry {
+        let RawCpuidEntry {
+            function, index, ..
+        } = self.cpuid[self.index];
+        self.cpuid.remove(function, index).unwrap()
+    }
+}
+
+/// A view into a vacant entry of a [`RawCpuid`].
+#[derive(Debug)]
+pub struct VacantEntry<'a> {
+    cpuid: &'a mut RawCpuid,
+    leaf: u32,
+    sub_leaf: u32,
+}
+impl<'a> VacantEntry<'a> {
+    /// Appends an entry with the given registers, returning a mutable reference to it.
+    ///
+    /// The flags of the entry are set with [`KvmCpuidFlags::for_leaf`].
+    ///
+    /// # Errors
+    ///
+    /// When appending would exceed [`KVM_MAX_CPUID_ENTRIES`] entries.
+    pub fn insert(
+        self,
+        eax: u32,
+        ebx: u32,
+        ecx: u32,
+        edx: u32,
+    ) -> Result<&'a mut RawCpuidEntry, CapacityError> {
+        let entry = RawCpuidEntry::new(
+            self.leaf,
+            self.sub_leaf,
+            KvmCpuidFlags::for_leaf(self.leaf),
+            eax,
+            ebx,
+            ecx,
+            edx,
+        );
+        self.cpuid.push(&entry)
+    }
+}
+
+bitflags! {
+    /// The flags of a
+    /// [`kvm_cpuid_entry2`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/include/uapi/asm/kvm.h#L211)
+    /// .
+    #[repr(C)]
+    pub struct KvmCpuidFlags: u32 {
+        /// The entry only applies to its sub-leaf (index), otherwise it applies to all sub-leaves.
+        const SIGNIFICANT_INDEX = KVM_CPUID_FLAG_SIGNIFCANT_INDEX;
+        /// The leaf (function) returns different values on successive invocations.
+        const STATEFUL_FUNC = KVM_CPUID_FLAG_STATEFUL_FUNC;
+        /// The entry of a stateful leaf (function) to be returned on the next invocation.
+        const STATE_READ_NEXT = KVM_CPUID_FLAG_STATE_READ_NEXT;
+    }
+}
+impl KvmCpuidFlags {
+    /// Returns the flags KVM sets on entries of a given leaf (function).
+    ///
+    /// Mirrors `cpuid_function_is_indexed` in
+    /// [`arch/x86/kvm/cpuid.c`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/kvm/cpuid.c).
+    #[must_use]
+    pub fn for_leaf(leaf: u32) -> Self {
+        match leaf {
+            0x4 | 0x7 | 0xB | 0xD | 0xF | 0x10 | 0x12 | 0x14 | 0x17 | 0x18 | 0x1D | 0x1E | 0x1F
+            | 0x8000_001D => Self::SIGNIFICANT_INDEX,
+            _ => Self::empty(),
+        }
+    }
+}
+impl Default for KvmCpuidFlags {
+    fn default() -> Self {
+        Self::empty()
     }
 }
-#[derive(Debug, Clone, Eq, PartialEq)]
+
+/// Serializes as `{leaf, subleaf, flags, eax, ebx, ecx, edx}` with each value as a hex string
+/// (e.g. `"0x0000000d"`).
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(from = "HexCpuidEntry", into = "HexCpuidEntry")]
 #[repr(C)]
 pub struct RawCpuidEntry {
     pub function: u32,
     pub index: u32,
-    pub flags: u32,
+    pub flags: KvmCpuidFlags,
     pub eax: u32,
     pub ebx: u32,
     pub ecx: u32,
@@ -104,7 +649,7 @@ impl RawCpuidEntry {
     pub fn new(
         function: u32,
         index: u32,
-        flags: u32,
+        flags: KvmCpuidFlags,
         eax: u32,
         ebx: u32,
         ecx: u32,
@@ -121,26 +666,150 @@ impl RawCpuidEntry {
             padding: Default::default(),
         }
     }
+
+    /// Returns `true` if KVM would use this entry for a given leaf (function) and sub-leaf
+    /// (index).
+    ///
+    /// Mirrors `cpuid_entry2_find` in
+    /// [`arch/x86/kvm/cpuid.c`](https://elixir.bootlin.com/linux/v5.10.129/source/arch/x86/kvm/cpuid.c),
+    /// the sub-leaf is only compared when [`KvmCpuidFlags::SIGNIFICANT_INDEX`] is set.
+    #[must_use]
+    pub fn matches(&self, leaf: u32, sub_leaf: u32) -> bool {
+        self.function == leaf
+            && (!self.flags.contains(KvmCpuidFlags::SIGNIFICANT_INDEX) || self.index == sub_leaf)
+    }
+
+    /// Returns the entry as the `u32`s making up its memory layout.
+    fn to_words(&self) -> [u32; ENTRY_LEN] {
+        let [p0, p1, p2] = self.padding;
+        [
+            self.function,
+            self.index,
+            self.flags.bits(),
+            self.eax,
+            self.ebx,
+            self.ecx,
+            self.edx,
+            p0,
+            p1,
+            p2,
+        ]
+    }
 }
-impl From<kvm_bindings::CpuId> for RawCpuid {
-    fn from(value: kvm_bindings::CpuId) -> Self {
-        // As cannot acquire ownership of the underlying slice, we clone it.
-        let cloned = value.as_slice().to_vec();
-        let (ptr, len, _cap) = vec_into_raw_parts(cloned);
+/// A `u32` (de)serialized as a `0x` prefixed, zero padded, hex string.
+#[derive(Debug, Clone, Copy)]
+struct Hex(u32);
+impl Serialize for Hex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        format!("{:#010x}", self.0).serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Hex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        let digits = raw
+            .strip_prefix("0x")
+            .ok_or_else(|| serde::de::Error::custom(format!("`{raw}` is missing `0x` prefix")))?;
+        u32::from_str_radix(digits, 16)
+            .map(Self)
+            .map_err(|err| serde::de::Error::custom(format!("`{raw}` is not a hex u32: {err}")))
+    }
+}
+
+/// The serde representation of [`RawCpuidEntry`].
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HexCpuidEntry {
+    leaf: Hex,
+    subleaf: Hex,
+    flags: Hex,
+    eax: Hex,
+    ebx: Hex,
+    ecx: Hex,
+    edx: Hex,
+}
+impl From<HexCpuidEntry> for RawCpuidEntry {
+    fn from(entry: HexCpuidEntry) -> Self {
+        Self::new(
+            entry.leaf.0,
+            entry.subleaf.0,
+            // We use `from_bits_unchecked` over `from_bits` here so flags unknown to us round
+            // trip unchanged.
+            unsafe { KvmCpuidFlags::from_bits_unchecked(entry.flags.0) },
+            entry.eax.0,
+            entry.ebx.0,
+            entry.ecx.0,
+            entry.edx.0,
+        )
+    }
+}
+impl From<RawCpuidEntry> for HexCpuidEntry {
+    fn from(entry: RawCpuidEntry) -> Self {
         Self {
-            nent: u32::try_from(len).unwrap(),
-            padding: Default::default(),
-            entries: ptr.cast::<RawCpuidEntry>(),
+            leaf: Hex(entry.function),
+            subleaf: Hex(entry.index),
+            flags: Hex(entry.flags.bits()),
+            eax: Hex(entry.eax),
+            ebx: Hex(entry.ebx),
+            ecx: Hex(entry.ecx),
+            edx: Hex(entry.edx),
         }
     }
 }
+
+impl From<&kvm_bindings::kvm_cpuid_entry2> for RawCpuidEntry {
+    fn from(entry: &kvm_bindings::kvm_cpuid_entry2) -> Self {
+        Self {
+            function: entry.function,
+            index: entry.index,
+            // We use `from_bits_unchecked` over `from_bits` here as KVM may set flags unknown to
+            // us, which we want to pass back unchanged.
+            flags: unsafe { KvmCpuidFlags::from_bits_unchecked(entry.flags) },
+            eax: entry.eax,
+            ebx: entry.ebx,
+            ecx: entry.ecx,
+            edx: entry.edx,
+            padding: entry.padding,
+        }
+    }
+}
+impl From<&RawCpuidEntry> for kvm_bindings::kvm_cpuid_entry2 {
+    fn from(entry: &RawCpuidEntry) -> Self {
+        Self {
+            function: entry.function,
+            index: entry.index,
+            flags: entry.flags.bits(),
+            eax: entry.eax,
+            ebx: entry.ebx,
+            ecx: entry.ecx,
+            edx: entry.edx,
+            padding: entry.padding,
+        }
+    }
+}
+impl From<kvm_bindings::CpuId> for RawCpuid {
+    /// Copies the entries, use [`RawCpuidRef::from`] to read them without copying.
+    fn from(value: kvm_bindings::CpuId) -> Self {
+        Self::from(RawCpuidRef::from(&value))
+    }
+}
 impl From<RawCpuid> for kvm_bindings::CpuId {
     fn from(this: RawCpuid) -> Self {
-        let cpuid_slice = unsafe { std::slice::from_raw_parts(this.entries, this.nent as usize) };
-        // println!("cpuid_slice: {:?}",cpuid_slice);
-        #[allow(clippy::transmute_ptr_to_ptr)]
-        let kvm_bindings_slice = unsafe { std::mem::transmute(cpuid_slice) };
-        kvm_bindings::CpuId::from_entries(kvm_bindings_slice).unwrap()
+        let entries = this
+            .iter()
+            .map(kvm_bindings::kvm_cpuid_entry2::from)
+            .collect::<Vec<_>>();
+        kvm_bindings::CpuId::from_entries(&entries).unwrap()
+    }
+}
+impl From<&RawCpuidEntry> for CpuidResult {
+    fn from(entry: &RawCpuidEntry) -> Self {
+        Self {
+            eax: entry.eax,
+            ebx: entry.ebx,
+            ecx: entry.ecx,
+            edx: entry.edx,
+        }
     }
 }
 impl From<RawCpuidEntry> for (u32, u32, u32, u32) {
@@ -161,12 +830,433 @@ impl fmt::LowerHex for RawCpuidEntry {
     }
 }
 
-#[cfg(tests)]
+/// Parses a fixture of `function index flags eax ebx ecx edx` lines.
+#[cfg(test)]
+pub(crate) fn fixture(fixture: &str) -> RawCpuid {
+    fixture
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| {
+            let words = line
+                .split_whitespace()
+                .map(|word| u32::from_str_radix(word.trim_start_matches("0x"), 16).unwrap())
+                .collect::<Vec<_>>();
+            let [function, index, flags, eax, ebx, ecx, edx] = words[..] else {
+                panic!("malformed fixture line: {line}");
+            };
+            RawCpuidEntry::new(
+                function,
+                index,
+                KvmCpuidFlags::from_bits(flags).unwrap(),
+                eax,
+                ebx,
+                ecx,
+                edx,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
 mod tests {
+    use super::*;
+
+    fn entries() -> Vec<RawCpuidEntry> {
+        vec![
+            RawCpuidEntry::new(
+                0,
+                0,
+                KvmCpuidFlags::empty(),
+                0xd,
+                0x756e_6547,
+                0x6c65_746e,
+                0x4965_6e69,
+            ),
+            RawCpuidEntry::new(
+                7,
+                0,
+                KvmCpuidFlags::SIGNIFICANT_INDEX,
+                0,
+                0x029c_67af,
+                0x4000_0004,
+                0xbc00_0400,
+            ),
+        ]
+    }
+
+    #[test]
+    fn ffi_layout() {
+        let cpuid = RawCpuid::from(entries());
+        assert_eq!(cpuid.nent(), 2);
+        assert_eq!(cpuid.len(), 2);
+        assert_eq!(cpuid[1], entries()[1]);
+        assert_eq!(cpuid.get(7, 0), Some(&entries()[1]));
+
+        let ptr = cpuid.as_ffi_ptr();
+        let kvm_cpuid = unsafe { &*ptr };
+        assert_eq!(kvm_cpuid.nent, 2);
+        let kvm_entries = unsafe { kvm_cpuid.entries.as_slice(kvm_cpuid.nent as usize) };
+        assert_eq!(
+            kvm_entries
+                .iter()
+                .map(RawCpuidEntry::from)
+                .collect::<Vec<_>>(),
+            entries()
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let cpuid = RawCpuid::default();
+        assert!(cpuid.is_empty());
+        assert_eq!(cpuid.nent(), 0);
+        assert_eq!(cpuid.iter().count(), 0);
+    }
+
+    #[test]
+    fn mutation() {
+        let mut cpuid = RawCpuid::from(entries());
+
+        cpuid.get_mut(7, 0).unwrap().ebx = 0;
+        assert_eq!(cpuid[1].ebx, 0);
+        cpuid[1].ebx = 1;
+        assert_eq!(cpuid.get(7, 0).unwrap().ebx, 1);
+
+        // Replaces in place.
+        let replacement = RawCpuidEntry::new(0, 0, KvmCpuidFlags::empty(), 0x1f, 0, 0, 0);
+        assert_eq!(
+            cpuid.insert(replacement.clone()),
+            Ok(Some(entries()[0].clone()))
+        );
+        assert_eq!(cpuid[0], replacement);
+        assert_eq!(cpuid.len(), 2);
+
+        // Appends.
+        let appended = RawCpuidEntry::new(1, 0, KvmCpuidFlags::empty(), 0x906ea, 0, 0, 0);
+        assert_eq!(cpuid.insert(appended.clone()), Ok(None));
+        assert_eq!(cpuid.nent(), 3);
+        assert_eq!(cpuid[2], appended);
+
+        cpuid.sort();
+        assert_eq!(
+            cpuid.iter().map(|e| e.function).collect::<Vec<_>>(),
+            [0, 1, 7]
+        );
+
+        assert_eq!(cpuid.remove(1, 0), Some(appended));
+        assert_eq!(cpuid.remove(1, 0), None);
+        assert_eq!(cpuid.nent(), 2);
+        assert_eq!(cpuid[1].function, 7);
+
+        cpuid.retain(|e| e.function != 7);
+        assert_eq!(cpuid.nent(), 1);
+        assert_eq!(cpuid.get(7, 0), None);
+    }
+
+    #[test]
+    fn dedup() {
+        let mut duplicated = entries();
+        duplicated.push(RawCpuidEntry::new(0, 0, KvmCpuidFlags::empty(), 0, 0, 0, 0));
+        duplicated.extend(entries());
+        let mut cpuid = RawCpuid::from(duplicated);
+        cpuid.dedup();
+        assert_eq!(cpuid, RawCpuid::from(entries()));
+    }
+
+    #[test]
+    fn entry() {
+        let mut cpuid = RawCpuid::from(entries());
+
+        let entry = cpuid
+            .entry(7, 0)
+            .and_modify(|e| e.ecx = 0)
+            .or_default()
+            .unwrap();
+        entry.edx = 0;
+        assert_eq!(cpuid[1].ecx, 0);
+        assert_eq!(cpuid[1].edx, 0);
+
+        cpuid.entry(7, 1).or_insert(1, 2, 3, 4).unwrap();
+        assert_eq!(
+            cpuid[2],
+            RawCpuidEntry::new(7, 1, KvmCpuidFlags::SIGNIFICANT_INDEX, 1, 2, 3, 4)
+        );
+
+        match cpuid.entry(0, 0) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), entries()[0]),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert_eq!(cpuid.len(), 2);
+        assert!(matches!(cpuid.entry(0, 0), Entry::Vacant(_)));
+    }
+
+    #[test]
+    fn kvm_matching() {
+        let mut cpuid = RawCpuid::from(entries());
+
+        // Any sub-leaf matches an entry without a significant index.
+        assert_eq!(cpuid.get(0, 3), Some(&entries()[0]));
+        assert_eq!(cpuid.get(7, 0), Some(&entries()[1]));
+        assert_eq!(cpuid.get(7, 1), None);
+        assert!(cpuid.get_mut(0, 1).is_some());
+        assert!(cpuid.get_mut(7, 1).is_none());
+
+        // Keyed operations still use the exact sub-leaf.
+        assert!(matches!(cpuid.entry(0, 1), Entry::Vacant(_)));
+        assert_eq!(cpuid.remove(0, 1), None);
+
+        assert_eq!(KvmCpuidFlags::for_leaf(0), KvmCpuidFlags::empty());
+        assert_eq!(
+            KvmCpuidFlags::for_leaf(0xB),
+            KvmCpuidFlags::SIGNIFICANT_INDEX
+        );
+        assert_eq!(
+            KvmCpuidFlags::for_leaf(0x8000_001D),
+            KvmCpuidFlags::SIGNIFICANT_INDEX
+        );
+        cpuid.entry(0xD, 1).or_default().unwrap();
+        assert_eq!(cpuid[2].flags, KvmCpuidFlags::SIGNIFICANT_INDEX);
+
+        // Unknown flags pass through the KVM conversions unchanged.
+        let kvm_entry = kvm_bindings::kvm_cpuid_entry2 {
+            function: 2,
+            flags: KVM_CPUID_FLAG_STATEFUL_FUNC | 0x80,
+            ..Default::default()
+        };
+        let entry = RawCpuidEntry::from(&kvm_entry);
+        assert!(entry.flags.contains(KvmCpuidFlags::STATEFUL_FUNC));
+        assert_eq!(
+            kvm_bindings::kvm_cpuid_entry2::from(&entry).flags,
+            kvm_entry.flags
+        );
+    }
+
+    fn result(eax: u32, ebx: u32, ecx: u32, edx: u32) -> CpuidResult {
+        CpuidResult { eax, ebx, ecx, edx }
+    }
+
+    #[test]
+    fn query_intel() {
+        let cpuid = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let highest_basic = result(0, 0, 0, 0);
+
+        assert_eq!(
+            cpuid.query(0, 0),
+            result(0x20, 0x756e_6547, 0x6c65_746e, 0x4965_6e69)
+        );
+        // Any sub-leaf of a leaf without a significant index.
+        assert_eq!(cpuid.query(1, 7), cpuid.query(1, 0));
+        assert_eq!(
+            cpuid.query(4, 3),
+            result(0x163, 0x04c0_003f, 0x0003_bfff, 0x4)
+        );
+        // Invalid sub-leaves.
+        assert_eq!(cpuid.query(7, 3), result(0, 0, 0, 0));
+        assert_eq!(cpuid.query(0xD, 3), result(0, 0, 0, 0));
+        // Out-of-range basic leaves return the highest basic leaf.
+        assert_eq!(cpuid.query(0x21, 0), highest_basic);
+        assert_eq!(
+            cpuid.query(0x21, 0),
+            CpuidResult::from(cpuid.get(0x20, 0).unwrap())
+        );
+        // Extended range limit.
+        assert_eq!(
+            cpuid.query(0x8000_0008, 0),
+            result(0x392e, 0x0100_d200, 0, 0)
+        );
+        assert_eq!(cpuid.query(0x8000_0009, 0), highest_basic);
+        // Hypervisor range limit.
+        assert_eq!(
+            cpuid.query(0x4000_0000, 0),
+            result(0x4000_0001, 0x4b4d_564b, 0x564b_4d56, 0x4d)
+        );
+        assert_eq!(cpuid.query(0x4000_0002, 0), highest_basic);
+        // Ranges without a limit leaf are entirely out of range.
+        assert_eq!(cpuid.query(0x4000_0100, 0), highest_basic);
+        assert_eq!(cpuid.query(0xC000_0000, 0), highest_basic);
+    }
+
+    #[test]
+    fn query_intel_max_basic_redirect() {
+        let mut cpuid = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        // Limit the basic range to leaf 7, so leaves without an entry return leaf 7 at the same
+        // sub-leaf.
+        cpuid.get_mut(0, 0).unwrap().eax = 7;
+        assert_eq!(cpuid.query(0x21, 0), cpuid.query(7, 0));
+        assert_eq!(cpuid.query(0x21, 1), cpuid.query(7, 1));
+        assert_eq!(cpuid.query(0x9000_0000, 2), cpuid.query(7, 2));
+        assert_eq!(cpuid.query(0x21, 3), result(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn query_amd() {
+        let cpuid = fixture(include_str!("../fixtures/amd-zen2.txt"));
+        let zero = result(0, 0, 0, 0);
+
+        assert_eq!(
+            cpuid.query(0, 0),
+            result(0x10, 0x6874_7541, 0x444d_4163, 0x6974_6e65)
+        );
+        assert_eq!(cpuid.query(0xD, 1), result(0xf, 0x340, 0, 0));
+        // Invalid sub-leaves.
+        assert_eq!(cpuid.query(0xD, 2), zero);
+        // Out-of-range leaves return zeros.
+        assert_eq!(cpuid.query(0x11, 0), zero);
+        assert_eq!(
+            cpuid.query(0x8000_001F, 0),
+            result(0x0001_000f, 0x16f, 0x1fd, 0x1)
+        );
+        assert_eq!(cpuid.query(0x8000_0020, 0), zero);
+        assert_eq!(cpuid.query(0x4000_0002, 0), zero);
+        // Leaves within range without an entry.
+        assert_eq!(cpuid.query(0x8000_0002, 0), zero);
+    }
+
+    #[test]
+    fn query_topology() {
+        let mut cpuid = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        cpuid
+            .insert(RawCpuidEntry::new(
+                0xB,
+                1,
+                KvmCpuidFlags::SIGNIFICANT_INDEX,
+                0x4,
+                0x8,
+                0x201,
+                0x3,
+            ))
+            .unwrap();
+        // Undefined sub-leaves of leaf 0xB still pass through `ecx[7:0]` and the x2APIC ID.
+        assert_eq!(cpuid.query(0xB, 5), result(0, 0, 5, 3));
+        // Leaf 0x1F has no sub-leaf 1.
+        assert_eq!(cpuid.query(0x1F, 5), result(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn serde() {
+        let entry = RawCpuidEntry::new(0xD, 1, KvmCpuidFlags::SIGNIFICANT_INDEX, 0xf, 0x340, 0, 0);
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(
+            json,
+            r#"{"leaf":"0x0000000d","subleaf":"0x00000001","flags":"0x00000001","eax":"0x0000000f","ebx":"0x00000340","ecx":"0x00000000","edx":"0x00000000"}"#
+        );
+        assert_eq!(serde_json::from_str::<RawCpuidEntry>(&json).unwrap(), entry);
+
+        for cpuid in [
+            fixture(include_str!("../fixtures/kvm-intel-xeon.txt")),
+            fixture(include_str!("../fixtures/amd-zen2.txt")),
+            RawCpuid::new(),
+        ] {
+            let json = serde_json::to_string_pretty(&cpuid).unwrap();
+            assert_eq!(serde_json::from_str::<RawCpuid>(&json).unwrap(), cpuid);
+        }
+
+        // Unknown flags round trip.
+        let mut cpuid = RawCpuid::from(entries());
+        cpuid[0].flags = unsafe { KvmCpuidFlags::from_bits_unchecked(0x80) };
+        let json = serde_json::to_string(&cpuid).unwrap();
+        assert_eq!(serde_json::from_str::<RawCpuid>(&json).unwrap(), cpuid);
+    }
+
+    #[test]
+    fn serde_errors() {
+        let entry = |eax: &str| {
+            format!(
+                r#"{{"leaf":"0x0","subleaf":"0x0","flags":"0x0","eax":"{eax}","ebx":"0x0","ecx":"0x0","edx":"0x0"}}"#
+            )
+        };
+        assert!(serde_json::from_str::<RawCpuidEntry>(&entry("0x1")).is_ok());
+        assert!(serde_json::from_str::<RawCpuidEntry>(&entry("1")).is_err());
+        assert!(serde_json::from_str::<RawCpuidEntry>(&entry("0xg")).is_err());
+        assert!(serde_json::from_str::<RawCpuidEntry>(&entry("0x100000000")).is_err());
+        assert!(serde_json::from_str::<RawCpuidEntry>(r#"{"leaf":"0x0"}"#).is_err());
+    }
+
+    #[test]
+    fn view() {
+        let cpuid = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let kvm_cpuid = kvm_bindings::CpuId::from(cpuid.clone());
+
+        let view = RawCpuidRef::from(&kvm_cpuid);
+        // No copy is made.
+        assert_eq!(
+            view.as_ref()
+                .as_ptr()
+                .cast::<kvm_bindings::kvm_cpuid_entry2>(),
+            kvm_cpuid.as_slice().as_ptr()
+        );
+        assert_eq!(view.len(), cpuid.len());
+        assert_eq!(view, cpuid.view());
+        assert_eq!(view.get(7, 0), cpuid.get(7, 0));
+        assert_eq!(view[4], cpuid[4]);
+        for (eax, ecx) in [(0, 0), (4, 2), (7, 3), (0x21, 0), (0x8000_0008, 0)] {
+            assert_eq!(view.query(eax, ecx), cpuid.query(eax, ecx));
+        }
+        assert!(view.iter().eq(&cpuid));
+        assert_eq!(RawCpuid::from(view), cpuid);
+
+        let ffi = unsafe { RawCpuidRef::from_ffi_ptr(cpuid.as_ffi_ptr()) };
+        assert_eq!(ffi, view);
+        assert_eq!(ffi.as_ref().as_ptr(), cpuid.as_slice().as_ptr());
+        assert!(unsafe { RawCpuidRef::from_ffi_ptr(RawCpuid::new().as_ffi_ptr()) }.is_empty());
+    }
+
+    #[test]
+    fn capacity() {
+        let full = (0..KVM_MAX_CPUID_ENTRIES)
+            .map(|i| {
+                RawCpuidEntry::new(
+                    0,
+                    u32::try_from(i).unwrap(),
+                    KvmCpuidFlags::empty(),
+                    0,
+                    0,
+                    0,
+                    0,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut cpuid = RawCpuid::from(full);
+        let extra = RawCpuidEntry::new(1, 0, KvmCpuidFlags::empty(), 0, 0, 0, 0);
+        assert_eq!(cpuid.insert(extra), Err(CapacityError));
+        assert_eq!(cpuid.entry(1, 0).or_default(), Err(CapacityError));
+        assert_eq!(cpuid.len(), KVM_MAX_CPUID_ENTRIES);
+
+        // Replacing does not grow.
+        let replacement = RawCpuidEntry::new(0, 0, KvmCpuidFlags::empty(), 1, 0, 0, 0);
+        assert!(cpuid.insert(replacement).is_ok());
+    }
+
+    #[test]
+    fn worker_thread() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RawCpuid>();
+
+        let cpuid = RawCpuid::from(entries());
+        let template = cpuid.clone();
+        let built = std::thread::spawn(move || {
+            let mut template = template;
+            template.as_mut_slice()[0].eax = 0x1f;
+            template
+        })
+        .join()
+        .unwrap();
+        assert_eq!(cpuid[0].eax, 0xd);
+        assert_eq!(built[0].eax, 0x1f);
+        assert_eq!(built[1], cpuid[1]);
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     fn testing() {
-        use kvm_bindings::{kvm_cpuid_entry2, KVM_MAX_CPUID_ENTRIES};
+        use kvm_bindings::KVM_MAX_CPUID_ENTRIES;
+
+        fn check_err() {
+            let errno = unsafe { libc::__errno_location() };
+            println!("errno: {}", unsafe { *errno });
+            let string = std::ffi::CString::new("get_supported_cpuid").unwrap();
+            unsafe { libc::perror(string.as_ptr()) };
+        }
 
         let kvm = kvm_ioctls::Kvm::new().unwrap();
         let vm = kvm.create_vm().unwrap();
@@ -176,19 +1266,19 @@ mod tests {
 
         println!("kvm_cpuid:");
         for x in kvm_cpuid.clone().as_slice() {
-            println!("\t{:?}", x);
+            println!("\t{x:?}");
         }
 
         let cpuid = RawCpuid::from(kvm_cpuid);
         println!("cpuid:");
-        for x in cpuid.iter() {
-            println!("\t{:?}", x);
+        for x in &cpuid {
+            println!("\t{x:?}");
         }
 
         let kvm_cpuid2: kvm_bindings::CpuId = cpuid.into();
         println!("kvm_cpuid2:");
         for x in kvm_cpuid2.clone().as_slice() {
-            println!("\t{:?}", x);
+            println!("\t{x:?}");
         }
 
         vcpu.set_cpuid2(&kvm_cpuid2).unwrap();
@@ -198,14 +1288,7 @@ mod tests {
         check_err();
         println!("kvm_cpuid 3:");
         for x in kvm_cpuid3.as_slice() {
-            println!("\t{:?}", x);
-        }
-
-        fn check_err() {
-            let errno = unsafe { libc::__errno_location() };
-            println!("errno: {}", unsafe { *errno });
-            let string = std::ffi::CString::new("get_supported_cpuid").unwrap();
-            unsafe { libc::perror(string.as_ptr()) };
+            println!("\t{x:?}");
         }
     }
 }
@@ -0,0 +1,342 @@
+use std::convert::Infallible;
+use std::fmt;
+
+use crate::{CapacityError, Cpuid, Feature, RawCpuid};
+
+/// Pairs of a feature and a feature it requires, after the Linux kernel's
+/// `arch/x86/kernel/cpu/cpuid-deps.c`.
+const DEPENDENCIES: &[(Feature, Feature)] = &[
+    // The SSE chain.
+    (Feature::Sse, Feature::Fxsr),
+    (Feature::Sse2, Feature::Sse),
+    (Feature::Sse3, Feature::Sse2),
+    (Feature::Ssse3, Feature::Sse3),
+    (Feature::Sse41, Feature::Ssse3),
+    (Feature::Sse42, Feature::Sse41),
+    (Feature::Pclmulqdq, Feature::Sse2),
+    (Feature::Aesni, Feature::Sse2),
+    (Feature::Sha, Feature::Sse2),
+    (Feature::Gfni, Feature::Sse2),
+    // XSAVE.
+    (Feature::Osxsave, Feature::Xsave),
+    (Feature::XsaveoptAvailable, Feature::Xsave),
+    (Feature::XsavecCompactedXrstor, Feature::Xsave),
+    (
+        Feature::XsavesXrstorsIa32Xss,
+        Feature::XsavecCompactedXrstor,
+    ),
+    // AVX.
+    (Feature::Avx, Feature::Xsave),
+    (Feature::Avx2, Feature::Avx),
+    (Feature::Fma, Feature::Avx),
+    (Feature::F16c, Feature::Avx),
+    (Feature::Vaes, Feature::Avx),
+    (Feature::Vpclmulqdq, Feature::Avx),
+    (Feature::Vpclmulqdq, Feature::Pclmulqdq),
+    (Feature::AvxVnni, Feature::Avx2),
+    // AVX-512.
+    (Feature::Avx512f, Feature::Avx),
+    (Feature::Avx512dq, Feature::Avx512f),
+    (Feature::Avx512Ifma, Feature::Avx512f),
+    (Feature::Avx512pf, Feature::Avx512f),
+    (Feature::Avx512er, Feature::Avx512f),
+    (Feature::Avx512cd, Feature::Avx512f),
+    (Feature::Avx512bw, Feature::Avx512f),
+    (Feature::Avx512vl, Feature::Avx512f),
+    (Feature::Avx512Vbmi, Feature::Avx512bw),
+    (Feature::Avx512Vbmi2, Feature::Avx512vl),
+    (Feature::Avx512Vnni, Feature::Avx512f),
+    (Feature::Avx512Bitalg, Feature::Avx512vl),
+    (Feature::Avx512Vpopcntdq, Feature::Avx512f),
+    (Feature::Avx5124vnniw, Feature::Avx512f),
+    (Feature::Avx5124fmaps, Feature::Avx512f),
+    (Feature::Avx512Vp2intersect, Feature::Avx512vl),
+    (Feature::Avx512Bf16, Feature::Avx512vl),
+    // Process-context identifiers.
+    (Feature::Invpcid, Feature::Pcid),
+    // Protection keys.
+    (Feature::Ospke, Feature::Pku),
+    // SGX.
+    (Feature::Sgx1, Feature::Sgx),
+    (Feature::Sgx2, Feature::Sgx1),
+    (Feature::SgxLc, Feature::Sgx),
+    // Key Locker.
+    (Feature::Aeskle, Feature::Kl),
+    // AMD memory encryption.
+    (Feature::SevEs, Feature::Sev),
+    (Feature::SevSnp, Feature::SevEs),
+    (Feature::Vmpl, Feature::SevSnp),
+];
+
+impl Feature {
+    /// Returns the features `self` directly requires.
+    pub fn prerequisites(self) -> impl Iterator<Item = Self> {
+        DEPENDENCIES
+            .iter()
+            .filter(move |(feature, _)| *feature == self)
+            .map(|(_, prerequisite)| *prerequisite)
+    }
+
+    /// Returns the features directly requiring `self`.
+    pub fn dependents(self) -> impl Iterator<Item = Self> {
+        DEPENDENCIES
+            .iter()
+            .filter(move |(_, prerequisite)| *prerequisite == self)
+            .map(|(feature, _)| *feature)
+    }
+}
+
+/// A feature which is set while a feature it requires is not, returned by [`Cpuid::validate`]
+/// and [`RawCpuid::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DependencyViolation {
+    /// The feature which is set.
+    pub feature: Feature,
+    /// The feature it requires, which is not set.
+    pub prerequisite: Feature,
+}
+impl fmt::Display for DependencyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is set but `{}`, which it requires, is not",
+            self.feature, self.prerequisite
+        )
+    }
+}
+
+/// How [`Cpuid::normalize`] and [`RawCpuid::normalize`] resolve [`DependencyViolation`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizePolicy {
+    /// Clears the features missing a prerequisite, and in turn their dependents.
+    ClearDependents,
+    /// Sets the missing prerequisites, and in turn their prerequisites.
+    SetPrerequisites,
+}
+
+/// The features of a [`Cpuid`] or [`RawCpuid`].
+trait Features {
+    /// Error type for [`Features::change`].
+    type Error;
+
+    /// Returns if `feature` is set.
+    fn has(&self, feature: Feature) -> bool;
+
+    /// Sets or clears `feature`.
+    fn change(&mut self, feature: Feature, set: bool) -> Result<(), Self::Error>;
+
+    /// Returns the violations, in the order of [`DEPENDENCIES`].
+    fn violations(&self) -> Vec<DependencyViolation> {
+        DEPENDENCIES
+            .iter()
+            .filter(|(feature, prerequisite)| self.has(*feature) && !self.has(*prerequisite))
+            .map(|&(feature, prerequisite)| DependencyViolation {
+                feature,
+                prerequisite,
+            })
+            .collect()
+    }
+
+    /// Resolves the violations until there are none, returning the changed features in the
+    /// order they were changed.
+    fn resolve(&mut self, policy: NormalizePolicy) -> Result<Vec<Feature>, Self::Error> {
+        let mut changed = Vec::new();
+        // Each pass only clears (or only sets) features, so this terminates.
+        loop {
+            let violations = self.violations();
+            if violations.is_empty() {
+                return Ok(changed);
+            }
+            for violation in violations {
+                let (feature, set) = match policy {
+                    NormalizePolicy::ClearDependents => (violation.feature, false),
+                    NormalizePolicy::SetPrerequisites => (violation.prerequisite, true),
+                };
+                if self.has(feature) != set {
+                    self.change(feature, set)?;
+                    changed.push(feature);
+                }
+            }
+        }
+    }
+}
+impl Features for Cpuid {
+    type Error = Infallible;
+
+    fn has(&self, feature: Feature) -> bool {
+        Cpuid::has(self, feature)
+    }
+
+    fn change(&mut self, feature: Feature, set: bool) -> Result<(), Self::Error> {
+        if set {
+            self.set(feature);
+        } else {
+            self.clear(feature);
+        }
+        Ok(())
+    }
+}
+impl Features for RawCpuid {
+    type Error = CapacityError;
+
+    fn has(&self, feature: Feature) -> bool {
+        RawCpuid::has(self, feature)
+    }
+
+    fn change(&mut self, feature: Feature, set: bool) -> Result<(), Self::Error> {
+        if set {
+            self.set(feature)
+        } else {
+            self.clear(feature);
+            Ok(())
+        }
+    }
+}
+
+impl Cpuid {
+    /// Checks every set feature has the features it requires set (see
+    /// [`Feature::prerequisites`]).
+    ///
+    /// # Errors
+    ///
+    /// When any does not, returning every violation.
+    pub fn validate(&self) -> Result<(), Vec<DependencyViolation>> {
+        match self.violations() {
+            violations if violations.is_empty() => Ok(()),
+            violations => Err(violations),
+        }
+    }
+
+    /// Resolves every [`DependencyViolation`] according to `policy`, returning the features
+    /// changed.
+    pub fn normalize(&mut self, policy: NormalizePolicy) -> Vec<Feature> {
+        match self.resolve(policy) {
+            Ok(changed) => changed,
+            Err(infallible) => match infallible {},
+        }
+    }
+}
+
+impl RawCpuid {
+    /// Checks every set feature has the features it requires set (see
+    /// [`Feature::prerequisites`]).
+    ///
+    /// # Errors
+    ///
+    /// When any does not, returning every violation.
+    pub fn validate(&self) -> Result<(), Vec<DependencyViolation>> {
+        match self.violations() {
+            violations if violations.is_empty() => Ok(()),
+            violations => Err(violations),
+        }
+    }
+
+    /// Resolves every [`DependencyViolation`] according to `policy`, returning the features
+    /// changed.
+    ///
+    /// # Errors
+    ///
+    /// When setting a prerequisite would insert an entry exceeding
+    /// [`kvm_bindings::KVM_MAX_CPUID_ENTRIES`] entries.
+    pub fn normalize(&mut self, policy: NormalizePolicy) -> Result<Vec<Feature>, CapacityError> {
+        self.resolve(policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpuid_ffi::fixture;
+
+    #[test]
+    fn graph() {
+        assert_eq!(
+            Feature::Avx2.prerequisites().collect::<Vec<_>>(),
+            [Feature::Avx]
+        );
+        assert!(Feature::Avx
+            .dependents()
+            .any(|feature| feature == Feature::Avx512f));
+        // Acyclic: following prerequisites always ends.
+        for (feature, _) in DEPENDENCIES {
+            let mut pending = vec![(*feature, 0)];
+            while let Some((feature, depth)) = pending.pop() {
+                assert!(depth < DEPENDENCIES.len(), "{feature}");
+                pending.extend(feature.prerequisites().map(|p| (p, depth + 1)));
+            }
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let mut cpuid = Cpuid::default();
+        assert_eq!(cpuid.validate(), Ok(()));
+        cpuid.set(Feature::Avx2);
+        cpuid.set(Feature::Avx512f);
+        cpuid.set(Feature::Avx512bw);
+        let violations = cpuid.validate().unwrap_err();
+        assert_eq!(
+            violations,
+            [
+                DependencyViolation {
+                    feature: Feature::Avx2,
+                    prerequisite: Feature::Avx
+                },
+                DependencyViolation {
+                    feature: Feature::Avx512f,
+                    prerequisite: Feature::Avx
+                },
+            ]
+        );
+        assert_eq!(
+            violations[0].to_string(),
+            "`avx2` is set but `avx`, which it requires, is not"
+        );
+        assert_eq!(RawCpuid::from(cpuid).validate().unwrap_err(), violations);
+    }
+
+    #[test]
+    fn normalize() {
+        let xeon = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let (valid, _) = Cpuid::from_raw_lenient(&xeon);
+        assert_eq!(valid.validate(), Ok(()));
+
+        // Clearing `avx` clears everything built on it.
+        let mut cpuid = valid.clone();
+        cpuid.set(Feature::Xsave);
+        cpuid.set(Feature::Avx2);
+        cpuid.set(Feature::Avx512f);
+        cpuid.set(Feature::Avx512vl);
+        cpuid.set(Feature::Avx512Bf16);
+        cpuid.clear(Feature::Avx);
+        let changed = cpuid.normalize(NormalizePolicy::ClearDependents);
+        assert_eq!(cpuid.validate(), Ok(()));
+        for feature in [
+            Feature::Avx2,
+            Feature::Avx512f,
+            Feature::Avx512vl,
+            Feature::Avx512Bf16,
+        ] {
+            assert!(!cpuid.has(feature));
+            assert!(changed.contains(&feature));
+        }
+        assert!(cpuid.has(Feature::Xsave));
+        assert_eq!(cpuid.normalize(NormalizePolicy::ClearDependents), []);
+
+        // Or restores it, and in turn what it requires.
+        let mut raw = RawCpuid::new();
+        raw.set(Feature::Avx512Vbmi).unwrap();
+        let changed = raw.normalize(NormalizePolicy::SetPrerequisites).unwrap();
+        assert_eq!(raw.validate(), Ok(()));
+        assert!(raw.has(Feature::Avx512Vbmi));
+        for feature in [
+            Feature::Avx512bw,
+            Feature::Avx512f,
+            Feature::Avx,
+            Feature::Xsave,
+        ] {
+            assert!(raw.has(feature));
+            assert!(changed.contains(&feature));
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use std::fmt;
+
+use bit_fields::{Field, FieldKind};
+
+use crate::{register_fields, RawCpuid, RawCpuidEntry, GENUINE_INTEL};
+
+/// A register of a [`RawCpuidEntry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Register {
+    Eax,
+    Ebx,
+    Ecx,
+    Edx,
+}
+impl Register {
+    /// All registers, in order.
+    pub const ALL: [Self; 4] = [Self::Eax, Self::Ebx, Self::Ecx, Self::Edx];
+
+    /// Returns the value of this register in an entry.
+    #[must_use]
+    pub fn of(self, entry: &RawCpuidEntry) -> u32 {
+        match self {
+            Self::Eax => entry.eax,
+            Self::Ebx => entry.ebx,
+            Self::Ecx => entry.ecx,
+            Self::Edx => entry.edx,
+        }
+    }
+
+    /// Returns a mutable reference to this register in an entry.
+    pub fn of_mut(self, entry: &mut RawCpuidEntry) -> &mut u32 {
+        match self {
+            Self::Eax => &mut entry.eax,
+            Self::Ebx => &mut entry.ebx,
+            Self::Ecx => &mut entry.ecx,
+            Self::Edx => &mut entry.edx,
+        }
+    }
+}
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Eax => "eax",
+            Self::Ebx => "ebx",
+            Self::Ecx => "ecx",
+            Self::Edx => "edx",
+        })
+    }
+}
+
+/// A register with a different value in two entries with the same leaf and sub-leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterDiff {
+    /// The leaf (function).
+    pub leaf: u32,
+    /// The sub-leaf (index).
+    pub sub_leaf: u32,
+    /// The register.
+    pub register: Register,
+    /// The value in the [`RawCpuid`] diffed.
+    pub this: u32,
+    /// The value in the [`RawCpuid`] diffed against.
+    pub other: u32,
+    /// The vendor ID (see [`RawCpuid::vendor`]) of the [`RawCpuid`] diffed.
+    pub vendor: [u32; 3],
+}
+impl RegisterDiff {
+    /// Returns a mask of the changed bits.
+    #[must_use]
+    pub fn mask(&self) -> u32 {
+        self.this ^ self.other
+    }
+
+    /// Returns the positions of the changed bits, in ascending order.
+    #[must_use]
+    pub fn bits(&self) -> Vec<u8> {
+        (0..32).filter(|i| self.mask() & (1 << i) != 0).collect()
+    }
+}
+impl fmt::Display for RegisterDiff {
+    /// Renders the change, decoding the changed fields when the leaf has a typed definition for
+    /// the vendor (see [`fields`]).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:#x}.{:#x} {}: {:#010x} -> {:#010x} (xor {:#010x})",
+            self.leaf,
+            self.sub_leaf,
+            self.register,
+            self.this,
+            self.other,
+            self.mask()
+        )?;
+        let mut named = 0;
+        for (ident, start, end) in fields(self.vendor, self.leaf, self.sub_leaf, self.register)
+            .iter()
+            .flat_map(elements)
+        {
+            let len = u32::from(end - start);
+            let mask = u32::MAX.checked_shr(32 - len).unwrap_or(0) << start;
+            if self.mask() & mask == 0 {
+                continue;
+            }
+            named |= mask;
+            let (this, other) = ((self.this & mask) >> start, (self.other & mask) >> start);
+            if len == 1 {
+                writeln!(f, "    {ident} (bit {start}): {this} -> {other}")?;
+            } else {
+                writeln!(
+                    f,
+                    "    {ident} (bits {start}..{end}): {this:#x} -> {other:#x}"
+                )?;
+            }
+        }
+        for bit in self.bits() {
+            if named & (1 << bit) == 0 {
+                let (this, other) = ((self.this >> bit) & 1, (self.other >> bit) & 1);
+                writeln!(f, "    bit {bit}: {this} -> {other}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the fields of a register of a leaf given by [`register_fields`], when the vendor ID
+/// (see [`RawCpuid::vendor`]) is [`GENUINE_INTEL`], otherwise none as other vendors define the
+/// leaves differently.
+pub(crate) fn fields(
+    vendor: [u32; 3],
+    leaf: u32,
+    sub_leaf: u32,
+    register: Register,
+) -> &'static [Field] {
+    if vendor != GENUINE_INTEL {
+        return &[];
+    }
+    register_fields(leaf, sub_leaf).map_or(&[], |fields| {
+        fields[Register::ALL.iter().position(|r| *r == register).unwrap()]
+    })
+}
+
+/// Returns the identifier, first bit and bit following the last bit of each element of a field,
+/// e.g. `f[0]`, `f[1]` for arrays.
+pub(crate) fn elements(field: &Field) -> Vec<(String, u8, u8)> {
+    match field.kind {
+        FieldKind::Array { len, step } => (0..len)
+            .map(|i| {
+                let start = field.start + i * step;
+                (format!("{}[{i}]", field.ident), start, start + step)
+            })
+            .collect(),
+        _ => vec![(String::from(field.ident), field.start, field.end)],
+    }
+}
+
+/// The differences between two [`RawCpuid`]s, given by [`RawCpuid::diff`].
+///
+/// Entries are matched by their exact leaf and sub-leaf, flags are not compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuidDiff {
+    /// Entries only present in the [`RawCpuid`] diffed against.
+    pub added: Vec<RawCpuidEntry>,
+    /// Entries only present in the [`RawCpuid`] diffed.
+    pub removed: Vec<RawCpuidEntry>,
+    /// Registers with different values.
+    pub changed: Vec<RegisterDiff>,
+}
+impl CpuidDiff {
+    /// Returns `true` if there are no differences.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+impl fmt::Display for CpuidDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entry = |f: &mut fmt::Formatter<'_>, sign, entry: &RawCpuidEntry| {
+            writeln!(
+                f,
+                "{sign} {:#x}.{:#x}: eax={:#010x} ebx={:#010x} ecx={:#010x} edx={:#010x}",
+                entry.function, entry.index, entry.eax, entry.ebx, entry.ecx, entry.edx
+            )
+        };
+        for added in &self.added {
+            entry(f, '+', added)?;
+        }
+        for removed in &self.removed {
+            entry(f, '-', removed)?;
+        }
+        for register in &self.changed {
+            write!(f, "{register}")?;
+        }
+        Ok(())
+    }
+}
+
+impl RawCpuid {
+    /// Returns the differences from `self` to `other`, in the order of the entries of `self`
+    /// followed by those only in `other`.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> CpuidDiff {
+        let key = |entry: &RawCpuidEntry| (entry.function, entry.index);
+        let find = |cpuid: &Self, entry: &RawCpuidEntry| {
+            cpuid.iter().find(|e| key(e) == key(entry)).cloned()
+        };
+
+        let vendor = self.vendor();
+        let mut diff = CpuidDiff::default();
+        for this in self {
+            match find(other, this) {
+                Some(other) => diff.changed.extend(
+                    Register::ALL
+                        .into_iter()
+                        .filter(|register| register.of(this) != register.of(&other))
+                        .map(|register| RegisterDiff {
+                            leaf: this.function,
+                            sub_leaf: this.index,
+                            register,
+                            this: register.of(this),
+                            other: register.of(&other),
+                            vendor,
+                        }),
+                ),
+                None => diff.removed.push(this.clone()),
+            }
+        }
+        diff.added = other
+            .iter()
+            .filter(|entry| find(self, entry).is_none())
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpuid_ffi::fixture;
+    use crate::KvmCpuidFlags;
+
+    #[test]
+    fn diff() {
+        let host = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        assert!(host.diff(&host).is_empty());
+        assert_eq!(host.diff(&host).to_string(), "");
+
+        let mut template = host.clone();
+        // Change the stepping.
+        template.get_mut(1, 0).unwrap().eax = 0x000c_06f4;
+        // Set `vmx` (bit 5) and reserved bit 16.
+        template.get_mut(1, 0).unwrap().ecx ^= 1 << 16 | 1 << 5;
+        // Clear `fdp_excptn_only` (bit 6), set `avx512f` (bit 16) and `avx512dq` (bit 17).
+        template.get_mut(7, 0).unwrap().ebx ^= 0b11 << 16 | 1 << 6;
+        template.remove(0x8000_0007, 0);
+        let added = RawCpuidEntry::new(0x8000_001F, 0, KvmCpuidFlags::empty(), 1, 0, 0, 0);
+        template.insert(added.clone()).unwrap();
+
+        let diff = host.diff(&template);
+        assert_eq!(diff.added, [added]);
+        assert_eq!(diff.removed, [host.get(0x8000_0007, 0).unwrap().clone()]);
+        assert_eq!(diff.changed.len(), 3);
+        assert_eq!(diff.changed[0].register, Register::Eax);
+        assert_eq!(diff.changed[0].bits(), [1, 2]);
+        assert_eq!(diff.changed[1].register, Register::Ecx);
+        assert_eq!(diff.changed[2].mask(), 0x0003_0040);
+        assert_eq!(diff.changed[2].bits(), [6, 16, 17]);
+
+        assert_eq!(
+            diff.to_string(),
+            "\
++ 0x8000001f.0x0: eax=0x00000001 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+- 0x80000007.0x0: eax=0x00000000 ebx=0x00000000 ecx=0x00000000 edx=0x00000100
+0x1.0x0 eax: 0x000c06f2 -> 0x000c06f4 (xor 0x00000006)
+    stepping_id (bits 0..4): 0x2 -> 0x4
+0x1.0x0 ecx: 0x81202000 -> 0x81212020 (xor 0x00010020)
+    vmx (bit 5): 0 -> 1
+    bit 16: 0 -> 1
+0x7.0x0 ebx: 0x01802042 -> 0x01832002 (xor 0x00030040)
+    fdp_excptn_only (bit 6): 1 -> 0
+    avx512f (bit 16): 0 -> 1
+    avx512dq (bit 17): 0 -> 1
+"
+        );
+    }
+
+    #[test]
+    fn undefined_leaf() {
+        let this = RawCpuid::from(vec![RawCpuidEntry::new(
+            0x4000_0001,
+            0,
+            KvmCpuidFlags::empty(),
+            0x0100_7efb,
+            0,
+            0,
+            0,
+        )]);
+        let mut other = this.clone();
+        other[0].eax = 0x0100_7efa;
+        assert_eq!(
+            this.diff(&other).to_string(),
+            "0x40000001.0x0 eax: 0x01007efb -> 0x01007efa (xor 0x00000001)\n    bit 0: 1 -> 0\n"
+        );
+    }
+
+    #[test]
+    fn other_vendor() {
+        // Only Intel's leaves are decoded.
+        let this = fixture(include_str!("../fixtures/amd-zen2.txt"));
+        let mut other = this.clone();
+        other.get_mut(7, 0).unwrap().ebx ^= 1 << 16;
+        assert_eq!(
+            this.diff(&other).to_string(),
+            "0x7.0x0 ebx: 0x219c91a9 -> 0x219d91a9 (xor 0x00010000)\n    bit 16: 0 -> 1\n"
+        );
+    }
+}
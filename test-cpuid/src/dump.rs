@@ -0,0 +1,394 @@
+use std::path::Path;
+use std::{fmt, fs, io};
+
+use crate::{KvmCpuidFlags, RawCpuid, RawCpuidEntry};
+
+/// A text format of CPUID dumps, possibly of multiple logical CPUs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// The raw output of the Linux `cpuid` utility (`cpuid -r`), e.g.
+    ///
+    /// ```text
+    /// CPU 0:
+    ///    0x00000007 0x00: eax=0x00000000 ebx=0x029c67af ecx=0x40000004 edx=0xbc000400
+    /// ```
+    ///
+    /// A single CPU (`cpuid -1 -r`) has the header `CPU:`, which may also be omitted.
+    CpuidRaw,
+    /// The format of the [InstLatx64](http://instlatx64.atw.hu/) dumps, e.g.
+    ///
+    /// ```text
+    /// CPU#000 AffMask: 0x0000000000000001
+    /// CPUID 00000007: 00000000-029C67AF-40000004-BC000400 [SL 00]
+    /// ```
+    ///
+    /// `CPU#<n>` headers are optional when there is a single CPU, and the `[SL <sub-leaf>]`
+    /// suffix is optional for sub-leaf 0. Other lines are ignored.
+    InstLatX64,
+}
+
+/// Error type for [`DumpFormat::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDumpError {
+    /// The line number (from 1) of the malformed line.
+    pub line: usize,
+    /// What is wrong with the line.
+    pub message: String,
+}
+impl fmt::Display for ParseDumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+impl std::error::Error for ParseDumpError {}
+
+/// Error type for [`DumpFormat::read`].
+#[derive(Debug)]
+pub enum ReadDumpError {
+    /// Failed to read the file.
+    Io(io::Error),
+    /// Failed to parse the file.
+    Parse(ParseDumpError),
+}
+impl fmt::Display for ReadDumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Failed to read dump: {err}"),
+            Self::Parse(err) => write!(f, "Failed to parse dump: {err}"),
+        }
+    }
+}
+impl std::error::Error for ReadDumpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl DumpFormat {
+    /// Parses a dump into the entries of each CPU, in the order they appear.
+    ///
+    /// The flags of the entries are set with [`KvmCpuidFlags::for_leaf`].
+    ///
+    /// # Errors
+    ///
+    /// When an entry line is malformed, or for [`DumpFormat::CpuidRaw`] when a line is neither
+    /// an entry nor a CPU header.
+    pub fn parse(self, dump: &str) -> Result<Vec<RawCpuid>, ParseDumpError> {
+        let mut cpus: Vec<Vec<RawCpuidEntry>> = Vec::new();
+        for (i, line) in dump.lines().enumerate() {
+            let error = |message: &str| ParseDumpError {
+                line: i + 1,
+                message: format!("{message}: `{line}`"),
+            };
+            let line = line.trim();
+            let parsed = match self {
+                Self::CpuidRaw => parse_cpuid_raw_line(line),
+                Self::InstLatX64 => parse_instlatx64_line(line),
+            };
+            match parsed {
+                Line::Cpu => cpus.push(Vec::new()),
+                Line::Entry(Some(entry)) => match cpus.last_mut() {
+                    Some(cpu) => cpu.push(entry),
+                    // Entries before any header belong to the single CPU of the dump.
+                    None => cpus.push(vec![entry]),
+                },
+                Line::Entry(None) => return Err(error("Malformed entry")),
+                Line::Other if self == Self::CpuidRaw && !line.is_empty() => {
+                    return Err(error("Unrecognized line"));
+                }
+                Line::Other => {}
+            }
+        }
+        Ok(cpus.into_iter().map(RawCpuid::from).collect())
+    }
+
+    /// Reads and parses a dump file, see [`DumpFormat::parse`].
+    ///
+    /// The entries of each CPU are a [`crate::CpuidSource`].
+    ///
+    /// # Errors
+    ///
+    /// When reading the file fails or its content is malformed.
+    pub fn read(self, path: impl AsRef<Path>) -> Result<Vec<RawCpuid>, ReadDumpError> {
+        let dump = fs::read_to_string(path).map_err(ReadDumpError::Io)?;
+        self.parse(&dump).map_err(ReadDumpError::Parse)
+    }
+
+    /// Writes the entries of each CPU as a dump.
+    #[must_use]
+    pub fn write(self, cpus: &[RawCpuid]) -> String {
+        let mut lines = Vec::new();
+        for (cpu, cpuid) in cpus.iter().enumerate() {
+            lines.push(match self {
+                Self::CpuidRaw => format!("CPU {cpu}:\n"),
+                Self::InstLatX64 if cpu > 0 => format!("\nCPU#{cpu:03}\n"),
+                Self::InstLatX64 => format!("CPU#{cpu:03}\n"),
+            });
+            for entry in cpuid {
+                let RawCpuidEntry {
+                    function,
+                    index,
+                    eax,
+                    ebx,
+                    ecx,
+                    edx,
+                    ..
+                } = *entry;
+                lines.push(match self {
+                    Self::CpuidRaw => format!(
+                        "   {function:#010x} {index:#04x}: eax={eax:#010x} ebx={ebx:#010x} \
+                         ecx={ecx:#010x} edx={edx:#010x}\n"
+                    ),
+                    Self::InstLatX64
+                        if index != 0
+                            || KvmCpuidFlags::for_leaf(function)
+                                .contains(KvmCpuidFlags::SIGNIFICANT_INDEX) =>
+                    {
+                        format!(
+                            "CPUID {function:08X}: {eax:08X}-{ebx:08X}-{ecx:08X}-{edx:08X} \
+                             [SL {index:02X}]\n"
+                        )
+                    }
+                    Self::InstLatX64 => {
+                        format!("CPUID {function:08X}: {eax:08X}-{ebx:08X}-{ecx:08X}-{edx:08X}\n")
+                    }
+                });
+            }
+        }
+        lines.concat()
+    }
+}
+
+/// A classified line of a dump.
+enum Line {
+    /// The header of a CPU.
+    Cpu,
+    /// An entry, `None` if malformed.
+    Entry(Option<RawCpuidEntry>),
+    /// Anything else.
+    Other,
+}
+
+/// Constructs an entry with the flags KVM sets.
+fn entry(leaf: u32, sub_leaf: u32, [eax, ebx, ecx, edx]: [u32; 4]) -> RawCpuidEntry {
+    RawCpuidEntry::new(
+        leaf,
+        sub_leaf,
+        KvmCpuidFlags::for_leaf(leaf),
+        eax,
+        ebx,
+        ecx,
+        edx,
+    )
+}
+
+/// Parses a hex number, with or without a `0x` prefix.
+fn hex(s: &str) -> Option<u32> {
+    u32::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+}
+
+/// Parses a line of [`DumpFormat::CpuidRaw`], e.g.
+/// `0x00000007 0x00: eax=0x00000000 ebx=0x029c67af ecx=0x40000004 edx=0xbc000400`.
+fn parse_cpuid_raw_line(line: &str) -> Line {
+    if let Some(cpu) = line.strip_prefix("CPU").and_then(|s| s.strip_suffix(':')) {
+        if cpu.is_empty()
+            || cpu
+                .strip_prefix(' ')
+                .is_some_and(|n| n.parse::<usize>().is_ok())
+        {
+            return Line::Cpu;
+        }
+    }
+    if !line.starts_with("0x") {
+        return Line::Other;
+    }
+    let parse = || {
+        let (keys, registers) = line.split_once(':')?;
+        let mut keys = keys.split_whitespace().map(hex);
+        let (leaf, sub_leaf) = (keys.next()??, keys.next()??);
+        let mut values = [0; 4];
+        let mut registers = registers.split_whitespace();
+        for (value, name) in values.iter_mut().zip(["eax=", "ebx=", "ecx=", "edx="]) {
+            *value = hex(registers.next()?.strip_prefix(name)?)?;
+        }
+        (keys.next().is_none() && registers.next().is_none()).then(|| entry(leaf, sub_leaf, values))
+    };
+    Line::Entry(parse())
+}
+
+/// Parses a line of [`DumpFormat::InstLatX64`], e.g.
+/// `CPUID 00000007: 00000000-029C67AF-40000004-BC000400 [SL 00]`.
+fn parse_instlatx64_line(line: &str) -> Line {
+    if let Some(cpu) = line.strip_prefix("CPU#") {
+        if cpu
+            .split_whitespace()
+            .next()
+            .and_then(|n| n.parse::<usize>().ok())
+            .is_some()
+        {
+            return Line::Cpu;
+        }
+    }
+    let Some(rest) = line.strip_prefix("CPUID ") else {
+        return Line::Other;
+    };
+    let parse = || {
+        let (leaf, rest) = rest.split_once(':')?;
+        let leaf = hex(leaf)?;
+        let rest = rest.trim();
+        let (registers, sub_leaf) = match rest.split_once('[') {
+            Some((registers, sub_leaf)) => (
+                registers.trim(),
+                hex(sub_leaf.strip_prefix("SL ")?.strip_suffix(']')?)?,
+            ),
+            None => (rest, 0),
+        };
+        let mut values = [0; 4];
+        let mut registers = registers.split('-');
+        for value in &mut values {
+            *value = hex(registers.next()?)?;
+        }
+        registers
+            .next()
+            .is_none()
+            .then(|| entry(leaf, sub_leaf, values))
+    };
+    Line::Entry(parse())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpuid_ffi::fixture;
+
+    /// The recorded entries of the two CPUs in the dump fixtures.
+    fn xeon() -> [RawCpuid; 2] {
+        // The fixtures are rendered from the KVM fixture, whose flags match `for_leaf`.
+        let first = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let mut second = first.clone();
+        second.get_mut(1, 0).unwrap().ebx = 0x0101_0800;
+        [first, second]
+    }
+
+    #[test]
+    fn cpuid_raw() {
+        let dump = include_str!("../fixtures/cpuid-r-xeon.txt");
+        let cpus = DumpFormat::CpuidRaw.parse(dump).unwrap();
+        assert_eq!(cpus, xeon());
+        assert_eq!(DumpFormat::CpuidRaw.write(&cpus), dump);
+    }
+
+    #[test]
+    fn instlatx64() {
+        let dump = include_str!("../fixtures/instlatx64-xeon.txt");
+        let cpus = DumpFormat::InstLatX64.parse(dump).unwrap();
+        assert_eq!(cpus, xeon());
+        let written = DumpFormat::InstLatX64.write(&cpus);
+        assert_eq!(DumpFormat::InstLatX64.parse(&written).unwrap(), cpus);
+        // Identical but for the file name and affinity masks.
+        let entries = |dump: &str| {
+            dump.lines()
+                .filter(|line| line.starts_with("CPUID"))
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(entries(&written), entries(dump));
+    }
+
+    #[test]
+    fn captured() {
+        // Captured on a KVM guest, as printed by `cpuid -1 -r` and InstLatx64.
+        let cpuid_raw = DumpFormat::CpuidRaw
+            .parse(include_str!("../fixtures/cpuid-1-r-kvm-guest.txt"))
+            .unwrap();
+        let instlatx64 = DumpFormat::InstLatX64
+            .parse(include_str!("../fixtures/instlatx64-kvm-guest.txt"))
+            .unwrap();
+        assert_eq!(cpuid_raw, instlatx64);
+        let [cpu] = &cpuid_raw[..] else {
+            panic!("expected a single CPU");
+        };
+        assert_eq!(cpu.len(), 74);
+        assert_eq!(cpu.get(0xD, 0x12).unwrap().eax, 0x2000);
+        assert_eq!(cpu.get(0x4000_0000, 0).unwrap().ebx, 0x4b4d_564b);
+        assert_eq!(cpu.get(0xC000_0000, 0).unwrap().eax, 0);
+    }
+
+    #[test]
+    fn read() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/cpuid-r-xeon.txt");
+        assert_eq!(DumpFormat::CpuidRaw.read(path).unwrap(), xeon());
+        assert!(matches!(
+            DumpFormat::CpuidRaw.read("fixtures/missing.txt"),
+            Err(ReadDumpError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn single_cpu() {
+        let cpuid_raw = "   0x00000000 0x00: eax=0x0000000d ebx=0x756e6547 ecx=0x6c65746e \
+                         edx=0x49656e69\n   0x00000007 0x00: eax=0x00000000 ebx=0x029c67af \
+                         ecx=0x40000004 edx=0xbc000400\n";
+        let instlatx64 = "GenuineIntel00906EA_CoffeeLake_CPUID.txt\n\n\
+                          CPUID 00000000: 0000000D-756E6547-6C65746E-49656E69\n\
+                          CPUID 00000007: 00000000-029C67AF-40000004-BC000400 [SL 00]\n";
+        let expected = vec![RawCpuid::from(vec![
+            RawCpuidEntry::new(
+                0,
+                0,
+                KvmCpuidFlags::empty(),
+                0xd,
+                0x756e_6547,
+                0x6c65_746e,
+                0x4965_6e69,
+            ),
+            RawCpuidEntry::new(
+                7,
+                0,
+                KvmCpuidFlags::SIGNIFICANT_INDEX,
+                0,
+                0x029c_67af,
+                0x4000_0004,
+                0xbc00_0400,
+            ),
+        ])];
+        assert_eq!(DumpFormat::CpuidRaw.parse(cpuid_raw).unwrap(), expected);
+        assert_eq!(
+            DumpFormat::CpuidRaw
+                .parse(&format!("CPU:\n{cpuid_raw}"))
+                .unwrap(),
+            expected
+        );
+        assert_eq!(DumpFormat::InstLatX64.parse(instlatx64).unwrap(), expected);
+        assert_eq!(DumpFormat::CpuidRaw.parse("").unwrap(), []);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            DumpFormat::CpuidRaw.parse("CPU 0:\n   0x00000000 0x00: eax=0x0 ebx=0x0 ecx=0x0\n"),
+            Err(ParseDumpError {
+                line: 2,
+                message: String::from(
+                    "Malformed entry: `   0x00000000 0x00: eax=0x0 ebx=0x0 ecx=0x0`"
+                ),
+            })
+        );
+        assert!(DumpFormat::CpuidRaw.parse("CPU zero:\n").is_err());
+        assert!(DumpFormat::CpuidRaw.parse("CPU0:\n").is_err());
+        assert!(DumpFormat::InstLatX64
+            .parse("CPUID 00000000: 0000000D-756E6547-6C65746E-49656E69 [SL]\n")
+            .is_err());
+        assert!(DumpFormat::InstLatX64
+            .parse("CPUID 00000000: 0000000D-756E6547-6C65746E\n")
+            .is_err());
+        // Unrecognized lines are ignored in InstLatx64 dumps.
+        assert_eq!(
+            DumpFormat::InstLatX64.parse("CPU Name: Xeon\n").unwrap(),
+            []
+        );
+    }
+}
@@ -0,0 +1,877 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bit_fields::{BitField, Field, FieldKind};
+
+use crate::intel::{
+    Leaf10Subleaf0Ebx, Leaf10Subleaf1Ecx, Leaf10Subleaf3Ecx, Leaf12Subleaf0Eax, Leaf14Subleaf0Ebx,
+    Leaf14Subleaf0Ecx, Leaf17Subleaf0Ebx, Leaf19Eax, Leaf19Ebx, Leaf19Ecx, Leaf1CEax, Leaf1CEbx,
+    Leaf1CEcx, Leaf1Ecx, Leaf1Edx, Leaf20Ebx, Leaf5Ecx, Leaf6Eax, Leaf6Ecx, Leaf7Subleaf0Ebx,
+    Leaf7Subleaf0Ecx, Leaf7Subleaf0Edx, Leaf7Subleaf1Eax, Leaf7Subleaf1Ebx, Leaf80000007Edx,
+    Leaf80000008Ebx, LeafAEbx, LeafAEdx, LeafDSubleaf0Eax, LeafDSubleaf1Eax, LeafDSubleaf1Ecx,
+    LeafFSubleaf0Edx, LeafFSubleaf1Edx,
+};
+use crate::{
+    CapacityError, Cpuid, Leaf0x8000_0001_SubLeaf0_Ecx, Leaf0x8000_0001_SubLeaf0_Edx,
+    Leaf0x8000_001F_SubLeaf0_Eax, RawCpuid, Register,
+};
+
+/// Where a [`Feature`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FeatureLocation {
+    /// The leaf (function).
+    pub leaf: u32,
+    /// The sub-leaf (index).
+    pub sub_leaf: u32,
+    /// The register.
+    pub register: Register,
+    /// The bit of the register.
+    pub bit: u8,
+}
+impl FeatureLocation {
+    /// Returns the mask of the bit in the register.
+    #[must_use]
+    pub const fn mask(&self) -> u32 {
+        1 << self.bit
+    }
+}
+
+/// Returns the bit of the flag `ident` of a `bitfield!`, failing to compile when there is none.
+const fn flag_bit(fields: &[Field], ident: &str) -> u8 {
+    let mut i = 0;
+    while i < fields.len() {
+        if matches!(fields[i].kind, FieldKind::Flag) && str_eq(fields[i].ident, ident) {
+            return fields[i].start;
+        }
+        i += 1;
+    }
+    panic!("No such flag");
+}
+
+/// `a == b` for `const` contexts.
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Returns the bit of the single bit `mask` of a `bitflags!` flag.
+const fn mask_bit(mask: u32) -> u8 {
+    let mut bit = 0;
+    while mask >> bit != 1 {
+        bit += 1;
+    }
+    bit
+}
+
+/// Returns the bit of a flag of a `bitfield!` or a `bitflags!`.
+macro_rules! feature_bit {
+    (bitfield, $ty:ident, $ident:ident) => {
+        flag_bit(<$ty as BitField>::FIELDS, stringify!($ident))
+    };
+    (bitflags, $ty:ident, $ident:ident) => {
+        mask_bit($ty::$ident.bits())
+    };
+}
+
+/// Returns the name of a feature, `$name` when given or else the identifier of its flag.
+macro_rules! feature_name {
+    ($ident:ident) => {
+        stringify!($ident)
+    };
+    ($ident:ident, $name:literal) => {
+        $name
+    };
+}
+
+/// Defines [`Feature`] from the flags of the `bitfield!` or `bitflags!` describing each register.
+///
+/// A flag whose identifier is not its name (e.g. `_3dnow`) is given one with `as "<name>"`.
+macro_rules! features {
+    ($(
+        ($leaf:literal, $sub_leaf:literal, $register:ident) $kind:ident $ty:ident {
+            $($variant:ident => $ident:ident $(as $name:literal)?,)*
+        }
+    )*) => {
+        /// A CPUID feature, a single bit of a register of a leaf (sub-leaf), named after its flag
+        /// in [`crate::intel`] (or [`Cpuid`] for AMD leaves).
+        ///
+        /// Every flag of [`crate::register_fields`] is a feature, but for those of the leaves
+        /// enumerating one sub-leaf per instance of a structure (e.g. each cache of leaf 4).
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Feature {
+            $($(
+                #[doc = concat!(
+                    "`", feature_name!($ident $(, $name)?), "`, leaf `", stringify!($leaf), "` sub-leaf `",
+                    stringify!($sub_leaf), "` `", stringify!($register), "`."
+                )]
+                $variant,
+            )*)*
+        }
+        impl Feature {
+            /// Every feature, ordered by leaf, sub-leaf, register and bit.
+            pub const ALL: &'static [Self] = &[$($(Self::$variant,)*)*];
+
+            /// Returns the name of the feature, e.g. `avx512f`.
+            #[must_use]
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $($(Self::$variant => feature_name!($ident $(, $name)?),)*)*
+                }
+            }
+
+            /// Returns where the feature is.
+            #[must_use]
+            pub const fn location(self) -> FeatureLocation {
+                match self {
+                    $($(Self::$variant => FeatureLocation {
+                        leaf: $leaf,
+                        sub_leaf: $sub_leaf,
+                        register: Register::$register,
+                        bit: const { feature_bit!($kind, $ty, $ident) },
+                    },)*)*
+                }
+            }
+        }
+    };
+}
+
+features! {
+    (0x1, 0, Ecx) bitfield Leaf1Ecx {
+        Sse3 => sse3,
+        Pclmulqdq => pclmulqdq,
+        Dtes64 => dtes64,
+        Monitor => monitor,
+        DsCpl => ds_cpl,
+        Vmx => vmx,
+        Smx => smx,
+        Eist => eist,
+        Tm2 => tm2,
+        Ssse3 => ssse3,
+        CnxtId => cnxt_id,
+        Sdbg => sdbg,
+        Fma => fma,
+        Cmpxchg16b => cmpxchg16b,
+        XtprUpdateControl => xtpr_update_control,
+        Pdcm => pdcm,
+        Pcid => pcid,
+        Dca => dca,
+        Sse41 => sse4_1,
+        Sse42 => sse4_2,
+        X2apic => x2apic,
+        Movbe => movbe,
+        Popcnt => popcnt,
+        TscDeadline => tsc_deadline,
+        Aesni => aesni,
+        Xsave => xsave,
+        Osxsave => osxsave,
+        Avx => avx,
+        F16c => f16c,
+        Rdrand => rdrand,
+    }
+    (0x1, 0, Edx) bitfield Leaf1Edx {
+        Fpu => fpu,
+        Vme => vme,
+        De => de,
+        Pse => pse,
+        Tsc => tsc,
+        Msr => msr,
+        Pae => pae,
+        Mce => mce,
+        Cx8 => cx8,
+        Apic => apic,
+        Sep => sep,
+        Mtrr => mtrr,
+        Pge => pge,
+        Mca => mca,
+        Cmov => cmov,
+        Pat => pat,
+        Pse336 => pse3_36,
+        Psn => psn,
+        Clfsh => clfsh,
+        Ds => ds,
+        Acpi => acpi,
+        Mmx => mmx,
+        Fxsr => fxsr,
+        Sse => sse,
+        Sse2 => sse2,
+        Ss => ss,
+        Htt => htt,
+        Tm => tm,
+        Pbe => pbe,
+    }
+    (0x5, 0, Ecx) bitfield Leaf5Ecx {
+        EnumMonitorMwaitExt => enum_monitor_mwait_ext,
+        SupportTreatingInterruptsAsBreakEventsForMwait => support_treating_interrupts_as_break_events_for_mwait,
+    }
+    (0x6, 0, Eax) bitfield Leaf6Eax {
+        DigitalTemperatureSensor => digital_temperature_sensor,
+        IntelTurboBoostTechnology => intel_turbo_boost_technology,
+        Arat => arat,
+        Pln => pln,
+        Ecmd => ecmd,
+        Ptm => ptm,
+        Hwp => hwp,
+        HwpNotification => hwp_notification,
+        HwpActivityWindow => hwp_activity_window,
+        HwpEnergyPerformance => hwp_energy_performance,
+        HwpPackageLevelRequest => hwp_package_level_request,
+        Hdc => hdc,
+        IntelTurboBoostMaxTechnology3 => intel_turbo_boost_max_technology_3,
+        HwpCapabilities => hwp_capabilities,
+        HwpPeciOverride => hwp_peci_override,
+        FlexibleHwp => flexible_hwp,
+        FastAccessModeForI32HwpRequestMsr => fast_access_mode_for_i32_hwp_request_msr,
+        HwFeedback => hw_feedback,
+        IilpHwpR => iilp_hwp_r,
+        IntelThreadDirector => intel_thread_director,
+    }
+    (0x6, 0, Ecx) bitfield Leaf6Ecx {
+        HardwareCoordinationFeedbackCapability => hardware_coordination_feedback_capability,
+        PerformanceEnergyBias => performance_energy_bias,
+    }
+    (0x7, 0, Ebx) bitfield Leaf7Subleaf0Ebx {
+        Fsgsbase => fsgsbase,
+        Ia32TscAdjustMsr => ia32_tsc_adjust_msr,
+        Sgx => sgx,
+        Bmi1 => bmi1,
+        Hle => hle,
+        Avx2 => avx2,
+        FdpExcptnOnly => fdp_excptn_only,
+        Smep => smep,
+        Bmi2 => bmi2,
+        Erms => erms,
+        Invpcid => invpcid,
+        Rtm => rtm,
+        RdtM => rdt_m,
+        DeprecatesFpuCsAndFpuDs => deprecates_fpu_cs_and_fpu_ds,
+        Mpx => mpx,
+        RdtT => rdt_t,
+        Avx512f => avx512f,
+        Avx512dq => avx512dq,
+        Rdseed => rdseed,
+        Adx => adx,
+        Smap => smap,
+        Avx512Ifma => avx512_ifma,
+        Clflushopt => clflushopt,
+        Clwb => clwb,
+        IntelProcessorTrace => intel_processor_trace,
+        Avx512pf => avx512pf,
+        Avx512er => avx512er,
+        Avx512cd => avx512cd,
+        Sha => sha,
+        Avx512bw => avx512bw,
+        Avx512vl => avx512vl,
+    }
+    (0x7, 0, Ecx) bitfield Leaf7Subleaf0Ecx {
+        Prefetchwt1 => prefetchwt1,
+        Avx512Vbmi => avx512_vbmi,
+        Umip => umip,
+        Pku => pku,
+        Ospke => ospke,
+        Waitpkg => waitpkg,
+        Avx512Vbmi2 => avx512_vbmi2,
+        CetSs => cet_ss,
+        Gfni => gfni,
+        Vaes => vaes,
+        Vpclmulqdq => vpclmulqdq,
+        Avx512Vnni => avx512_vnni,
+        Avx512Bitalg => avx512_bitalg,
+        TmeEn => tme_en,
+        Avx512Vpopcntdq => avx512_vpopcntdq,
+        La57 => la57,
+        RdpidAndIa32TscAux => rdpid_and_ia32_tsc_aux,
+        Kl => kl,
+        Cldemote => cldemote,
+        Movdiri => movdiri,
+        Movdir64b => movdir64b,
+        SgxLc => sgx_lc,
+        Pks => pks,
+    }
+    (0x7, 0, Edx) bitfield Leaf7Subleaf0Edx {
+        Avx5124vnniw => avx512_4vnniw,
+        Avx5124fmaps => avx512_4fmaps,
+        FastShortRepMov => fast_short_rep_mov,
+        Avx512Vp2intersect => avx512_vp2intersect,
+        MdClear => md_clear,
+        Hybrid => hybrid,
+        Pconfig => pconfig,
+        CetIbt => cet_ibt,
+        IbrsIbpbEnum => ibrs_ibpb_enum,
+        StibpEnum => stibp_enum,
+        L1dFlushEnum => l1d_flush_enum,
+        Ia32ArchCapabilitiesMsrEnum => ia32_arch_capabilities_msr_enum,
+        Ia32CoreCapabilitiesMsrEnum => ia32_core_capabilities_msr_enum,
+        SsbdEnum => ssbd_enum,
+    }
+    (0x7, 1, Eax) bitfield Leaf7Subleaf1Eax {
+        AvxVnni => avx_vnni,
+        Avx512Bf16 => avx512_bf16,
+        FastZeroLengthRepMovsh => fast_zero_length_rep_movsh,
+        FastShortRepStosb => fast_short_rep_stosb,
+        FastShortRepCmpsbRepScasb => fast_short_rep_cmpsb_rep_scasb,
+        Hreset => hreset,
+    }
+    (0x7, 1, Ebx) bitfield Leaf7Subleaf1Ebx {
+        Ia32PpinAndIa32PpinCtlMsrsEnum => ia32_ppin_and_ia32_ppin_ctl_msrs_enum,
+    }
+    (0xA, 0, Ebx) bitfield LeafAEbx {
+        CoreCycleEvent => core_cycle_event,
+        InstructionRetiredEvent => instruction_retired_event,
+        ReferenceCyclesEvent => reference_cycles_event,
+        LastLevelCacheReferenceEvent => last_level_cache_reference_event,
+        LastLevelCacheMissesEvent => last_level_cache_misses_event,
+        BranchInstructionRetiredEvent => branch_instruction_retired_event,
+        BranchMispredictRetiredEvent => branch_mispredict_retired_event,
+        TopDownSlotsEvent => top_down_slots_event,
+    }
+    (0xA, 0, Edx) bitfield LeafAEdx {
+        AnythreadDeprecation => anythread_deprecation,
+    }
+    (0xD, 0, Eax) bitfield LeafDSubleaf0Eax {
+        X87State => x87_state,
+        SseState => sse_state,
+        AvxState => avx_state,
+        PkruState => pkru_state,
+    }
+    (0xD, 1, Eax) bitfield LeafDSubleaf1Eax {
+        XsaveoptAvailable => xsaveopt_available,
+        XsavecCompactedXrstor => xsavec_compacted_xrstor,
+        Xgetbv => xgetbv,
+        XsavesXrstorsIa32Xss => xsaves_xrstors_ia32_xss,
+    }
+    (0xD, 1, Ecx) bitfield LeafDSubleaf1Ecx {
+        PtState => pt_state,
+        Xcr02 => xcr0_2,
+        CetUserState => cet_user_state,
+        CetSupervisorState => cet_supervisor_state,
+        HdcState => hdc_state,
+        LbrState => lbr_state,
+        HwpState => hwp_state,
+    }
+    (0xF, 0, Edx) bitfield LeafFSubleaf0Edx {
+        L3RdtMonitor => l3_rdt_monitor,
+    }
+    (0xF, 1, Edx) bitfield LeafFSubleaf1Edx {
+        L3OccupancyMonitor => l3_occupancy_monitor,
+        L3TotalBandMonitor => l3_total_band_monitor,
+        L3LocalBandMonitor => l3_local_band_monitor,
+    }
+    (0x10, 0, Ebx) bitfield Leaf10Subleaf0Ebx {
+        L3Alloc => l3_alloc,
+        L2Alloc => l2_alloc,
+        MemBandAlloc => mem_band_alloc,
+    }
+    (0x10, 1, Ecx) bitfield Leaf10Subleaf1Ecx {
+        CdPrior => cd_prior,
+    }
+    (0x10, 3, Ecx) bitfield Leaf10Subleaf3Ecx {
+        LinearResponseDelayValues => linear_response_delay_values,
+    }
+    (0x12, 0, Eax) bitfield Leaf12Subleaf0Eax {
+        Sgx1 => sgx1,
+        Sgx2 => sgx2,
+        Enclv => enclv,
+        Encls => encls,
+    }
+    (0x14, 0, Ebx) bitfield Leaf14Subleaf0Ebx {
+        Ia32Rtit => ia32_rtit,
+        PsbConfigCam => psb_config_cam,
+        IpFilteringAndTsFilteringAndPtMsrPreservation => ip_filtering_and_ts_filtering_and_pt_msr_preservation,
+        MtcTimingAndCofiSuppression => mtc_timing_and_cofi_suppression,
+        Ptwrite => ptwrite,
+        PowerEventTrace => power_event_trace,
+        PsbAndPmiPreservation => psb_and_pmi_preservation,
+        Ia32RtitCtl31 => ia32_rtit_ctl_31,
+        Ia32RtitCtl55 => ia32_rtit_ctl_55,
+    }
+    (0x14, 0, Ecx) bitfield Leaf14Subleaf0Ecx {
+        Ia32RtitCtlTopa => ia32_rtit_ctl_topa,
+        TopaExt => topa_ext,
+        Sros => sros,
+        Otts => otts,
+        LipCsBase => lip_cs_base,
+    }
+    (0x17, 0, Ebx) bitfield Leaf17Subleaf0Ebx {
+        IsVendorScheme => is_vendor_scheme,
+    }
+    (0x19, 0, Eax) bitfield Leaf19Eax {
+        Cpl0Only => cpl0_only,
+        NoEncrypt => no_encrypt,
+        NoDecrypt => no_decrypt,
+    }
+    (0x19, 0, Ebx) bitfield Leaf19Ebx {
+        Aeskle => aeskle,
+        AesWideKeyLocker => aes_wide_key_locker,
+        KeyLockerMsrs => key_locker_msrs,
+    }
+    (0x19, 0, Ecx) bitfield Leaf19Ecx {
+        LoadikeyNoBackup => loadikey_no_backup,
+        KeySourceEncodingOne => key_source_encoding_one,
+    }
+    (0x1C, 0, Eax) bitfield Leaf1CEax {
+        DeepCState => deep_c_state,
+        IpValuesContainLip => ip_values_contain_lip,
+    }
+    (0x1C, 0, Ebx) bitfield Leaf1CEbx {
+        CplFiltering => cpl_filtering,
+        BranchFiltering => branch_filtering,
+        CallStackMode => call_stack_mode,
+    }
+    (0x1C, 0, Ecx) bitfield Leaf1CEcx {
+        MispredictBit => mispredict_bit,
+        TimedLbrs => timed_lbrs,
+        BranchTypeField => branch_type_field,
+    }
+    (0x20, 0, Ebx) bitfield Leaf20Ebx {
+        ThreadDirectorHistoryReset => thread_director_history_reset,
+    }
+    (0x8000_0001, 0, Ecx) bitflags Leaf0x8000_0001_SubLeaf0_Ecx {
+        Lahf => lahf,
+        CmpLegacy => cmp_legacy,
+        Svm => svm,
+        Extapic => extapic,
+        Cr8Legacy => cr8_legacy,
+        Abm => abm,
+        Sse4a => sse4a,
+        Misalignsse => misalignsse,
+        ThreeDnowPrefetch => _3dnowprefetch as "3dnowprefetch",
+        Osvw => osvw,
+        Ibs => ibs,
+        Xop => xop,
+        Skinit => skinit,
+        Wdt => wdt,
+        Lwp => lwp,
+        Fma4 => fma4,
+        Tce => tce,
+        NodeidMsr => nodeid_msr,
+        Tbm => tbm,
+        Topoext => topoext,
+        PerfctrCore => perfctr_core,
+        PerfctrNb => perfctr_nb,
+        Dbx => dbx,
+        Perftsc => perftsc,
+        PcxL2i => pcx_l2i,
+        Monitorx => monitorx,
+        AddrMaskExt => addr_mask_ext,
+    }
+    (0x8000_0001, 0, Edx) bitflags Leaf0x8000_0001_SubLeaf0_Edx {
+        Syscall => syscall,
+        Mp => mp,
+        Nx => nx,
+        Mmxext => mmxext,
+        FxsrOpt => fxsr_opt,
+        Pdpe1gb => pdpe1gb,
+        Rdtscp => rdtscp,
+        Lm => lm,
+        ThreeDnowExt => _3dnowext as "3dnowext",
+        ThreeDnow => _3dnow as "3dnow",
+    }
+    (0x8000_0007, 0, Edx) bitfield Leaf80000007Edx {
+        InvariantTsc => invariant_tsc,
+    }
+    (0x8000_0008, 0, Ebx) bitfield Leaf80000008Ebx {
+        Wbnoinvd => wbnoinvd,
+    }
+    (0x8000_001F, 0, Eax) bitflags Leaf0x8000_001F_SubLeaf0_Eax {
+        Sme => sme,
+        Sev => sev,
+        PageFlush => page_flush,
+        SevEs => sev_es,
+        SevSnp => sev_snp,
+        Vmpl => vmpl,
+        HwCacheCoherency => hw_cache_coherency,
+        Host64Bit => _64_host as "64bit_host",
+        RestrictedInjection => restricted_injection,
+        AlternativeInjection => alternative_injection,
+        DebugSwap => debug_swap,
+        PreventHostIbs => prevent_host_ibs,
+        Vte => vte,
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Error type for [`Feature::from_str`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFeatureError {
+    /// The unknown name.
+    pub name: String,
+}
+impl fmt::Display for ParseFeatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown feature `{}`", self.name)
+    }
+}
+impl std::error::Error for ParseFeatureError {}
+
+impl FromStr for Feature {
+    type Err = ParseFeatureError;
+
+    /// Looks up a feature by its name (see [`Feature::name`]).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|feature| feature.name() == s)
+            .ok_or_else(|| ParseFeatureError {
+                name: String::from(s),
+            })
+    }
+}
+
+impl RawCpuid {
+    /// Returns if `feature` is set, `false` when its leaf (sub-leaf) is missing.
+    #[must_use]
+    pub fn has(&self, feature: Feature) -> bool {
+        let location = feature.location();
+        self.get(location.leaf, location.sub_leaf)
+            .is_some_and(|entry| location.register.of(entry) & location.mask() != 0)
+    }
+
+    /// Sets `feature`, inserting its leaf (sub-leaf) zeroed when missing.
+    ///
+    /// # Errors
+    ///
+    /// When inserting would exceed [`kvm_bindings::KVM_MAX_CPUID_ENTRIES`] entries.
+    pub fn set(&mut self, feature: Feature) -> Result<(), CapacityError> {
+        let location = feature.location();
+        let entry = self.entry(location.leaf, location.sub_leaf).or_default()?;
+        *location.register.of_mut(entry) |= location.mask();
+        Ok(())
+    }
+
+    /// Clears `feature`, when its leaf (sub-leaf) is present.
+    pub fn clear(&mut self, feature: Feature) {
+        let location = feature.location();
+        if let Some(entry) = self.get_mut(location.leaf, location.sub_leaf) {
+            *location.register.of_mut(entry) &= !location.mask();
+        }
+    }
+}
+
+/// Expands to the bits of the typed register of a [`Cpuid`] holding the flags of a leaf,
+/// sub-leaf and register, behind `$ref` (e.g. `&mut`), or to `$misc` when the register is kept in
+/// [`Cpuid::misc`].
+macro_rules! typed_flags {
+    ($cpuid:expr, $location:expr, ($($ref:tt)*), $misc:expr) => {
+        match ($location.leaf, $location.sub_leaf, $location.register) {
+            (0x1, 0, Register::Ecx) => {
+                $($ref)* $cpuid.leaf0x01_process_info_and_feature_bits.feature_information.ecx.bits
+            }
+            (0x1, 0, Register::Edx) => {
+                $($ref)* $cpuid.leaf0x01_process_info_and_feature_bits.feature_information.edx.bits
+            }
+            (0x6, 0, Register::Eax) => {
+                $($ref)* $cpuid.leaf0x06_thermal_and_power_management.features.eax.bits
+            }
+            (0x6, 0, Register::Ecx) => {
+                $($ref)* $cpuid.leaf0x06_thermal_and_power_management.features.ecx.bits
+            }
+            (0x7, 0, Register::Ebx) => $($ref)* $cpuid.leaf0x07_extended_features.sub_leaf0.ebx.bits,
+            (0x7, 0, Register::Ecx) => $($ref)* $cpuid.leaf0x07_extended_features.sub_leaf0.ecx.bits,
+            (0x7, 0, Register::Edx) => $($ref)* $cpuid.leaf0x07_extended_features.sub_leaf0.edx.bits,
+            (0x7, 1, Register::Eax) => $($ref)* $cpuid.leaf0x07_extended_features.sub_leaf1.bits,
+            (0xD, 1, Register::Eax) => $($ref)* $cpuid.leaf0x0d_cpuid_feature_bits.bits,
+            (0x12, 0, Register::Eax) => $($ref)* $cpuid.leaf0x12_cpuid_feature_bits.bits,
+            (0x14, 0, Register::Ebx) => $($ref)* $cpuid.leaf0x14_cpuid_feature_bits.bits,
+            (0x19, 0, Register::Ebx) => $($ref)* $cpuid.leaf0x19_cpuid_feature_bits.bits,
+            (0x8000_0001, 0, Register::Ecx) => {
+                $($ref)* $cpuid
+                    .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                    .ecx
+                    .bits
+            }
+            (0x8000_0001, 0, Register::Edx) => {
+                $($ref)* $cpuid
+                    .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                    .edx
+                    .bits
+            }
+            (0x8000_0008, 0, Register::Ebx) => {
+                $($ref)* $cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes.ebx.bits
+            }
+            (0x8000_001F, 0, Register::Eax) => {
+                $($ref)* $cpuid.leaf0x8000_001F_cpuid_feature_bits.bits
+            }
+            _ => $misc,
+        }
+    };
+}
+
+/// Returns a register of an entry of [`Cpuid::misc`].
+fn misc_register(registers: &mut (u32, u32, u32, u32), register: Register) -> &mut u32 {
+    match register {
+        Register::Eax => &mut registers.0,
+        Register::Ebx => &mut registers.1,
+        Register::Ecx => &mut registers.2,
+        Register::Edx => &mut registers.3,
+    }
+}
+
+impl Cpuid {
+    /// Returns if `feature` is set.
+    #[must_use]
+    pub fn has(&self, feature: Feature) -> bool {
+        let location = feature.location();
+        let bits = typed_flags!(
+            self,
+            location,
+            (),
+            self.misc
+                .get(&(location.leaf, location.sub_leaf))
+                .copied()
+                .map_or(0, |mut registers| *misc_register(
+                    &mut registers,
+                    location.register
+                ))
+        );
+        bits & location.mask() != 0
+    }
+
+    /// Sets `feature`, inserting its leaf (sub-leaf) zeroed into [`Cpuid::misc`] when it is kept
+    /// there and missing.
+    pub fn set(&mut self, feature: Feature) {
+        let location = feature.location();
+        *self.flags_mut(location) |= location.mask();
+    }
+
+    /// Clears `feature`.
+    pub fn clear(&mut self, feature: Feature) {
+        // Checked first, so a missing leaf (sub-leaf) is not inserted into `misc`.
+        if self.has(feature) {
+            let location = feature.location();
+            *self.flags_mut(location) &= !location.mask();
+        }
+    }
+
+    /// Returns the register holding the bit of `location`.
+    fn flags_mut(&mut self, location: FeatureLocation) -> &mut u32 {
+        typed_flags!(
+            self,
+            location,
+            (&mut),
+            misc_register(
+                self.misc
+                    .entry((location.leaf, location.sub_leaf))
+                    .or_default(),
+                location.register
+            )
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpuid_ffi::fixture;
+    use crate::register_fields;
+
+    #[test]
+    fn from_str() {
+        let avx512f = Feature::from_str("avx512f").unwrap();
+        assert_eq!(avx512f, Feature::Avx512f);
+        assert_eq!(
+            avx512f.location(),
+            FeatureLocation {
+                leaf: 7,
+                sub_leaf: 0,
+                register: Register::Ebx,
+                bit: 16
+            }
+        );
+        assert_eq!(avx512f.to_string(), "avx512f");
+        assert_eq!(
+            "sev_snp".parse::<Feature>().unwrap().location().leaf,
+            0x8000_001F
+        );
+        assert_eq!(
+            "avx1024".parse::<Feature>().unwrap_err().to_string(),
+            "Unknown feature `avx1024`"
+        );
+        for feature in Feature::ALL {
+            assert_eq!(feature.name().parse::<Feature>(), Ok(*feature));
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let key = |feature: &Feature| {
+            let location = feature.location();
+            (
+                location.leaf,
+                location.sub_leaf,
+                location.register as u8,
+                location.bit,
+            )
+        };
+        assert!(Feature::ALL.windows(2).all(|w| key(&w[0]) < key(&w[1])));
+
+        let mut names = Feature::ALL.iter().map(|f| f.name()).collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), Feature::ALL.len());
+
+        // Every feature but those of the AMD leaves is a flag of `register_fields`.
+        let flags = |leaf, sub_leaf| {
+            register_fields(leaf, sub_leaf)
+                .into_iter()
+                .flat_map(|fields| Register::ALL.into_iter().zip(fields))
+                .flat_map(|(register, fields)| fields.iter().map(move |field| (register, field)))
+                .filter(|(_, field)| field.kind == FieldKind::Flag)
+        };
+        for feature in Feature::ALL {
+            let location = feature.location();
+            if matches!(location.leaf, 0x8000_0001 | 0x8000_001F) {
+                continue;
+            }
+            assert!(
+                flags(location.leaf, location.sub_leaf).any(|(register, field)| {
+                    register == location.register
+                        && field.start == location.bit
+                        && field.ident == feature.name()
+                }),
+                "{feature}"
+            );
+        }
+
+        // Every flag of the leaves which are not enumerated per instance is a feature.
+        let leaves = [
+            (0x1, 0),
+            (0x2, 0),
+            (0x3, 0),
+            (0x5, 0),
+            (0x6, 0),
+            (0x7, 0),
+            (0x7, 1),
+            (0x9, 0),
+            (0xA, 0),
+            (0xD, 0),
+            (0xD, 1),
+            (0xF, 0),
+            (0xF, 1),
+            (0x10, 0),
+            (0x10, 1),
+            (0x10, 2),
+            (0x10, 3),
+            (0x12, 0),
+            (0x12, 1),
+            (0x14, 0),
+            (0x14, 1),
+            (0x15, 0),
+            (0x16, 0),
+            (0x17, 0),
+            (0x19, 0),
+            (0x1A, 0),
+            (0x1B, 0),
+            (0x1C, 0),
+            (0x20, 0),
+            (0x8000_0000, 0),
+            (0x8000_0001, 0),
+            (0x8000_0005, 0),
+            (0x8000_0006, 0),
+            (0x8000_0007, 0),
+            (0x8000_0008, 0),
+        ];
+        for (leaf, sub_leaf) in leaves {
+            for (register, field) in flags(leaf, sub_leaf) {
+                let location = FeatureLocation {
+                    leaf,
+                    sub_leaf,
+                    register,
+                    bit: field.start,
+                };
+                assert!(
+                    Feature::ALL.iter().any(|f| f.location() == location),
+                    "{leaf:#x}.{sub_leaf} {register} `{}`",
+                    field.ident
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn names() {
+        for (name, leaf, register, bit) in [
+            ("clflushopt", 0x7, Register::Ebx, 23),
+            ("erms", 0x7, Register::Ebx, 9),
+            ("movdir64b", 0x7, Register::Ecx, 28),
+            ("hybrid", 0x7, Register::Edx, 15),
+            ("svm", 0x8000_0001, Register::Ecx, 2),
+            ("sse4a", 0x8000_0001, Register::Ecx, 6),
+            ("abm", 0x8000_0001, Register::Ecx, 5),
+            ("3dnowprefetch", 0x8000_0001, Register::Ecx, 8),
+            ("nx", 0x8000_0001, Register::Edx, 20),
+            ("lm", 0x8000_0001, Register::Edx, 29),
+            ("3dnow", 0x8000_0001, Register::Edx, 31),
+            ("64bit_host", 0x8000_001F, Register::Eax, 11),
+        ] {
+            let location = name.parse::<Feature>().unwrap().location();
+            assert_eq!(
+                (location.leaf, location.register, location.bit),
+                (leaf, register, bit),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn raw() {
+        let mut cpuid = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        assert!(cpuid.has(Feature::Clwb));
+        assert!(!cpuid.has(Feature::Sme));
+        let ebx = cpuid.get(7, 0).unwrap().ebx;
+
+        cpuid.clear(Feature::Clwb);
+        assert!(!cpuid.has(Feature::Clwb));
+        assert_eq!(cpuid.get(7, 0).unwrap().ebx, ebx & !(1 << 24));
+        cpuid.set(Feature::Clwb).unwrap();
+        assert_eq!(cpuid.get(7, 0).unwrap().ebx, ebx);
+
+        // The AMD leaf is missing, clearing does nothing and setting inserts it.
+        let len = cpuid.len();
+        cpuid.clear(Feature::Sev);
+        assert_eq!(cpuid.len(), len);
+        cpuid.set(Feature::Sev).unwrap();
+        assert_eq!(cpuid.get(0x8000_001F, 0).unwrap().eax, 1 << 1);
+    }
+
+    #[test]
+    fn cpuid() {
+        for feature in Feature::ALL {
+            let mut cpuid = Cpuid::default();
+            assert!(!cpuid.has(*feature));
+            cpuid.set(*feature);
+            assert!(cpuid.has(*feature), "{feature}");
+
+            // Only the bit of the feature is set in the entries.
+            let raw = RawCpuid::from(cpuid.clone());
+            let set = Feature::ALL
+                .iter()
+                .filter(|feature| raw.has(**feature))
+                .collect::<Vec<_>>();
+            assert_eq!(set, [feature]);
+
+            cpuid.clear(*feature);
+            assert!(!cpuid.has(*feature));
+        }
+
+        // Clearing does not insert missing leaves into `misc`.
+        let mut cpuid = Cpuid::default();
+        cpuid.clear(Feature::InvariantTsc);
+        assert!(cpuid.misc.is_empty());
+    }
+}
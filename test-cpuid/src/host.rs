@@ -0,0 +1,565 @@
+use core::arch::x86_64::{CpuidResult, __cpuid_count};
+use std::convert::Infallible;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+use std::{io, mem};
+
+use crate::{KvmCpuidFlags, RawCpuid, RawCpuidEntry};
+
+/// The first leaf of the hypervisor range.
+const HYPERVISOR_LEAF: u32 = 0x4000_0000;
+/// The last leaf of the hypervisor range we enumerate.
+const HYPERVISOR_LEAF_MAX: u32 = 0x4000_00FF;
+/// The first leaf of the extended range.
+const EXTENDED_LEAF: u32 = 0x8000_0000;
+/// The hypervisor present bit in `ecx` of leaf 1.
+const HYPERVISOR_PRESENT: u32 = 1 << 31;
+
+impl RawCpuid {
+    /// Enumerates the entries of the host.
+    ///
+    /// See [`RawCpuid::enumerate`].
+    #[must_use]
+    pub fn from_host() -> Self {
+        Self::enumerate(__cpuid_count)
+    }
+
+    /// Enumerates every basic, hypervisor and extended leaf returned by `cpuid`, walking the
+    /// sub-leaves of each leaf according to its own rules, with the flags KVM sets
+    /// (see [`KvmCpuidFlags::for_leaf`]).
+    ///
+    /// The hypervisor range is only enumerated when the hypervisor present bit (`ecx[31]` of
+    /// leaf 1) is set.
+    pub fn enumerate<F: FnMut(u32, u32) -> CpuidResult>(mut cpuid: F) -> Self {
+        match Self::try_enumerate(|leaf, sub_leaf| Ok::<_, Infallible>(cpuid(leaf, sub_leaf))) {
+            Ok(cpuid) => cpuid,
+            Err(infallible) => match infallible {},
+        }
+    }
+
+    /// Enumerates like [`RawCpuid::enumerate`] with a fallible `cpuid`.
+    ///
+    /// # Errors
+    ///
+    /// When `cpuid` errors.
+    pub fn try_enumerate<E, F: FnMut(u32, u32) -> Result<CpuidResult, E>>(
+        mut cpuid: F,
+    ) -> Result<Self, E> {
+        let mut entries = Vec::new();
+
+        let max_basic = cpuid(0, 0)?.eax;
+        for leaf in 0..=max_basic {
+            entries.extend(sub_leaves(&mut cpuid, leaf)?);
+        }
+
+        if cpuid(1, 0)?.ecx & HYPERVISOR_PRESENT != 0 {
+            let max_hypervisor = cpuid(HYPERVISOR_LEAF, 0)?.eax;
+            if (HYPERVISOR_LEAF..=HYPERVISOR_LEAF_MAX).contains(&max_hypervisor) {
+                for leaf in HYPERVISOR_LEAF..=max_hypervisor {
+                    entries.extend(sub_leaves(&mut cpuid, leaf)?);
+                }
+            }
+        }
+
+        let max_extended = cpuid(EXTENDED_LEAF, 0)?.eax;
+        if max_extended >= EXTENDED_LEAF {
+            for leaf in EXTENDED_LEAF..=max_extended {
+                entries.extend(sub_leaves(&mut cpuid, leaf)?);
+            }
+        }
+
+        Ok(Self::from(entries))
+    }
+
+    /// Enumerates the entries of each logical CPU of the host.
+    ///
+    /// Reads through [`CpuidDevice::default`], falling back to
+    /// [`RawCpuid::per_cpu_pinned`] when the `cpuid` driver is unavailable.
+    ///
+    /// # Errors
+    ///
+    /// When both methods fail, returning the error of the fallback.
+    pub fn per_cpu() -> io::Result<Vec<Self>> {
+        CpuidDevice::default()
+            .read_all()
+            .or_else(|_| Self::per_cpu_pinned())
+    }
+
+    /// Enumerates the entries of each logical CPU the calling thread may run on, by pinning the
+    /// calling thread to each in turn with `sched_setaffinity`.
+    ///
+    /// The affinity of the calling thread is restored afterwards.
+    ///
+    /// # Errors
+    ///
+    /// When getting or setting the affinity fails.
+    pub fn per_cpu_pinned() -> io::Result<Vec<Self>> {
+        let original = get_affinity()?;
+        let cpus = (0..libc::CPU_SETSIZE as usize)
+            // SAFETY: `cpu` is less than `CPU_SETSIZE`, the number of CPUs `original` holds.
+            .filter(|cpu| unsafe { libc::CPU_ISSET(*cpu, &original) })
+            .collect::<Vec<_>>();
+
+        let per_cpu = cpus
+            .into_iter()
+            .map(|cpu| {
+                // SAFETY: `cpu_set_t` is a bit mask, for which all zeroes is the empty set.
+                let mut set = unsafe { mem::zeroed::<libc::cpu_set_t>() };
+                // SAFETY: `cpu` is less than `CPU_SETSIZE`, the number of CPUs `set` holds.
+                unsafe { libc::CPU_SET(cpu, &mut set) };
+                set_affinity(&set).map(|()| Self::from_host())
+            })
+            .collect::<io::Result<Vec<_>>>();
+
+        set_affinity(&original)?;
+        per_cpu
+    }
+}
+
+/// Returns the affinity of the calling thread.
+fn get_affinity() -> io::Result<libc::cpu_set_t> {
+    // SAFETY: `cpu_set_t` is a bit mask, for which all zeroes is the empty set.
+    let mut set = unsafe { mem::zeroed::<libc::cpu_set_t>() };
+    // SAFETY: `set` is a valid `cpu_set_t` of the given size.
+    match unsafe { libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &raw mut set) } {
+        0 => Ok(set),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Sets the affinity of the calling thread.
+fn set_affinity(set: &libc::cpu_set_t) -> io::Result<()> {
+    // SAFETY: `set` is a valid `cpu_set_t` of the given size.
+    match unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), set) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// A source of the `cpuid` of each logical CPU, e.g. the `cpuid` driver ([`CpuidDevice`]).
+pub trait CpuidReader {
+    /// Returns the logical CPUs, in ascending order.
+    ///
+    /// # Errors
+    ///
+    /// When the CPUs cannot be listed.
+    fn cpus(&self) -> io::Result<Vec<usize>>;
+
+    /// Returns what the `cpuid` instruction returns on a logical CPU for a given leaf and
+    /// sub-leaf.
+    ///
+    /// # Errors
+    ///
+    /// When the CPU cannot be read.
+    fn cpuid(&self, cpu: usize, leaf: u32, sub_leaf: u32) -> io::Result<CpuidResult>;
+
+    /// Enumerates the entries of a logical CPU (see [`RawCpuid::enumerate`]).
+    ///
+    /// # Errors
+    ///
+    /// When the CPU cannot be read.
+    fn read(&self, cpu: usize) -> io::Result<RawCpuid> {
+        RawCpuid::try_enumerate(|leaf, sub_leaf| self.cpuid(cpu, leaf, sub_leaf))
+    }
+
+    /// Enumerates the entries of each logical CPU, in ascending order of CPU.
+    ///
+    /// # Errors
+    ///
+    /// When there are no CPUs or any CPU cannot be read.
+    fn read_all(&self) -> io::Result<Vec<RawCpuid>> {
+        let cpus = self.cpus()?;
+        if cpus.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "No CPUs"));
+        }
+        cpus.into_iter().map(|cpu| self.read(cpu)).collect()
+    }
+}
+
+/// The `cpuid` driver, exposing the `cpuid` instruction of each logical CPU `n` at
+/// `<root>/<n>/cpuid`.
+///
+/// Reading 16 bytes at offset `ecx << 32 | eax` returns `eax`, `ebx`, `ecx` and `edx` (in native
+/// endianness) for leaf `eax` and sub-leaf `ecx`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuidDevice {
+    root: PathBuf,
+}
+impl CpuidDevice {
+    /// Constructs a [`CpuidDevice`] with a given root.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Opens the device of a logical CPU.
+    fn open(&self, cpu: usize) -> io::Result<File> {
+        File::open(self.root.join(cpu.to_string()).join("cpuid"))
+    }
+}
+impl CpuidReader for CpuidDevice {
+    /// Returns the logical CPUs with a `cpuid` device, in ascending order.
+    fn cpus(&self) -> io::Result<Vec<usize>> {
+        let mut cpus = Vec::new();
+        for dir in std::fs::read_dir(&self.root)? {
+            let dir = dir?;
+            let cpu = dir.file_name().to_str().and_then(|name| name.parse().ok());
+            if let Some(cpu) = cpu {
+                if dir.path().join("cpuid").exists() {
+                    cpus.push(cpu);
+                }
+            }
+        }
+        cpus.sort_unstable();
+        Ok(cpus)
+    }
+
+    fn cpuid(&self, cpu: usize, leaf: u32, sub_leaf: u32) -> io::Result<CpuidResult> {
+        read_record(&self.open(cpu)?, leaf, sub_leaf)
+    }
+
+    /// Enumerates the entries of a logical CPU, opening its device once.
+    fn read(&self, cpu: usize) -> io::Result<RawCpuid> {
+        let file = self.open(cpu)?;
+        RawCpuid::try_enumerate(|leaf, sub_leaf| read_record(&file, leaf, sub_leaf))
+    }
+}
+impl Default for CpuidDevice {
+    /// The `cpuid` driver at `/dev/cpu`.
+    fn default() -> Self {
+        Self::new("/dev/cpu")
+    }
+}
+
+/// Reads the record of a leaf and sub-leaf from a `cpuid` device.
+fn read_record(file: &File, leaf: u32, sub_leaf: u32) -> io::Result<CpuidResult> {
+    let mut buf = [0; 16];
+    file.read_exact_at(&mut buf, (u64::from(sub_leaf) << 32) | u64::from(leaf))?;
+    let register =
+        |i: usize| u32::from_ne_bytes([buf[i * 4], buf[i * 4 + 1], buf[i * 4 + 2], buf[i * 4 + 3]]);
+    Ok(CpuidResult {
+        eax: register(0),
+        ebx: register(1),
+        ecx: register(2),
+        edx: register(3),
+    })
+}
+
+/// Returns the entries of all the sub-leaves of a leaf.
+fn sub_leaves<E, F: FnMut(u32, u32) -> Result<CpuidResult, E>>(
+    cpuid: &mut F,
+    leaf: u32,
+) -> Result<Vec<RawCpuidEntry>, E> {
+    let first = cpuid(leaf, 0)?;
+    let indices: Vec<u32> = match leaf {
+        // Deterministic cache parameters, until the null cache type (inclusive).
+        0x4 | 0x8000_001D => {
+            let mut indices = vec![0];
+            let mut eax = first.eax;
+            while eax & 0x1F != 0 {
+                let index = u32::try_from(indices.len()).unwrap();
+                indices.push(index);
+                eax = cpuid(leaf, index)?.eax;
+            }
+            indices
+        }
+        // Extended topology, until the invalid level type (inclusive).
+        0xB | 0x1F => {
+            let mut indices = vec![0];
+            let mut ecx = first.ecx;
+            while ecx & 0xFF00 != 0 {
+                let index = u32::try_from(indices.len()).unwrap();
+                indices.push(index);
+                ecx = cpuid(leaf, index)?.ecx;
+            }
+            indices
+        }
+        // Processor extended state, sub-leaves 0 and 1 then each state component supported in
+        // XCR0 or IA32_XSS.
+        0xD => {
+            let xcr0 = u64::from(first.eax) | (u64::from(first.edx) << 32);
+            let second = cpuid(leaf, 1)?;
+            let xss = u64::from(second.ecx) | (u64::from(second.edx) << 32);
+            let mask = xcr0 | xss;
+            [0, 1]
+                .into_iter()
+                .chain((2..64).filter(|i| mask & (1 << i) != 0))
+                .collect()
+        }
+        // Resource director technology monitoring, sub-leaf 0 then each resource type in `edx`.
+        0xF => std::iter::once(0)
+            .chain((1..32).filter(|i| first.edx & (1 << i) != 0))
+            .collect(),
+        // Resource director technology allocation, sub-leaf 0 then each resource ID in `ebx`.
+        0x10 => std::iter::once(0)
+            .chain((1..32).filter(|i| first.ebx & (1 << i) != 0))
+            .collect(),
+        // SGX, sub-leaves 0 and 1 then EPC sections until the invalid section (inclusive), when
+        // SGX1 or SGX2 is supported.
+        0x12 if first.eax.trailing_zeros() >= 2 => vec![0],
+        0x12 => {
+            let mut indices = vec![0, 1];
+            loop {
+                let index = u32::try_from(indices.len()).unwrap();
+                indices.push(index);
+                if cpuid(leaf, index)?.eax & 0xF != 1 {
+                    break indices;
+                }
+            }
+        }
+        // Sub-leaf 0 reports the maximum sub-leaf in `eax`. Leaf 0x20 does too, but KVM does not
+        // index it so only its sub-leaf 0 is enumerated.
+        0x7 | 0x14 | 0x17 | 0x18 | 0x1D => (0..=first.eax).collect(),
+        _ => vec![0],
+    };
+    let flags = KvmCpuidFlags::for_leaf(leaf);
+    indices
+        .into_iter()
+        .map(|index| {
+            let CpuidResult { eax, ebx, ecx, edx } = if index == 0 {
+                first
+            } else {
+                cpuid(leaf, index)?
+            };
+            Ok(RawCpuidEntry::new(leaf, index, flags, eax, ebx, ecx, edx))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpuid_ffi::fixture;
+
+    fn sorted(mut cpuid: RawCpuid) -> RawCpuid {
+        cpuid.sort();
+        cpuid
+    }
+
+    #[test]
+    fn enumerate_intel() {
+        // Enumerating an emulation of the entries KVM enumerated recovers exactly those entries.
+        let intel = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let enumerated = RawCpuid::enumerate(|leaf, sub_leaf| intel.query(leaf, sub_leaf));
+        assert_eq!(sorted(enumerated), sorted(intel));
+    }
+
+    #[test]
+    fn enumerate_amd() {
+        // The fixture is abridged, so the enumeration also contains the missing leaves.
+        let amd = fixture(include_str!("../fixtures/amd-zen2.txt"));
+        let enumerated = RawCpuid::enumerate(|leaf, sub_leaf| amd.query(leaf, sub_leaf));
+        for entry in &amd {
+            assert!(enumerated.iter().any(|e| e == entry), "{entry:x}");
+        }
+        // Leaf 0xD sub-leaf 2 (AVX) and 9 (PKRU) are supported in XCR0.
+        assert!(enumerated.get(0xD, 2).is_some());
+        assert!(enumerated.get(0xD, 9).is_some());
+        assert!(enumerated.get(0xD, 3).is_none());
+        // Highest basic and extended leaves.
+        assert_eq!(
+            enumerated
+                .iter()
+                .rfind(|e| e.function < 0x4000_0000)
+                .unwrap()
+                .function,
+            0x10
+        );
+        assert_eq!(enumerated.iter().next_back().unwrap().function, 0x8000_001F);
+        // Deterministic cache parameters until the null cache type, which AMD lacks here.
+        assert_eq!(
+            enumerated
+                .iter()
+                .filter(|e| e.function == 0x8000_001D)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn enumerate_sub_leaves() {
+        let mut cpuid = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let topology = |index, level_type: u32| {
+            RawCpuidEntry::new(
+                0x1F,
+                index,
+                KvmCpuidFlags::SIGNIFICANT_INDEX,
+                1,
+                1,
+                (level_type << 8) | index,
+                0,
+            )
+        };
+        cpuid.insert(topology(0, 1)).unwrap();
+        cpuid.insert(topology(1, 2)).unwrap();
+        cpuid.insert(topology(2, 0)).unwrap();
+        // SGX1 with a single EPC section.
+        cpuid.get_mut(0x12, 0).unwrap().eax = 1;
+        cpuid
+            .insert(RawCpuidEntry::new(
+                0x12,
+                2,
+                KvmCpuidFlags::SIGNIFICANT_INDEX,
+                1,
+                0,
+                0,
+                0,
+            ))
+            .unwrap();
+
+        let enumerated = RawCpuid::enumerate(|leaf, sub_leaf| cpuid.query(leaf, sub_leaf));
+        let indices = |leaf| {
+            enumerated
+                .iter()
+                .filter(|e| e.function == leaf)
+                .map(|e| e.index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(indices(0x1F), [0, 1, 2]);
+        assert_eq!(indices(0x12), [0, 1, 2, 3]);
+        assert_eq!(indices(0x4), [0, 1, 2, 3, 4]);
+        assert_eq!(indices(0x7), [0, 1, 2]);
+        assert_eq!(indices(0xD), [0, 1, 2, 5, 6, 7, 9]);
+        // Leaf 0x20 reports a maximum sub-leaf, but is not indexed.
+        cpuid.get_mut(0x20, 0).unwrap().eax = 1;
+        let enumerated = RawCpuid::enumerate(|leaf, sub_leaf| cpuid.query(leaf, sub_leaf));
+        assert_eq!(enumerated.iter().filter(|e| e.function == 0x20).count(), 1);
+        // Every leaf enumerated per sub-leaf is flagged as such.
+        for entry in enumerated.iter().filter(|e| e.index != 0) {
+            assert!(
+                KvmCpuidFlags::for_leaf(entry.function).contains(KvmCpuidFlags::SIGNIFICANT_INDEX),
+                "{entry:x}"
+            );
+        }
+    }
+
+    /// Emulates the `cpuid` of each CPU with the given entries.
+    struct Emulated(Vec<RawCpuid>);
+    impl CpuidReader for Emulated {
+        fn cpus(&self) -> io::Result<Vec<usize>> {
+            Ok((0..self.0.len()).collect())
+        }
+
+        fn cpuid(&self, cpu: usize, leaf: u32, sub_leaf: u32) -> io::Result<CpuidResult> {
+            self.0
+                .get(cpu)
+                .map(|cpuid| cpuid.query(leaf, sub_leaf))
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        }
+    }
+
+    #[test]
+    fn reader() {
+        let intel = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let mut second = intel.clone();
+        // A different initial APIC ID.
+        second.get_mut(1, 0).unwrap().ebx = 0x0101_0800;
+
+        let emulated = Emulated(vec![intel.clone(), second.clone()]);
+        let per_cpu = emulated.read_all().unwrap();
+        assert_eq!(per_cpu.len(), 2);
+        assert_eq!(sorted(per_cpu[0].clone()), sorted(intel));
+        assert_eq!(sorted(per_cpu[1].clone()), sorted(second));
+        assert!(emulated.read(2).is_err());
+        assert!(Emulated(Vec::new()).read_all().is_err());
+
+        let missing = CpuidDevice::new(std::env::temp_dir().join("test-cpuid-missing"));
+        assert!(missing.read_all().is_err());
+    }
+
+    #[test]
+    fn device() {
+        // The driver maps each offset to a leaf and sub-leaf while a file maps it to a byte, so the
+        // records of adjacent leaves would overlap. Only records at least 16 bytes apart fit: leaves
+        // 0 and 0x8000_0000, as the highest leaves of their ranges, and sub-leaves 1 to 3 of leaf 4.
+        let xeon = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let mut intel = RawCpuid::from(
+            xeon.iter()
+                .filter(|entry| matches!(entry.function, 0 | EXTENDED_LEAF))
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+        intel.get_mut(0, 0).unwrap().eax = 0;
+        intel.get_mut(EXTENDED_LEAF, 0).unwrap().eax = EXTENDED_LEAF;
+        let mut second = intel.clone();
+        // A different (reserved) `ebx` of leaf 0x8000_0000.
+        second.get_mut(EXTENDED_LEAF, 0).unwrap().ebx = 1;
+
+        // Emulates the driver with sparse files holding each record at `sub_leaf << 32 | leaf`.
+        let root = std::env::temp_dir().join(format!("test-cpuid-device-{}", std::process::id()));
+        let records = |cpuid: &RawCpuid| {
+            cpuid
+                .iter()
+                .chain((1..4).map(|sub_leaf| xeon.get(4, sub_leaf).unwrap()))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        for (cpu, cpuid) in [&intel, &second].into_iter().enumerate() {
+            let dir = root.join(cpu.to_string());
+            std::fs::create_dir_all(&dir).unwrap();
+            let file = File::create(dir.join("cpuid")).unwrap();
+            // Reads of other leaves return zeros rather than reaching the end of the file.
+            file.set_len(1 << 40).unwrap();
+            for entry in records(cpuid) {
+                let record = [entry.eax, entry.ebx, entry.ecx, entry.edx]
+                    .into_iter()
+                    .flat_map(u32::to_ne_bytes)
+                    .collect::<Vec<_>>();
+                let offset = (u64::from(entry.index) << 32) | u64::from(entry.function);
+                file.write_all_at(&record, offset).unwrap();
+            }
+        }
+
+        let device = CpuidDevice::new(&root);
+        let per_cpu = device.read_all();
+        let sub_leaves = (1..4)
+            .map(|sub_leaf| device.cpuid(1, 4, sub_leaf))
+            .collect::<io::Result<Vec<_>>>();
+        std::fs::remove_dir_all(&root).unwrap();
+        let per_cpu = per_cpu.unwrap();
+        assert_eq!(per_cpu.len(), 2);
+        assert_eq!(sorted(per_cpu[0].clone()), sorted(intel));
+        assert_eq!(sorted(per_cpu[1].clone()), sorted(second));
+        for (sub_leaf, result) in (1..4).zip(sub_leaves.unwrap()) {
+            assert_eq!(result, xeon.query(4, sub_leaf));
+        }
+    }
+
+    #[test]
+    fn device_host() {
+        let device = CpuidDevice::default();
+        if let Ok(per_cpu) = device.read_all() {
+            assert_eq!(per_cpu.len(), device.cpus().unwrap().len());
+            assert_eq!(per_cpu[0].query(0, 0), __cpuid_count(0, 0));
+        }
+    }
+
+    #[test]
+    fn pinned() {
+        let original = get_affinity().unwrap();
+        let per_cpu = RawCpuid::per_cpu_pinned().unwrap();
+        assert_eq!(
+            per_cpu.len(),
+            // SAFETY: `original` is a valid `cpu_set_t`.
+            usize::try_from(unsafe { libc::CPU_COUNT(&original) }).unwrap()
+        );
+        assert_eq!(per_cpu[0].query(0, 0), __cpuid_count(0, 0));
+        // The affinity is restored.
+        // SAFETY: Both are valid `cpu_set_t`s.
+        assert!(unsafe { libc::CPU_EQUAL(&original, &get_affinity().unwrap()) });
+        assert!(!RawCpuid::per_cpu().unwrap().is_empty());
+    }
+
+    #[test]
+    fn from_host() {
+        let host = RawCpuid::from_host();
+        assert_eq!(host.query(0, 0), __cpuid_count(0, 0));
+        assert_eq!(host.query(1, 0).eax, __cpuid_count(1, 0).eax);
+        let mut deduped = host.clone();
+        deduped.dedup();
+        assert_eq!(deduped, host);
+        for entry in &host {
+            assert_eq!(entry.flags, KvmCpuidFlags::for_leaf(entry.function));
+        }
+    }
+}
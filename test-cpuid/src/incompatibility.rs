@@ -0,0 +1,351 @@
+use std::fmt;
+
+use bit_fields::{FieldMismatch, Policy};
+
+use crate::diff::{elements, fields};
+use crate::Register;
+
+/// Where in a leaf an [`IncompatibilityKind::Mismatch`] is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    /// A whole register.
+    Register(Register),
+    /// A field of a register.
+    Field(Register, String),
+    /// A bit of a register which is not part of any field (see [`crate::register_fields`]).
+    Bit(Register, u8),
+}
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(register) => write!(f, "{register}"),
+            Self::Field(register, field) => write!(f, "{register} `{field}`"),
+            Self::Bit(register, bit) => write!(f, "{register} bit {bit}"),
+        }
+    }
+}
+
+/// How a leaf is incompatible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncompatibilityKind {
+    /// The whole leaf (sub-leaf) is present in the CPUID checked against but missing.
+    Missing,
+    /// A register, field or bit does not satisfy the rule it is checked with.
+    Mismatch {
+        /// Where in the leaf.
+        location: Location,
+        /// The rule `this` must satisfy against `other`.
+        rule: Policy,
+        /// The value in the CPUID checked.
+        this: u128,
+        /// The value in the CPUID checked against.
+        other: u128,
+    },
+}
+
+/// A leaf which is missing, or a register, field or bit of it which does not satisfy the rule it
+/// is checked with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incompatibility {
+    /// The leaf (function).
+    pub leaf: u32,
+    /// The sub-leaf (index).
+    pub sub_leaf: u32,
+    /// How the leaf is incompatible.
+    pub kind: IncompatibilityKind,
+}
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}.{:#x}", self.leaf, self.sub_leaf)?;
+        match &self.kind {
+            IncompatibilityKind::Missing => write!(f, ": missing"),
+            IncompatibilityKind::Mismatch {
+                location,
+                rule,
+                this,
+                other,
+            } => write!(f, " {location}: {this:#x} {rule} {other:#x} does not hold"),
+        }
+    }
+}
+
+/// The reasons a CPUID does not cover (support) another, returned by [`crate::Cpuid::covers`]
+/// and [`crate::intel::IntelCpuid::supports`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Incompatibilities(pub Vec<Incompatibility>);
+impl fmt::Display for Incompatibilities {
+    /// Renders each incompatibility on its own line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, incompatibility) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{incompatibility}")?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for Incompatibilities {}
+
+impl Incompatibilities {
+    /// Returns `Ok(())` if there are no incompatibilities.
+    fn into_result(self) -> Result<(), Self> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Collects the incompatibilities of several checks.
+    ///
+    /// # Errors
+    ///
+    /// When any check fails, returning the incompatibilities of all of them.
+    pub fn combine(checks: impl IntoIterator<Item = Result<(), Self>>) -> Result<(), Self> {
+        Self(
+            checks
+                .into_iter()
+                .filter_map(Result::err)
+                .flat_map(|incompatibilities| incompatibilities.0)
+                .collect(),
+        )
+        .into_result()
+    }
+
+    /// Checks every bit set in `other` is set in `this`, naming the fields of the missing bits
+    /// when the leaf is described for `vendor` (see [`crate::RawCpuid::vendor`] and
+    /// [`crate::register_fields`]).
+    pub(crate) fn superset(
+        vendor: [u32; 3],
+        leaf: u32,
+        sub_leaf: u32,
+        register: Register,
+        this: u32,
+        other: u32,
+    ) -> Result<(), Self> {
+        let missing = other & !this;
+        let mut named = 0;
+        let mut incompatibilities = Vec::new();
+        for (ident, start, end) in fields(vendor, leaf, sub_leaf, register)
+            .iter()
+            .flat_map(elements)
+        {
+            let mask = u32::MAX
+                .checked_shr(32 - u32::from(end - start))
+                .unwrap_or(0)
+                << start;
+            if missing & mask == 0 {
+                continue;
+            }
+            named |= mask;
+            incompatibilities.push(Incompatibility {
+                leaf,
+                sub_leaf,
+                kind: IncompatibilityKind::Mismatch {
+                    location: Location::Field(register, ident),
+                    rule: Policy::Superset,
+                    this: u128::from((this & mask) >> start),
+                    other: u128::from((other & mask) >> start),
+                },
+            });
+        }
+        incompatibilities.extend(
+            (0..32)
+                .filter(|bit| missing & !named & (1 << bit) != 0)
+                .map(|bit| Incompatibility {
+                    leaf,
+                    sub_leaf,
+                    kind: IncompatibilityKind::Mismatch {
+                        location: Location::Bit(register, bit),
+                        rule: Policy::Superset,
+                        this: u128::from((this >> bit) & 1),
+                        other: 1,
+                    },
+                }),
+        );
+        Self(incompatibilities).into_result()
+    }
+
+    /// Checks a whole register satisfies `rule`, use [`Incompatibilities::superset`] to name the
+    /// fields of missing bits instead.
+    pub(crate) fn register(
+        leaf: u32,
+        sub_leaf: u32,
+        register: Register,
+        rule: Policy,
+        this: u32,
+        other: u32,
+    ) -> Result<(), Self> {
+        match rule.check("", this.into(), other.into()) {
+            Some(_) => Err(Self(vec![Incompatibility {
+                leaf,
+                sub_leaf,
+                kind: IncompatibilityKind::Mismatch {
+                    location: Location::Register(register),
+                    rule,
+                    this: this.into(),
+                    other: other.into(),
+                },
+            }])),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a field of a register satisfies `rule`.
+    pub(crate) fn field(
+        leaf: u32,
+        sub_leaf: u32,
+        register: Register,
+        field: &'static str,
+        rule: Policy,
+        this: impl Into<u128>,
+        other: impl Into<u128>,
+    ) -> Result<(), Self> {
+        Self::mismatches(
+            leaf,
+            sub_leaf,
+            register,
+            rule.check(field, this.into(), other.into())
+                .map_or(Ok(()), |mismatch| Err(vec![mismatch])),
+        )
+    }
+
+    /// Converts the mismatches returned by the `supports` function of a register's bit field.
+    pub(crate) fn mismatches(
+        leaf: u32,
+        sub_leaf: u32,
+        register: Register,
+        mismatches: Result<(), Vec<FieldMismatch>>,
+    ) -> Result<(), Self> {
+        mismatches.map_err(|mismatches| {
+            Self(
+                mismatches
+                    .into_iter()
+                    .map(|mismatch| Incompatibility {
+                        leaf,
+                        sub_leaf,
+                        kind: IncompatibilityKind::Mismatch {
+                            location: Location::Field(register, String::from(mismatch.field)),
+                            rule: mismatch.policy,
+                            this: mismatch.this,
+                            other: mismatch.other,
+                        },
+                    })
+                    .collect(),
+            )
+        })
+    }
+
+    /// Checks a leaf which may be absent, it must be present in `this` when present in `other`.
+    pub(crate) fn optional<T>(
+        leaf: u32,
+        sub_leaf: u32,
+        this: Option<&T>,
+        other: Option<&T>,
+        supports: impl FnOnce(&T, &T) -> Result<(), Self>,
+    ) -> Result<(), Self> {
+        match (this, other) {
+            (Some(this), Some(other)) => supports(this, other),
+            (_, None) => Ok(()),
+            (None, Some(_)) => Err(Self(vec![Incompatibility {
+                leaf,
+                sub_leaf,
+                kind: IncompatibilityKind::Missing,
+            }])),
+        }
+    }
+
+    /// Checks the enumerated sub-leaves of a leaf, `this[n]` and `other[n]` being sub-leaf
+    /// `first + n`, each sub-leaf present in `other` must be present in `this`.
+    pub(crate) fn sub_leaves<T>(
+        leaf: u32,
+        first: u32,
+        this: &[T],
+        other: &[T],
+        supports: impl Fn(&T, &T, u32) -> Result<(), Self>,
+    ) -> Result<(), Self> {
+        Self::combine(
+            (first..)
+                .zip(other)
+                .enumerate()
+                .map(|(i, (sub_leaf, other))| {
+                    Self::optional(leaf, sub_leaf, this.get(i), Some(other), |this, other| {
+                        supports(this, other, sub_leaf)
+                    })
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GENUINE_INTEL;
+
+    #[test]
+    fn superset() {
+        assert_eq!(
+            Incompatibilities::superset(GENUINE_INTEL, 7, 0, Register::Ebx, 0b11, 0b1),
+            Ok(())
+        );
+
+        // Leaf 7 `ebx`: `avx512f` (bit 16) and `avx512vl` (bit 31) are missing.
+        let incompatibilities =
+            Incompatibilities::superset(GENUINE_INTEL, 7, 0, Register::Ebx, 0, 1 << 16 | 1 << 31)
+                .unwrap_err();
+        assert_eq!(
+            incompatibilities.0[0].kind,
+            IncompatibilityKind::Mismatch {
+                location: Location::Field(Register::Ebx, String::from("avx512f")),
+                rule: Policy::Superset,
+                this: 0,
+                other: 1,
+            }
+        );
+        assert_eq!(incompatibilities.0.len(), 2);
+        assert_eq!(
+            incompatibilities.to_string(),
+            "0x7.0x0 ebx `avx512f`: 0x0 superset 0x1 does not hold\n0x7.0x0 ebx `avx512vl`: 0x0 \
+             superset 0x1 does not hold"
+        );
+
+        // Undefined leaves name the bits.
+        assert_eq!(
+            Incompatibilities::superset(GENUINE_INTEL, 0x4000_0001, 0, Register::Eax, 1, 0b110)
+                .unwrap_err()
+                .to_string(),
+            "0x40000001.0x0 eax bit 1: 0x0 superset 0x1 does not hold\n0x40000001.0x0 eax bit 2: \
+             0x0 superset 0x1 does not hold"
+        );
+
+        // Only Intel's leaves are named.
+        assert_eq!(
+            Incompatibilities::superset([0; 3], 7, 0, Register::Ebx, 0, 1 << 16)
+                .unwrap_err()
+                .to_string(),
+            "0x7.0x0 ebx bit 16: 0x0 superset 0x1 does not hold"
+        );
+    }
+
+    #[test]
+    fn combine() {
+        let result = Incompatibilities::combine([
+            Incompatibilities::register(0, 0, Register::Eax, Policy::GreaterOrEqual, 0xd, 0x16),
+            Ok(()),
+            Incompatibilities::field(6, 0, Register::Ebx, "thresholds", Policy::Equal, 1u8, 2u8),
+            Incompatibilities::optional(7, 1, None, Some(&()), |(), ()| Ok(())),
+            Incompatibilities::optional(7, 1, Some(&()), None, |(), ()| unreachable!()),
+            Incompatibilities::sub_leaves(4, 0, &[0], &[0, 1], |_, _, sub_leaf| {
+                assert_eq!(sub_leaf, 0);
+                Ok(())
+            }),
+        ]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "0x0.0x0 eax: 0xd >= 0x16 does not hold\n0x6.0x0 ebx `thresholds`: 0x1 == 0x2 does \
+             not hold\n0x7.0x1: missing\n0x4.0x1: missing"
+        );
+        assert_eq!(Incompatibilities::combine([Ok(()), Ok(())]), Ok(()));
+    }
+}
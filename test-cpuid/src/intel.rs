@@ -1,13 +1,19 @@
 #![warn(clippy::pedantic)]
-#![allow(unused_variables, unreachable_code)] // To avoid warning on todo's for now
-#![allow(clippy::similar_names, clippy::module_name_repetitions)]
+#![allow(unused_variables, unused_assignments, unreachable_code)] // To avoid warning on todo's for now
+#![allow(
+    clippy::similar_names,
+    clippy::module_name_repetitions,
+    clippy::missing_errors_doc
+)]
 use std::fmt;
 
-use bit_fields::bitfield;
+use bit_fields::{bitfield, BitField, Policy, UnknownDiscriminantErr};
 use log_derive::{logfn, logfn_inputs};
 
-use super::{FixedString, RawCpuid};
+use super::{FixedString, Incompatibilities, KvmCpuidFlags, RawCpuid, RawCpuidEntry, Register};
 
+/// Description of a leaf 2 descriptor byte missing from [`KEYWORDS`].
+const UNKNOWN_DESCRIPTOR: &str = "Unknown descriptor";
 static KEYWORDS: phf::Map<u8, &'static str> = phf::phf_map! {
     0x00u8 => "Null descriptor, this byte contains no information",
     0x01u8 => "Instruction TLB: 4 KByte pages, 4-way set associative, 32 entries",
@@ -124,58 +130,6 @@ static KEYWORDS: phf::Map<u8, &'static str> = phf::phf_map! {
     0xFFu8 => "CPUID leaf 2 does not report cache descriptor information, use CPUID leaf 4 to query cache parameters"
 };
 
-#[derive(Debug)]
-pub struct TlbCachePrefetchInformation {
-    /// Maximum Input Value for Basic CPUID Information.
-    eax: [u8; 4],
-    /// “Genu”
-    ebx: [u8; 4],
-    /// “ntel”
-    ecx: [u8; 4],
-    /// “ineI”
-    edx: [u8; 4],
-}
-impl fmt::Display for TlbCachePrefetchInformation {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let a: [&'static str; 16] = self.into();
-        write!(f, "{:#?}", a)
-    }
-}
-// - The least-significant-byte of eax always returns 01h.
-// - The most significant bit indicates whether the register contains valid information (TODO Does
-//   this mean we only have 3 descriptors per register?)
-impl From<&TlbCachePrefetchInformation> for [&'static str; 16] {
-    fn from(this: &TlbCachePrefetchInformation) -> Self {
-        [
-            KEYWORDS.get(&this.eax[0]).unwrap(),
-            KEYWORDS.get(&this.eax[1]).unwrap(),
-            KEYWORDS.get(&this.eax[2]).unwrap(),
-            KEYWORDS.get(&this.eax[3]).unwrap(),
-            KEYWORDS.get(&this.ebx[0]).unwrap(),
-            KEYWORDS.get(&this.ebx[1]).unwrap(),
-            KEYWORDS.get(&this.ebx[2]).unwrap(),
-            KEYWORDS.get(&this.ebx[3]).unwrap(),
-            KEYWORDS.get(&this.ecx[0]).unwrap(),
-            KEYWORDS.get(&this.ecx[1]).unwrap(),
-            KEYWORDS.get(&this.ecx[2]).unwrap(),
-            KEYWORDS.get(&this.ecx[3]).unwrap(),
-            KEYWORDS.get(&this.edx[0]).unwrap(),
-            KEYWORDS.get(&this.edx[1]).unwrap(),
-            KEYWORDS.get(&this.edx[2]).unwrap(),
-            KEYWORDS.get(&this.edx[3]).unwrap(),
-        ]
-    }
-}
-impl From<(u32, u32, u32, u32)> for TlbCachePrefetchInformation {
-    fn from((eax, ebx, ecx, edx): (u32, u32, u32, u32)) -> Self {
-        Self {
-            eax: eax.to_ne_bytes(),
-            ebx: ebx.to_ne_bytes(),
-            ecx: ecx.to_ne_bytes(),
-            edx: edx.to_ne_bytes(),
-        }
-    }
-}
 // -------------------------------------------------------------------------------------------------
 // Leaf 1.0
 // -------------------------------------------------------------------------------------------------
@@ -199,13 +153,13 @@ bitfield!(Leaf1Ebx, u32, {
     /// Brand Index.
     brand_index, 0..8,
     /// CLFLUSH line size (Value ∗ 8 = cache line size in bytes; used also by CLFLUSHOPT).
-    clflush, 8..16,
+    clflush, 8..16 >=,
     /// Maximum number of addressable IDs for logical processors in this physical package.
     ///
     /// The nearest power-of-2 integer that is not smaller than EBX[23:16] is the number of unique 
     /// initial APIC IDs reserved for addressing different logical processors in a physical package. 
     /// This field is only valid if CPUID.1.EDX.HTT[bit 28]= 1.
-    max_addressable_logical_processor_ids: 16..24,
+    max_addressable_logical_processor_ids: 16..24 >=,
     /// Initial APIC ID.
     ///
     /// The 8-bit initial APIC ID in EBX[31:24] is replaced by the 32-bit x2APIC ID, available in 
@@ -408,6 +362,57 @@ bitfield!(Leaf1Edx, u32, {
     pbe: 31,
 });
 // -------------------------------------------------------------------------------------------------
+// Leaf 2
+// -------------------------------------------------------------------------------------------------
+// - The least-significant-byte of eax always returns 01h.
+// - The most significant bit indicates whether the register contains valid information (TODO Does
+//   this mean we only have 3 descriptors per register?)
+#[rustfmt::skip]
+bitfield!(Leaf2Eax, u32, {
+    /// TLB, cache and prefetch descriptors.
+    descriptors[4]: 0..32 step 8,
+});
+#[rustfmt::skip]
+bitfield!(Leaf2Ebx, u32, {
+    /// TLB, cache and prefetch descriptors.
+    descriptors[4]: 0..32 step 8,
+});
+#[rustfmt::skip]
+bitfield!(Leaf2Ecx, u32, {
+    /// TLB, cache and prefetch descriptors.
+    descriptors[4]: 0..32 step 8,
+});
+#[rustfmt::skip]
+bitfield!(Leaf2Edx, u32, {
+    /// TLB, cache and prefetch descriptors.
+    descriptors[4]: 0..32 step 8,
+});
+impl fmt::Display for Leaf2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let a: [&'static str; 16] = self.into();
+        write!(f, "{a:#?}")
+    }
+}
+impl From<&Leaf2> for [&'static str; 16] {
+    fn from(this: &Leaf2) -> Self {
+        let mut descriptors = this
+            .eax
+            .descriptors
+            .iter()
+            .chain(this.ebx.descriptors.iter())
+            .chain(this.ecx.descriptors.iter())
+            .chain(this.edx.descriptors.iter())
+            .map(|descriptor| {
+                u8::try_from(descriptor)
+                    .ok()
+                    .and_then(|descriptor| KEYWORDS.get(&descriptor))
+                    .copied()
+                    .unwrap_or(UNKNOWN_DESCRIPTOR)
+            });
+        std::array::from_fn(|_| descriptors.next().unwrap())
+    }
+}
+// -------------------------------------------------------------------------------------------------
 // Leaf 3
 // -------------------------------------------------------------------------------------------------
 #[rustfmt::skip]
@@ -445,9 +450,9 @@ bitfield!(Leaf4Eax, u32, {
     /// Cache Level (starts at 1).
     cache_level: 5..8,
     /// Self Initializing cache level (does not need SW initialization).
-    sicl: 8,
+    sicl: 8 ignore,
     /// Fully Associative cache.
-    fac: 9,
+    fac: 9 ignore,
     // Reserved 10..14
     /// Maximum number of addressable IDs for logical processors sharing this cache.
     /// - Add one to the return value to get the result.
@@ -493,15 +498,15 @@ bitfield!(Leaf4Edx, u32, {
     ///   sharing this cache.
     /// - 1 = WBINVD/INVD is not guaranteed to act upon lower level caches of non-originating 
     ///   threads sharing this cache.
-    write_back_invalidate: 0,
+    write_back_invalidate: 0 ignore,
     /// Cache Inclusiveness.
     /// - 0 = Cache is not inclusive of lower cache levels.
     /// - 1 = Cache is inclusive of lower cache levels.
-    cache_inclusiveness: 1,
+    cache_inclusiveness: 1 ignore,
     /// Complex Cache Indexing.
     /// - 0 = Direct mapped cache.
     /// - 1 = A complex function is used to index the cache, potentially using all address bits.
-    complex_cache_indexing: 2
+    complex_cache_indexing: 2 ignore
 });
 // -------------------------------------------------------------------------------------------------
 // Leaf 5
@@ -509,13 +514,13 @@ bitfield!(Leaf4Edx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf5Eax, u32, {
     // Smallest monitor-line size in bytes (default is processor's monitor granularity).
-    smallest_monitor_line_size: 0..16,
+    smallest_monitor_line_size: 0..16 <=,
     // Reserved
 });
 #[rustfmt::skip]
 bitfield!(Leaf5Ebx, u32, {
     // Largest monitor-line size in bytes (default is processor's monitor granularity).
-    largest_monitor_line_size: 0..16,
+    largest_monitor_line_size: 0..16 >=,
     // Reserved
 });
 #[rustfmt::skip]
@@ -532,42 +537,42 @@ bitfield!(Leaf5Edx, u32, {
     ///
     /// The definition of C0 through C7 states for MWAIT extension are processor-specific C-states, 
     /// not ACPI Cstates.
-    c0_states: 0..4,
+    c0_states: 0..4 >=,
     /// Number of C1* sub C-states supported using MWAIT.
     ///
     /// The definition of C0 through C7 states for MWAIT extension are processor-specific C-states, 
     /// not ACPI Cstates.
-    c1_states: 4..8,
+    c1_states: 4..8 >=,
     /// Number of C2* sub C-states supported using MWAIT.
     ///
     /// The definition of C0 through C7 states for MWAIT extension are processor-specific C-states, 
     /// not ACPI Cstates.
-    c2_states: 8..12,
+    c2_states: 8..12 >=,
     /// Number of C3* sub C-states supported using MWAIT.
     ///
     /// The definition of C0 through C7 states for MWAIT extension are processor-specific C-states, 
     /// not ACPI Cstates.
-    c3_states: 12..16,
+    c3_states: 12..16 >=,
     /// Number of C4* sub C-states supported using MWAIT.
     ///
     /// The definition of C0 through C7 states for MWAIT extension are processor-specific C-states, 
     /// not ACPI Cstates.
-    c4_states: 16..20,
+    c4_states: 16..20 >=,
     /// Number of C5* sub C-states supported using MWAIT.
     ///
     /// The definition of C0 through C7 states for MWAIT extension are processor-specific C-states, 
     /// not ACPI Cstates.
-    c5_states: 20..24,
+    c5_states: 20..24 >=,
     /// Number of C6* sub C-states supported using MWAIT.
     ///
     /// The definition of C0 through C7 states for MWAIT extension are processor-specific C-states, 
     /// not ACPI Cstates.
-    c6_states: 24..28,
+    c6_states: 24..28 >=,
     /// Number of C7* sub C-states supported using MWAIT.
     ///
     /// The definition of C0 through C7 states for MWAIT extension are processor-specific C-states, 
     /// not ACPI Cstates.
-    c7_states: 28..32
+    c7_states: 28..32 >=,
 });
 // -------------------------------------------------------------------------------------------------
 // Leaf 6
@@ -628,7 +633,7 @@ bitfield!(Leaf6Eax, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf6Ebx, u32, {
     /// Number of Interrupt Thresholds in Digital Thermal Sensor.
-    number_of_interrupt_thresholds_in_digital_thermal_sensor: 0..4,
+    number_of_interrupt_thresholds_in_digital_thermal_sensor: 0..4 >=,
     // Reserved 4..=31
 });
 #[rustfmt::skip]
@@ -646,7 +651,7 @@ bitfield!(Leaf6Ecx, u32, {
     /// Reserved 04..=07
     /// Number of Intel® Thread Director classes supported by the processor. Information for that
     /// many classes is written into the Intel Thread Director Table by the hardware.
-    intel_thread_director_classes: 8..16,
+    intel_thread_director_classes: 8..16 >=,
     // Reserved 16..=31
 });
 #[rustfmt::skip]
@@ -655,7 +660,7 @@ bitfield!(Leaf6Edx, u32, {
     /// - 0 = When set to 1, indicates support for performance capability reporting.
     /// - 1 = When set to 1, indicates support for energy efficiency capability reporting.
     /// - 2-7 = Reserved
-    bitmap_hardware_feedback_interface_capabilities: 0..8,
+    bitmap_hardware_feedback_interface_capabilities: 0..8 superset,
     /// Enumerates the size of the hardware feedback interface structure in number of 4 KB pages; 
     /// add one to the return value to get the result.
     enum_hardware_feedback_interface_4k: 8..12,
@@ -663,7 +668,7 @@ bitfield!(Leaf6Edx, u32, {
     /// structure. Note that on some parts the index may be same for multiple logical processors. On
     /// some parts the indices may not be contiguous, i.e., there may be unused rows in the hardware
     /// feedback interface structure.
-    index: 16..32
+    index: 16..32 ignore,
 });
 // -------------------------------------------------------------------------------------------------
 // Leaf 7
@@ -671,7 +676,7 @@ bitfield!(Leaf6Edx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf7Subleaf0Eax, u32, {
     /// Reports the maximum input value for supported leaf 7 sub-leaves.
-    max_input_value_subleaf: 0..32
+    max_input_value_subleaf: 0..32 >=
 });
 #[rustfmt::skip]
 bitfield!(Leaf7Subleaf0Ebx, u32, {
@@ -694,7 +699,7 @@ bitfield!(Leaf7Subleaf0Ebx, u32, {
     /// BMI2.
     bmi2: 8,
     /// Supports Enhanced REP MOVSB/STOSB if 1.
-    suports_enhanced_rep_movsb_stosb: 9,
+    erms: 9,
     /// INVPCID. If 1, supports INVPCID instruction for system software that manages process-context 
     /// identifiers.
     invpcid: 10,
@@ -722,7 +727,7 @@ bitfield!(Leaf7Subleaf0Ebx, u32, {
     avx512_ifma: 21,
     // Reserved
     /// CLFLUSHOPT.
-    clfushopt: 23,
+    clflushopt: 23,
     /// CLWB.
     clwb: 24,
     /// Intel Processor Trace.
@@ -791,7 +796,7 @@ bitfield!(Leaf7Subleaf0Ecx, u32, {
     /// MOVDIRI. Supports MOVDIRI if 1.
     movdiri: 27,
     /// MOVDIR64B. Supports MOVDIR64B if 1.
-    movdiri64b: 28,
+    movdir64b: 28,
     // Reserved
     /// SGX_LC. Supports SGX Launch Configuration if 1.
     sgx_lc: 30,
@@ -817,7 +822,7 @@ bitfield!(Leaf7Subleaf0Edx, u32, {
     /// SERIALIZE.
     serialize:  11..14,
     /// Hybrid. If 1, the processor is identified as a hybrid part.
-    hydrid: 15,
+    hybrid: 15,
     // Reserved 16..=17
     /// PCONFIG. Supports PCONFIG if 1.
     pconfig: 18,
@@ -888,7 +893,7 @@ bitfield!(Leaf7Subleaf1Edx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf9Eax, u32, {
     /// Value of bits [31:0] of IA32_PLATFORM_DCA_CAP MSR (address 1F8H).
-    ia32_platform_dca_cap_msr: 0..32
+    ia32_platform_dca_cap_msr: 0..32 ==
 });
 #[rustfmt::skip]
 bitfield!(Leaf9Ebx, u32, {
@@ -1010,7 +1015,7 @@ bitfield!(LeafDSubleaf0Eax, u32, {
     // Bits 31 - 00: Reports the supported bits of the lower 32 bits of XCR0. XCR0[n] can be set to 
     // 1 only if EAX[n] is 1.
     /// x87 state.
-    x86_state: 0,
+    x87_state: 0,
     /// SSE state.
     sse_state: 1,
     /// AVX state.
@@ -1019,16 +1024,13 @@ bitfield!(LeafDSubleaf0Eax, u32, {
     mpx_state: 3..5,
     /// AVX-512 state.
     avx512_state: 5..8,
-    /// Used for IA32_XSS.
-    used_for_ia32_xss: 8,
+    // 8 used for IA32_XSS
     /// PKRU state.
     pkru_state: 9,
     // Reserved 10..=12
-    /// Used for IA32_XSS.
-    used_for_ia32_xss_1: 13,
+    // 13 used for IA32_XSS
     // Reserved 14..=15
-    /// Used for IA32_XSS.
-    used_for_ia32_xss_2: 16,
+    // 16 used for IA32_XSS
     // Reserved 17..=31
 });
 #[rustfmt::skip]
@@ -1036,7 +1038,7 @@ bitfield!(LeafDSubleaf0Ebx, u32, {
     /// Maximum size (bytes, from the beginning of the XSAVE/XRSTOR save area) required by enabled 
     /// features in XCR0. May be different than ECX if some features at the end of the XSAVE save
     /// area are not enabled.
-    maximum_size: 0..32
+    maximum_size: 0..32 >=
 });
 #[rustfmt::skip]
 bitfield!(LeafDSubleaf0Ecx, u32, {
@@ -1045,7 +1047,7 @@ bitfield!(LeafDSubleaf0Ecx, u32, {
     /// fields in XCR0.
     ///
     // `LeafDSubleaf0Ecx::maximum_size() >= LeafDSubleaf0Ebx::maximum_size()`
-    maximum_size: 0..32
+    maximum_size: 0..32 >=
 });
 #[rustfmt::skip]
 bitfield!(LeafDSubleaf0Edx, u32, {
@@ -1077,7 +1079,7 @@ bitfield!(LeafDSubleaf1Ecx, u32, {
     // Reports the supported bits of the lower 32 bits of the IA32_XSS MSR. IA32_XSS[n] can be set 
     // to 1 only if ECX[n] is 1.
     /// Used for XCR0.
-    xcr0_1: 0..8,
+    xcr0_1: 0..8 superset,
     /// PT state.
     pt_state: 8,
     /// Used for XCR0.
@@ -1107,7 +1109,7 @@ bitfield!(LeafDSubleaf1Edx, u32, {
 bitfield!(LeafDSubleafGt1Eax, u32, {
     /// The size in bytes (from the offset specified in EBX) of the save area for an extended state 
     /// feature associated with a valid sub-leaf index, n.
-    save_area_size: 0..32,
+    save_area_size: 0..32 ==,
 });
 #[rustfmt::skip]
 bitfield!(LeafDSubleafGt1Ebx, u32, {
@@ -1121,7 +1123,7 @@ bitfield!(LeafDSubleafGt1Ebx, u32, {
     /// (0 ≤ n ≤ 31) is invalid if sub-leaf 0 returns 0 in EAX[n] and sub-leaf 1 returns 0 in 
     /// ECX[n]. Sub-leaf n (32 ≤ n ≤ 63) is invalid if sub-leaf 0 returns 0 in EDX[n-32] and 
     /// sub-leaf 1 returns 0 in EDX[n-32].
-    save_area_offset: 0..32
+    save_area_offset: 0..32 ==
 });
 #[rustfmt::skip]
 bitfield!(LeafDSubleafGt1Ecx, u32, {
@@ -1134,7 +1136,7 @@ bitfield!(LeafDSubleafGt1Ecx, u32, {
     /// (0 ≤ n ≤ 31) is invalid if sub-leaf 0 returns 0 in EAX[n] and sub-leaf 1 returns 0 in 
     /// ECX[n]. Sub-leaf n (32 ≤ n ≤ 63) is invalid if sub-leaf 0 returns 0 in EDX[n-32] and 
     /// sub-leaf 1 returns 0 in EDX[n-32].
-    supported_ia32_xss_msr: 0,
+    supported_ia32_xss_msr: 0..1 ==,
     /// Is set if, when the compacted format of an XSAVE area is used, this extended state component
     /// located on the next 64-byte boundary following the preceding state component (otherwise, it 
     /// is located immediately following the preceding state component).
@@ -1145,14 +1147,78 @@ bitfield!(LeafDSubleafGt1Ecx, u32, {
     /// (0 ≤ n ≤ 31) is invalid if sub-leaf 0 returns 0 in EAX[n] and sub-leaf 1 returns 0 in 
     /// ECX[n]. Sub-leaf n (32 ≤ n ≤ 63) is invalid if sub-leaf 0 returns 0 in EDX[n-32] and 
     /// sub-leaf 1 returns 0 in EDX[n-32].
-    compacted_xsave_used: 1,
+    compacted_xsave_used: 1 ignore,
     // 0..=31 reserved
+}, match supported_ia32_xss_msr {
+    /// The state component is a user state component, supported in XCR0.
+    0 => Xcr0,
+    /// The state component is a supervisor state component, supported in the IA32_XSS MSR.
+    1 => Ia32Xss,
 });
 #[rustfmt::skip]
 bitfield!(LeafDSubleafGt1Edx, u32, {
     // This field reports 0 if the sub-leaf index, n, is invalid*; otherwise it is reserved.
     // 0..=31 reserved
 });
+/// The layouts of leaf DH sub-leaves >1, selected by
+/// [`LeafDSubleafGt1Ecx::supported_ia32_xss_msr`].
+#[derive(Debug, Clone)]
+pub enum LeafDSubleafGt1Layout {
+    /// A user state component, which has an offset in the standard format of the XSAVE area.
+    Xcr0 {
+        /// The size of the save area.
+        eax: LeafDSubleafGt1Eax,
+        /// The offset of the save area.
+        ebx: LeafDSubleafGt1Ebx,
+        /// The alignment of the save area.
+        ecx: LeafDSubleafGt1EcxXcr0,
+    },
+    /// A supervisor state component, which is only saved in the compacted format so EBX is 0.
+    Ia32Xss {
+        /// The size of the save area.
+        eax: LeafDSubleafGt1Eax,
+        /// The alignment of the save area.
+        ecx: LeafDSubleafGt1EcxIa32Xss,
+    },
+}
+impl LeafDSubleafGt1 {
+    /// Returns the layout of `self` selected by [`LeafDSubleafGt1Ecx::supported_ia32_xss_msr`].
+    ///
+    /// # Errors
+    ///
+    /// Never, the discriminant is a single bit.
+    pub fn layout(&self) -> Result<LeafDSubleafGt1Layout, UnknownDiscriminantErr<u32>> {
+        Ok(match self.ecx.layout()? {
+            LeafDSubleafGt1EcxLayout::Xcr0(ecx) => LeafDSubleafGt1Layout::Xcr0 {
+                eax: self.eax.clone(),
+                ebx: self.ebx.clone(),
+                ecx,
+            },
+            LeafDSubleafGt1EcxLayout::Ia32Xss(ecx) => LeafDSubleafGt1Layout::Ia32Xss {
+                eax: self.eax.clone(),
+                ecx,
+            },
+        })
+    }
+}
+impl From<LeafDSubleafGt1Layout> for LeafDSubleafGt1 {
+    fn from(layout: LeafDSubleafGt1Layout) -> Self {
+        match layout {
+            LeafDSubleafGt1Layout::Xcr0 { eax, ebx, ecx } => Self::from((
+                eax,
+                ebx,
+                LeafDSubleafGt1Ecx::from(LeafDSubleafGt1EcxLayout::Xcr0(ecx)),
+                LeafDSubleafGt1Edx::from(0),
+            )),
+            LeafDSubleafGt1Layout::Ia32Xss { eax, ecx } => Self::from((
+                eax,
+                LeafDSubleafGt1Ebx::from(0),
+                LeafDSubleafGt1Ecx::from(LeafDSubleafGt1EcxLayout::Ia32Xss(ecx)),
+                LeafDSubleafGt1Edx::from(0),
+            )),
+        }
+    }
+}
 // -------------------------------------------------------------------------------------------------
 // Leaf F
 // -------------------------------------------------------------------------------------------------
@@ -1164,7 +1230,7 @@ bitfield!(LeafFSubleaf0Eax, u32, {
 #[rustfmt::skip]
 bitfield!(LeafFSubleaf0Ebx, u32, {
     /// Maximum range (zero-based) of RMID within this physical processor of all types.
-    max_rmid_range: 0..32,
+    max_rmid_range: 0..32 >=,
 });
 #[rustfmt::skip]
 bitfield!(LeafFSubleaf0Ecx, u32, {
@@ -1186,12 +1252,12 @@ bitfield!(LeafFSubleaf1Eax, u32, {
 bitfield!(LeafFSubleaf1Ebx, u32, {
     /// Conversion factor from reported IA32_QM_CTR value to occupancy metric (bytes) and Memory 
     /// Bandwidth Monitoring (MBM) metrics.
-    ia32_qm_ctr_conv_factor: 0..32,
+    ia32_qm_ctr_conv_factor: 0..32 ==,
 });
 #[rustfmt::skip]
 bitfield!(LeafFSubleaf1Ecx, u32, {
     /// Maximum range (zero-based) of RMID of this resource type.
-    rmid_max: 0..32,
+    rmid_max: 0..32 >=,
 });
 #[rustfmt::skip]
 bitfield!(LeafFSubleaf1Edx, u32, {
@@ -1235,13 +1301,13 @@ bitfield!(Leaf10Subleaf0Edx, u32, {
 bitfield!(Leaf10Subleaf1Eax, u32, {
     /// Length of the capacity bit mask for the corresponding ResID. Add one to the return value to 
     /// get the result.
-    len_cap_resid_mask: 0..5,
+    len_cap_resid_mask: 0..5 >=,
     // 5..=31 reserved
 });
 #[rustfmt::skip]
 bitfield!(Leaf10Subleaf1Ebx, u32, {
     /// Bit-granular map of isolation/contention of allocation units.
-    granular_iso_cont_map: 0..32,
+    granular_iso_cont_map: 0..32 superset,
 });
 #[rustfmt::skip]
 bitfield!(Leaf10Subleaf1Ecx, u32, {
@@ -1253,7 +1319,7 @@ bitfield!(Leaf10Subleaf1Ecx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf10Subleaf1Edx, u32, {
     /// Highest COS number supported for this ResID.
-    highest_cos_resid: 0..16,
+    highest_cos_resid: 0..16 >=,
     // 0..=31 reserved
 });
 // Leaf 2
@@ -1261,13 +1327,13 @@ bitfield!(Leaf10Subleaf1Edx, u32, {
 bitfield!(Leaf10Subleaf2Eax, u32, {
     /// Length of the capacity bit mask for the corresponding ResID. Add one to the return value to 
     /// get the result.
-    len_cap_resid_mask: 0..5,
+    len_cap_resid_mask: 0..5 >=,
     // 5..=31 reserved
 });
 #[rustfmt::skip]
 bitfield!(Leaf10Subleaf2Ebx, u32, {
     /// Bit-granular map of isolation/contention of allocation units.
-    granular_iso_cont_map: 0..32,
+    granular_iso_cont_map: 0..32 superset,
 });
 #[rustfmt::skip]
 bitfield!(Leaf10Subleaf2Ecx, u32, {
@@ -1276,7 +1342,7 @@ bitfield!(Leaf10Subleaf2Ecx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf10Subleaf2Edx, u32, {
     /// Highest COS number supported for this ResID.
-    highest_cos_resid: 0..16,
+    highest_cos_resid: 0..16 >=,
     // 0..=31 reserved
 });
 // Leaf 3
@@ -1284,7 +1350,7 @@ bitfield!(Leaf10Subleaf2Edx, u32, {
 bitfield!(Leaf10Subleaf3Eax, u32, {
     /// Reports the maximum MBA throttling value supported for the corresponding ResID. Add one to 
     /// the return value to get the result.
-    max_mba_throt_resid: 0..12,
+    max_mba_throt_resid: 0..12 >=,
     // reserved 12..=31
 });
 #[rustfmt::skip]
@@ -1301,7 +1367,7 @@ bitfield!(Leaf10Subleaf3Ecx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf10Subleaf3Edx, u32, {
     /// Highest COS number supported for this ResID.
-    highest_cos_resid: 0..16,
+    highest_cos_resid: 0..16 >=,
     // 16..=31 reserved
 });
 // -------------------------------------------------------------------------------------------------
@@ -1326,7 +1392,7 @@ bitfield!(Leaf12Subleaf0Eax, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf12Subleaf0Ebx, u32, {
     /// MISCSELECT. Bit vector of supported extended SGX features.
-    miscselect: 0..32,
+    miscselect: 0..32 superset,
 });
 #[rustfmt::skip]
 bitfield!(Leaf12Subleaf0Ecx, u32, {
@@ -1335,31 +1401,31 @@ bitfield!(Leaf12Subleaf0Ecx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf12Subleaf0Edx, u32, {
     /// MaxEnclaveSize_Not64. The maximum supported enclave size in non-64-bit mode is 2^(EDX[7:0]).
-    max_enclave_size_not_64: 0..8,
+    max_enclave_size_not_64: 0..8 >=,
     /// MaxEnclaveSize_64. The maximum supported enclave size in 64-bit mode is 2^(EDX[15:8]).
-    max_enclave_size_64: 8..16,
+    max_enclave_size_64: 8..16 >=,
     // 16..=31 reserved
 });
 // Leaf 1
 #[rustfmt::skip]
 bitfield!(Leaf12Subleaf1Eax, u32, {
     /// Reports the valid bits of SECS.ATTRIBUTES[31:0] that software can set with ECREATE.
-    ecreate_attrs_0_31: 0..32,
+    ecreate_attrs_0_31: 0..32 superset,
 });
 #[rustfmt::skip]
 bitfield!(Leaf12Subleaf1Ebx, u32, {
     /// Reports the valid bits of SECS.ATTRIBUTES[63:32] that software can set with ECREATE.
-    ecreate_attrs_32_63: 0..32,
+    ecreate_attrs_32_63: 0..32 superset,
 });
 #[rustfmt::skip]
 bitfield!(Leaf12Subleaf1Ecx, u32, {
     /// Reports the valid bits of SECS.ATTRIBUTES[95:64] that software can set with ECREATE.
-    ecreate_attrs_64_95: 0..32,
+    ecreate_attrs_64_95: 0..32 superset,
 });
 #[rustfmt::skip]
 bitfield!(Leaf12Subleaf1Edx, u32, {
     /// Reports the valid bits of SECS.ATTRIBUTES[127:96] that software can set with ECREATE.
-    ecreate_attrs_96_127: 0..32,
+    ecreate_attrs_96_127: 0..32 superset,
 });
 // Leaf >1
 #[rustfmt::skip]
@@ -1370,10 +1436,15 @@ bitfield!(Leaf12SubleafGt1Eax, u32, {
     /// Enclave Page Cache (EPC) section.
     /// All other type encodings are reserved.
     subleaf_type: 0..4,
-    /// Bits 31:12 of the physical address of the base of the EPC section.
-    ///
-    /// When EAX[03:00] = 0001b (otherwise 0)
-    epc_base_31_12: 12..32,
+}, match subleaf_type {
+    /// Indicates this sub-leaf is invalid.
+    0b0000 => Invalid,
+    /// This sub-leaf enumerates an EPC section. EBX:EAX and EDX:ECX provide information on the
+    /// Enclave Page Cache (EPC) section.
+    0b0001 => EpcSection {
+        /// Bits 31:12 of the physical address of the base of the EPC section.
+        epc_base_31_12: 12..32,
+    },
 });
 #[rustfmt::skip]
 bitfield!(Leaf12SubleafGt1Ebx, u32, {
@@ -1410,6 +1481,59 @@ bitfield!(Leaf12SubleafGt1Edx, u32, {
     epc_reserved_51_32: 0..20,
     // 20..=31 reserved
 });
+/// The layouts of leaf 12H sub-leaves >1, selected by [`Leaf12SubleafGt1Eax::subleaf_type`].
+#[derive(Debug, Clone)]
+pub enum Leaf12SubleafGt1Layout {
+    /// Indicates this sub-leaf is invalid, EBX, ECX and EDX are 0.
+    Invalid(Leaf12SubleafGt1EaxInvalid),
+    /// This sub-leaf enumerates an EPC section.
+    EpcSection {
+        /// Bits 31:12 of the base of the EPC section.
+        eax: Leaf12SubleafGt1EaxEpcSection,
+        /// Bits 51:32 of the base of the EPC section.
+        ebx: Leaf12SubleafGt1Ebx,
+        /// The EPC section property and bits 31:12 of its size.
+        ecx: Leaf12SubleafGt1Ecx,
+        /// Bits 51:32 of the size of the EPC section.
+        edx: Leaf12SubleafGt1Edx,
+    },
+}
+impl Leaf12SubleafGt1 {
+    /// Returns the layout of `self` selected by [`Leaf12SubleafGt1Eax::subleaf_type`].
+    ///
+    /// # Errors
+    ///
+    /// When [`Leaf12SubleafGt1Eax::subleaf_type`] is a reserved encoding.
+    pub fn layout(&self) -> Result<Leaf12SubleafGt1Layout, UnknownDiscriminantErr<u32>> {
+        Ok(match self.eax.layout()? {
+            Leaf12SubleafGt1EaxLayout::Invalid(eax) => Leaf12SubleafGt1Layout::Invalid(eax),
+            Leaf12SubleafGt1EaxLayout::EpcSection(eax) => Leaf12SubleafGt1Layout::EpcSection {
+                eax,
+                ebx: self.ebx.clone(),
+                ecx: self.ecx.clone(),
+                edx: self.edx.clone(),
+            },
+        })
+    }
+}
+impl From<Leaf12SubleafGt1Layout> for Leaf12SubleafGt1 {
+    fn from(layout: Leaf12SubleafGt1Layout) -> Self {
+        match layout {
+            Leaf12SubleafGt1Layout::Invalid(eax) => Self::from((
+                Leaf12SubleafGt1Eax::from(Leaf12SubleafGt1EaxLayout::Invalid(eax)),
+                Leaf12SubleafGt1Ebx::from(0),
+                Leaf12SubleafGt1Ecx::from(0),
+                Leaf12SubleafGt1Edx::from(0),
+            )),
+            Leaf12SubleafGt1Layout::EpcSection { eax, ebx, ecx, edx } => Self::from((
+                Leaf12SubleafGt1Eax::from(Leaf12SubleafGt1EaxLayout::EpcSection(eax)),
+                ebx,
+                ecx,
+                edx,
+            )),
+        }
+    }
+}
 // -------------------------------------------------------------------------------------------------
 // Leaf 14
 // -------------------------------------------------------------------------------------------------
@@ -1420,7 +1544,7 @@ bitfield!(Leaf14Subleaf0Eax, u32, {
     ///
     /// **At the moment of writing the Intel specification only notes the format of ECX=1, therefore
     /// this field should only be 0 or 1**
-    max_subleaf: 0..32,
+    max_subleaf: 0..32 >=,
 });
 #[rustfmt::skip]
 bitfield!(Leaf14Subleaf0Ebx, u32, {
@@ -1475,17 +1599,17 @@ bitfield!(Leaf14Subleaf0Edx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf14Subleaf1Eax, u32, {
     /// Number of configurable Address Ranges for filtering.
-    configurable_filterig_addr_ranges: 0..3,
+    configurable_filterig_addr_ranges: 0..3 >=,
     // 3..=15 reserved
     /// Bitmap of supported MTC period encodings.
-    mtc_period_encodings: 16..32,
+    mtc_period_encodings: 16..32 superset,
 });
 #[rustfmt::skip]
 bitfield!(Leaf14Subleaf1Ebx, u32, {
     /// Bitmap of supported Cycle Threshold value encodings.
-    cycle_threshold_value_encodings: 0..16,
+    cycle_threshold_value_encodings: 0..16 superset,
     /// Bitmap of supported Configurable PSB frequency encodings.
-    configurable_psb_freq_encodings: 16..32,
+    configurable_psb_freq_encodings: 16..32 superset,
 });
 #[rustfmt::skip]
 bitfield!(Leaf14Subleaf1Ecx, u32, {
@@ -1507,7 +1631,7 @@ bitfield!(Leaf15Eax, u32, {
     /// the nominal core crystal clock frequency is not enumerated. “TSC frequency” = “core crystal 
     /// clock frequency” * EBX/EAX. The core crystal clock may differ from the reference clock, bus 
     /// clock, or core clock frequencies.
-    tsc_denominator: 0..32,
+    tsc_denominator: 0..32 ==,
 
 });
 #[rustfmt::skip]
@@ -1519,7 +1643,7 @@ bitfield!(Leaf15Ebx, u32, {
     /// the nominal core crystal clock frequency is not enumerated. “TSC frequency” = “core crystal 
     /// clock frequency” * EBX/EAX. The core crystal clock may differ from the reference clock, bus 
     /// clock, or core clock frequencies.
-    tsc_numerator: 0..32,
+    tsc_numerator: 0..32 ==,
 });
 #[rustfmt::skip]
 bitfield!(Leaf15Ecx, u32, {
@@ -1530,7 +1654,7 @@ bitfield!(Leaf15Ecx, u32, {
     /// the nominal core crystal clock frequency is not enumerated. “TSC frequency” = “core crystal 
     /// clock frequency” * EBX/EAX. The core crystal clock may differ from the reference clock, bus 
     /// clock, or core clock frequencies.
-    nominal_freqeuncy: 0..32
+    nominal_freqeuncy: 0..32 ==
 });
 #[rustfmt::skip]
 bitfield!(Leaf15Edx, u32, {
@@ -1576,7 +1700,7 @@ bitfield!(Leaf17Subleaf0Ebx, u32, {
     soc_vendor_id: 0..16,
     /// IsVendorScheme. If 1, the SOC Vendor ID field is assigned via an industry standard
     /// enumeration scheme. Otherwise, the SOC Vendor ID field is assigned by Intel.
-    is_vendor_scheme: 16,
+    is_vendor_scheme: 16 ignore,
     // 17..=31 reserved
 });
 #[rustfmt::skip]
@@ -1654,7 +1778,7 @@ bitfield!(Leaf17SubleafGt3Edx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf18Subleaf0Eax, u32, {
     /// Reports the maximum input value of supported sub-leaf in leaf 18H.
-    max_subleaf: 0..32,
+    max_subleaf: 0..32 >=,
 });
 #[rustfmt::skip]
 bitfield!(Leaf18Subleaf0Ebx, u32, {
@@ -1671,7 +1795,7 @@ bitfield!(Leaf18Subleaf0Ebx, u32, {
     partitioning: 8..16,
     // 11..=15 reserved
     /// W = Ways of associativity.
-    ways_of_associativity: 16..32,
+    ways_of_associativity: 16..32 >=,
 });
 #[rustfmt::skip]
 bitfield!(Leaf18Subleaf0Ecx, u32, {
@@ -1697,7 +1821,7 @@ bitfield!(Leaf18Subleaf0Edx, u32, {
     /// Translation cache level (starts at 1).
     translation_cache_level: 5..8,
     /// Fully associative structure.
-    fully_associative_structure: 8,
+    fully_associative_structure: 8 ignore,
     // 9..=13 reserved
     /// Maximum number of addressable IDs for logical processors sharing this translation cache**
     ///
@@ -1751,7 +1875,7 @@ bitfield!(Leaf18SubleafGt0Edx, u32, {
     /// Translation cache level (starts at 1).
     translation_cache_level: 5..8,
     /// Fully associative structure.
-    fully_associative_structure: 8,
+    fully_associative_structure: 8 ignore,
     // 9..=13 reserved
     /// Maximum number of addressable IDs for logical processors sharing this translation cache**
     ///
@@ -1853,7 +1977,7 @@ bitfield!(Leaf1BEdx, u32, {
 bitfield!(Leaf1CEax, u32, {
     /// Supported LBR Depth Values. For each bit n set in this field, the IA32_LBR_DEPTH.DEPTH value
     /// 8*(n+1) is supported.
-    lbr_depth_values: 0..8,
+    lbr_depth_values: 0..8 superset,
     // 9..=28 reserved
     /// Deep C-state Reset. If set, indicates that LBRs may be cleared on an MWAIT that requests a 
     /// C-state numerically greater than C1.
@@ -1943,7 +2067,9 @@ bitfield!(Leaf1FEdx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf20Eax, u32, {
     /// Reports the maximum number of sub-leaves that are supported in leaf 20H.
-    max_subleaves: 0..32,
+    #[since("SDM-075")]
+    #[vendor(intel)]
+    max_subleaves: 0..32 >=,
 });
 #[rustfmt::skip]
 bitfield!(Leaf20Ebx, u32, {
@@ -1952,7 +2078,9 @@ bitfield!(Leaf20Ebx, u32, {
 
     /// Indicates support for both HRESET’s EAX[0] parameter, and IA32_HRESET_ENABLE[0] set by the 
     /// OS to enable reset of Intel® Thread Director history.
-    hreset: 0,
+    #[since("SDM-075")]
+    #[vendor(intel)]
+    thread_director_history_reset: 0,
     // 1..=31 reserved
 });
 #[rustfmt::skip]
@@ -1969,7 +2097,7 @@ bitfield!(Leaf20Edx, u32, {
 #[rustfmt::skip]
 bitfield!(Leaf80000000Eax, u32, {
     /// Maximum Input Value for Extended Function CPUID Information.
-    max_extend_function_input: 0..32,
+    max_extend_function_input: 0..32 >=,
 });
 #[rustfmt::skip]
 bitfield!(Leaf80000000Ebx, u32, {
@@ -2001,13 +2129,16 @@ bitfield!(Leaf80000001Ecx, u32, {
     ///
     /// * LAHF and SAHF are always available in other modes, regardless of the enumeration of this 
     /// feature flag.
+    #[vendor(intel | amd)]
     lahf_sahf: 0,
     // 1..=4 reserved
     /// LZCNT.
+    #[vendor(intel | amd)]
     lzcnt: 5,
     // 6..=7 reserved
     /// PREFETCHW.
-    prefetchcw: 8,
+    #[vendor(intel | amd)]
+    prefetchw: 8,
     // 9..=31 reserved
 });
 #[rustfmt::skip]
@@ -2017,17 +2148,22 @@ bitfield!(Leaf80000001Edx, u32, {
     ///
     /// ** Intel processors support SYSCALL and SYSRET only in 64-bit mode. This feature flag is 
     /// always enumerated as 0 outside 64-bit mode.
+    #[vendor(intel | amd)]
     syscall_sysret: 11,
     // 12..=19 reserved
     /// Execute Disable Bit available.
+    #[vendor(intel | amd)]
     execute_disable_bit: 20,
     // 21..=25 reserved
     /// 1-GByte pages are available if 1.
+    #[vendor(intel | amd)]
     pages_1g: 26,
     /// RDTSCP and IA32_TSC_AUX are available if 1.
+    #[vendor(intel | amd)]
     rdtscp_and_ia32_tsc_aux: 27,
     // Reserved.
     /// Intel® 64 Architecture available if 1.
+    #[vendor(intel | amd)]
     arch64: 29,
     // 30..=31 reserved
 });
@@ -2184,7 +2320,7 @@ impl<A, B, C, D> From<(A, B, C, D)> for Leaf<A, B, C, D> {
 }
 type Leaf0 = Leaf<u32, FixedString<4>, FixedString<4>, FixedString<4>>;
 type Leaf1 = Leaf<Leaf1Eax, Leaf1Ebx, Leaf1Ecx, Leaf1Edx>;
-type Leaf2 = TlbCachePrefetchInformation;
+type Leaf2 = Leaf<Leaf2Eax, Leaf2Ebx, Leaf2Ecx, Leaf2Edx>;
 type Leaf3 = Leaf<Leaf3Eax, Leaf3Ebx, Leaf3Ecx, Leaf3Edx>;
 type Leaf4 = Leaf<Leaf4Eax, Leaf4Ebx, Leaf4Ecx, Leaf4Edx>;
 type Leaf5 = Leaf<Leaf5Eax, Leaf5Ebx, Leaf5Ecx, Leaf5Edx>;
@@ -2278,378 +2414,699 @@ type Leaf80000008 = Leaf<Leaf80000008Eax, Leaf80000008Ebx, Leaf80000008Ecx, Leaf
 impl Leaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax >= other.eax
-            && self.ebx == other.ebx
-            && self.ecx == other.ecx
-            && self.edx == other.edx
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        let manufacturer_id = |register, this: &FixedString<4>, other: &FixedString<4>| {
+            Incompatibilities::register(
+                0,
+                0,
+                register,
+                Policy::Equal,
+                u32::from_ne_bytes(this.0),
+                u32::from_ne_bytes(other.0),
+            )
+        };
+        Incompatibilities::combine([
+            Incompatibilities::register(
+                0,
+                0,
+                Register::Eax,
+                Policy::GreaterOrEqual,
+                self.eax,
+                other.eax,
+            ),
+            manufacturer_id(Register::Ebx, &self.ebx, &other.ebx),
+            manufacturer_id(Register::Ecx, &self.ecx, &other.ecx),
+            manufacturer_id(Register::Edx, &self.edx, &other.edx),
+        ])
     }
 }
 impl Leaf1 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.ebx.clflush >= other.ebx.clflush
-            && self.ebx.max_addressable_logical_processor_ids
-                >= other.ebx.max_addressable_logical_processor_ids
-            && self.ecx.superset(&other.ecx)
-            && self.edx.superset(&other.edx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(1, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(1, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(1, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf2 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(2, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(2, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(2, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(2, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf3 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(3, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(3, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf4 {
+    /// Checks `self` supports `other`, both being sub-leaf `sub_leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, sub_leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                4,
+                sub_leaf,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                4,
+                sub_leaf,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                4,
+                sub_leaf,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                4,
+                sub_leaf,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl Leaf5 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.smallest_monitor_line_size <= other.eax.smallest_monitor_line_size
-            && self.ebx.largest_monitor_line_size >= other.ebx.largest_monitor_line_size
-            && self.ecx.superset(&other.ecx)
-        // TODO edx
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(5, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(5, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(5, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(5, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf6 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.superset(&other.eax)
-            && self
-                .ebx
-                .number_of_interrupt_thresholds_in_digital_thermal_sensor
-                >= other
-                    .ebx
-                    .number_of_interrupt_thresholds_in_digital_thermal_sensor
-            && self.ecx.intel_thread_director_classes >= other.ecx.intel_thread_director_classes
-            && self.ecx.superset(&other.ecx)
-        // TODO edx
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(6, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(6, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(6, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(6, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf7Subleaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.max_input_value_subleaf >= other.eax.max_input_value_subleaf
-            && self.ebx.superset(&self.ebx)
-            && self.ecx.superset(&self.ecx)
-            && self.edx.superset(&self.edx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(7, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(7, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(7, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(7, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf7Subleaf1 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.superset(&self.eax) && self.ebx.superset(&self.ebx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(7, 1, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(7, 1, Register::Ebx, self.ebx.supports(&other.ebx)),
+        ])
     }
 }
 impl Leaf9 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        // TODO Can we use `>=` here instead?
-        // TODO ia32_platform_dca_c0BHap_msr
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::mismatches(9, 0, Register::Eax, self.eax.supports(&other.eax))
     }
 }
 impl LeafA {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
         // Do any of these feature affect program functionality or security?
-        self.ebx.superset(&other.ebx)
+        Incompatibilities::mismatches(0xA, 0, Register::Ebx, self.ebx.supports(&other.ebx))
     }
 }
 impl LeafB {
+    /// Checks `self` supports `other`, both being sub-leaf `sub_leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, sub_leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0xB,
+                sub_leaf,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                0xB,
+                sub_leaf,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                0xB,
+                sub_leaf,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                0xB,
+                sub_leaf,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl LeafDSubleaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.ebx.maximum_size >= other.ebx.maximum_size
-            && self.ecx.maximum_size >= other.ecx.maximum_size
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0xD, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0xD, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+        ])
     }
 }
 impl LeafDSubleaf1 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0xD, 1, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0xD, 1, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0xD, 1, Register::Ecx, self.ecx.supports(&other.ecx)),
+        ])
     }
 }
 impl LeafDSubleafGt1 {
+    /// Checks `self` supports `other`, both being sub-leaf `sub_leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, sub_leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0xD,
+                sub_leaf,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                0xD,
+                sub_leaf,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                0xD,
+                sub_leaf,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+        ])
     }
 }
 impl LeafFSubleaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.ebx.max_rmid_range >= other.ebx.max_rmid_range && self.edx.superset(&other.edx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0xF, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0xF, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl LeafFSubleaf1 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        // TODO EBX
-        todo!() && self.ecx.rmid_max >= other.ecx.rmid_max && self.edx.superset(&other.edx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0xF, 1, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0xF, 1, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(0xF, 1, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf10Subleaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.ebx.superset(&other.ebx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::mismatches(0x10, 0, Register::Ebx, self.ebx.supports(&other.ebx))
     }
 }
 impl Leaf10Subleaf1 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!() && self.ecx.superset(&other.ecx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x10, 1, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x10, 1, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x10, 1, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(0x10, 1, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf10Subleaf2 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x10, 2, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x10, 2, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x10, 2, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf10Subleaf3 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!() && self.ecx.superset(&other.ecx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x10, 3, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x10, 3, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(0x10, 3, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf12Subleaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.superset(&other.eax)
-            && todo!()
-            && self.edx.max_enclave_size_not_64 >= other.edx.max_enclave_size_not_64
-            && self.edx.max_enclave_size_64 >= other.edx.max_enclave_size_64
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x12, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x12, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x12, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf12Subleaf1 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        superset_u32(&self.eax.ecreate_attrs_0_31, &other.eax.ecreate_attrs_0_31)
-            && superset_u32(
-                &self.ebx.ecreate_attrs_32_63,
-                &other.ebx.ecreate_attrs_32_63,
-            )
-            && superset_u32(
-                &self.ecx.ecreate_attrs_64_95,
-                &other.ecx.ecreate_attrs_64_95,
-            )
-            && superset_u32(
-                &self.edx.ecreate_attrs_96_127,
-                &other.edx.ecreate_attrs_96_127,
-            )
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x12, 1, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x12, 1, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x12, 1, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(0x12, 1, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf12SubleafGt1 {
+    /// Checks `self` supports `other`, both being sub-leaf `sub_leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, sub_leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0x12,
+                sub_leaf,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                0x12,
+                sub_leaf,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                0x12,
+                sub_leaf,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                0x12,
+                sub_leaf,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl Leaf14Subleaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.max_subleaf >= other.eax.max_subleaf
-            && self.ebx.superset(&other.ebx)
-            && self.ecx.superset(&other.ecx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x14, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x14, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x14, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+        ])
     }
 }
 impl Leaf14Subleaf1 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.configurable_filterig_addr_ranges >= other.eax.configurable_filterig_addr_ranges
-            && todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x14, 1, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x14, 1, Register::Ebx, self.ebx.supports(&other.ebx)),
+        ])
     }
 }
 impl Leaf15 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x15, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x15, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x15, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+        ])
     }
 }
 impl Leaf16 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x16, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x16, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x16, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+        ])
     }
 }
 impl Leaf17Subleaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x17, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x17, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x17, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(0x17, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf17Subleaf1 {
+    /// Checks `self` supports `other`, both being sub-leaf `sub_leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, sub_leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0x17,
+                sub_leaf,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                0x17,
+                sub_leaf,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                0x17,
+                sub_leaf,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                0x17,
+                sub_leaf,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl Leaf17SubleafGt3 {
+    /// Checks `self` supports `other`, both being sub-leaf `sub_leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, sub_leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0x17,
+                sub_leaf,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                0x17,
+                sub_leaf,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                0x17,
+                sub_leaf,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                0x17,
+                sub_leaf,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl Leaf18Subleaf0 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.max_subleaf >= other.eax.max_subleaf
-            && self.ebx.superset(&other.ebx)
-            && self.ebx.ways_of_associativity >= other.ebx.ways_of_associativity
-            && todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x18, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x18, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x18, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(0x18, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf18SubleafGt0 {
+    /// Checks `self` supports `other`, both being sub-leaf `sub_leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, sub_leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0x18,
+                sub_leaf,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                0x18,
+                sub_leaf,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                0x18,
+                sub_leaf,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl Leaf19 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.superset(&other.eax)
-            && self.ebx.superset(&other.ebx)
-            && self.ecx.superset(&other.ecx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x19, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x19, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x19, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+        ])
     }
 }
 impl Leaf1A {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::mismatches(0x1A, 0, Register::Eax, self.eax.supports(&other.eax))
     }
 }
 impl Leaf1B {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x1B, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x1B, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x1B, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(0x1B, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf1C {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        superset_u32(&self.eax.lbr_depth_values, &other.eax.lbr_depth_values)
-            && self.eax.superset(&other.eax)
-            && self.ebx.superset(&other.ebx)
-            && self.ecx.superset(&other.ecx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x1C, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x1C, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(0x1C, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+        ])
     }
 }
 impl Leaf1F {
+    /// Checks `self` supports `other`, both being sub-leaf `sub_leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, sub_leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0x1F,
+                sub_leaf,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                0x1F,
+                sub_leaf,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                0x1F,
+                sub_leaf,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                0x1F,
+                sub_leaf,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl Leaf20 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        debug_assert_eq!(self.eax.max_subleaves, 1);
-        debug_assert_eq!(other.eax.max_subleaves, 1);
-        self.eax.max_subleaves >= other.eax.max_subleaves && self.ebx.superset(&other.ebx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(0x20, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(0x20, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+        ])
     }
 }
 impl Leaf80000000 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.eax.max_extend_function_input >= other.eax.max_extend_function_input
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::mismatches(0x8000_0000, 0, Register::Eax, self.eax.supports(&other.eax))
     }
 }
 impl Leaf80000001 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!() && self.ecx.superset(&other.ecx) && self.edx.superset(&other.edx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0x8000_0001,
+                0,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                0x8000_0001,
+                0,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                0x8000_0001,
+                0,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl Leaf80000002 {
+    /// Checks `self` supports `other`, both being leaf `leaf`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self, leaf: u32) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(leaf, 0, Register::Eax, self.eax.supports(&other.eax)),
+            Incompatibilities::mismatches(leaf, 0, Register::Ebx, self.ebx.supports(&other.ebx)),
+            Incompatibilities::mismatches(leaf, 0, Register::Ecx, self.ecx.supports(&other.ecx)),
+            Incompatibilities::mismatches(leaf, 0, Register::Edx, self.edx.supports(&other.edx)),
+        ])
     }
 }
 impl Leaf80000005 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            Incompatibilities::mismatches(
+                0x8000_0005,
+                0,
+                Register::Eax,
+                self.eax.supports(&other.eax),
+            ),
+            Incompatibilities::mismatches(
+                0x8000_0005,
+                0,
+                Register::Ebx,
+                self.ebx.supports(&other.ebx),
+            ),
+            Incompatibilities::mismatches(
+                0x8000_0005,
+                0,
+                Register::Ecx,
+                self.ecx.supports(&other.ecx),
+            ),
+            Incompatibilities::mismatches(
+                0x8000_0005,
+                0,
+                Register::Edx,
+                self.edx.supports(&other.edx),
+            ),
+        ])
     }
 }
 impl Leaf80000006 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        todo!()
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::mismatches(0x8000_0006, 0, Register::Ecx, self.ecx.supports(&other.ecx))
     }
 }
 impl Leaf80000007 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.edx.superset(&other.edx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::mismatches(0x8000_0007, 0, Register::Edx, self.edx.supports(&other.edx))
     }
 }
 impl Leaf80000008 {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.edx.superset(&other.edx)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::mismatches(0x8000_0008, 0, Register::Edx, self.edx.supports(&other.edx))
     }
 }
 
@@ -2782,587 +3239,370 @@ pub struct IntelCpuid {
     pub leaf_80000008: Leaf80000008,
 }
 impl IntelCpuid {
+    /// If the feature set of `self` supports the feature set of `other`.
+    ///
+    /// # Errors
+    ///
+    /// When it does not, returning every leaf, register or field which is not supported.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn supports(&self, other: &Self) -> bool {
-        self.leaf_0.supports(&other.leaf_0) &&
-        self.leaf_1.supports(&other.leaf_1) &&
-        self.leaf_2.supports(&other.leaf_2) &&
-        self.leaf_3.supports(&other.leaf_3) &&
-        // TODO self.leaf_4 supports
-        todo!() &&
-        self.leaf_5.supports(&other.leaf_5) &&
-        self.leaf_6.supports(&other.leaf_6) &&
-        self.leaf_7.0.supports(&other.leaf_7.0) &&
-        match (&self.leaf_7.1,&other.leaf_7.1) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        self.leaf_9.supports(&other.leaf_9) &&
-        self.leaf_a.supports(&other.leaf_a) &&
-        // TODO self.leaf_b supports
-        todo!() &&
-        self.leaf_d.0.supports(&other.leaf_d.0) &&
-        self.leaf_d.1.supports(&other.leaf_d.1) &&
-        // TODO self.leaf_d_2 supports
-        todo!() &&
-        self.leaf_f.0.supports(&other.leaf_f.0) &&
-        match (&self.leaf_f.1,&other.leaf_f.1) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        self.leaf_10.0.supports(&other.leaf_10.0) &&
-        match (&self.leaf_10.1,&other.leaf_10.1) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        match (&self.leaf_10.2,&other.leaf_10.2) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        self.leaf_12.0.supports(&other.leaf_12.0) &&
-        match (&self.leaf_12.1,&other.leaf_12.1) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        // TODO self.leaf_12.2 supports
-        todo!() &&
-        self.leaf_14.0.supports(&other.leaf_14.0) &&
-        match (&self.leaf_14.1,&other.leaf_14.1) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        self.leaf_15.supports(&other.leaf_15) &&
-        self.leaf_16.supports(&other.leaf_16) &&
-        match (&self.leaf_17,&other.leaf_17) {
-            // TODO self.leaf_17.4 supports
-            (Some(a), Some(b)) => a.0.supports(&b.0) &&
-                a.1.supports(&b.1) &&
-                a.2.supports(&b.2) &&
-                a.3.supports(&b.3) &&
-                todo!(),
-            (_, None) => true,
-            (_, Some(_)) => false,
-        } &&
-        match (&self.leaf_18,&other.leaf_18) {
-            // TODO self.leaf_18.1 supports
-            (Some(a), Some(b)) => a.0.supports(&b.0) && todo!(),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        match (&self.leaf_19,&other.leaf_19) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        match (&self.leaf_1a,&other.leaf_1a) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        match (&self.leaf_1b,&other.leaf_1b) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        match (&self.leaf_1c,&other.leaf_1c) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        // TODO self.leaf_1F supports
-        todo!() &&
-        match (&self.leaf_20,&other.leaf_20) {
-            (Some(a), Some(b)) => a.supports(b),
-            (_, None) => true,
-            (None, Some(_)) => false
-        } &&
-        self.leaf_80000000.supports(&other.leaf_80000000) &&
-        self.leaf_80000001.supports(&other.leaf_80000001) &&
-        self.leaf_80000002.supports(&other.leaf_80000002) &&
-        self.leaf_80000003.supports(&other.leaf_80000003) &&
-        self.leaf_80000004.supports(&other.leaf_80000004) &&
-        self.leaf_80000005.supports(&other.leaf_80000005) &&
-        self.leaf_80000006.supports(&other.leaf_80000006) &&
-        self.leaf_80000007.supports(&other.leaf_80000007) &&
-        self.leaf_80000008.supports(&other.leaf_80000008)
+    pub fn supports(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            self.leaf_0.supports(&other.leaf_0),
+            self.leaf_1.supports(&other.leaf_1),
+            self.leaf_2.supports(&other.leaf_2),
+            self.leaf_3.supports(&other.leaf_3),
+            Incompatibilities::sub_leaves(4, 0, &self.leaf_4, &other.leaf_4, Leaf4::supports),
+            self.leaf_5.supports(&other.leaf_5),
+            self.leaf_6.supports(&other.leaf_6),
+            self.leaf_7.0.supports(&other.leaf_7.0),
+            Incompatibilities::optional(
+                7,
+                1,
+                self.leaf_7.1.as_ref(),
+                other.leaf_7.1.as_ref(),
+                Leaf7Subleaf1::supports,
+            ),
+            self.leaf_9.supports(&other.leaf_9),
+            self.leaf_a.supports(&other.leaf_a),
+            Incompatibilities::sub_leaves(0xB, 0, &self.leaf_b, &other.leaf_b, LeafB::supports),
+            self.leaf_d.0.supports(&other.leaf_d.0),
+            self.leaf_d.1.supports(&other.leaf_d.1),
+            Incompatibilities::sub_leaves(
+                0xD,
+                2,
+                &self.leaf_d.2,
+                &other.leaf_d.2,
+                LeafDSubleafGt1::supports,
+            ),
+            self.leaf_f.0.supports(&other.leaf_f.0),
+            Incompatibilities::optional(
+                0xF,
+                1,
+                self.leaf_f.1.as_ref(),
+                other.leaf_f.1.as_ref(),
+                LeafFSubleaf1::supports,
+            ),
+            self.leaf_10.0.supports(&other.leaf_10.0),
+            Incompatibilities::optional(
+                0x10,
+                1,
+                self.leaf_10.1.as_ref(),
+                other.leaf_10.1.as_ref(),
+                Leaf10Subleaf1::supports,
+            ),
+            Incompatibilities::optional(
+                0x10,
+                2,
+                self.leaf_10.2.as_ref(),
+                other.leaf_10.2.as_ref(),
+                Leaf10Subleaf2::supports,
+            ),
+            Incompatibilities::optional(
+                0x10,
+                3,
+                self.leaf_10.3.as_ref(),
+                other.leaf_10.3.as_ref(),
+                Leaf10Subleaf3::supports,
+            ),
+            self.leaf_12.0.supports(&other.leaf_12.0),
+            Incompatibilities::optional(
+                0x12,
+                1,
+                self.leaf_12.1.as_ref(),
+                other.leaf_12.1.as_ref(),
+                Leaf12Subleaf1::supports,
+            ),
+            Incompatibilities::sub_leaves(
+                0x12,
+                2,
+                &self.leaf_12.2,
+                &other.leaf_12.2,
+                Leaf12SubleafGt1::supports,
+            ),
+            self.leaf_14.0.supports(&other.leaf_14.0),
+            Incompatibilities::optional(
+                0x14,
+                1,
+                self.leaf_14.1.as_ref(),
+                other.leaf_14.1.as_ref(),
+                Leaf14Subleaf1::supports,
+            ),
+            self.leaf_15.supports(&other.leaf_15),
+            self.leaf_16.supports(&other.leaf_16),
+            Incompatibilities::optional(
+                0x17,
+                0,
+                self.leaf_17.as_ref(),
+                other.leaf_17.as_ref(),
+                |a, b| {
+                    Incompatibilities::combine([
+                        a.0.supports(&b.0),
+                        a.1.supports(&b.1, 1),
+                        a.2.supports(&b.2, 2),
+                        a.3.supports(&b.3, 3),
+                        Incompatibilities::sub_leaves(
+                            0x17,
+                            4,
+                            &a.4,
+                            &b.4,
+                            Leaf17SubleafGt3::supports,
+                        ),
+                    ])
+                },
+            ),
+            Incompatibilities::optional(
+                0x18,
+                0,
+                self.leaf_18.as_ref(),
+                other.leaf_18.as_ref(),
+                |a, b| {
+                    Incompatibilities::combine([
+                        a.0.supports(&b.0),
+                        Incompatibilities::sub_leaves(
+                            0x18,
+                            1,
+                            &a.1,
+                            &b.1,
+                            Leaf18SubleafGt0::supports,
+                        ),
+                    ])
+                },
+            ),
+            Incompatibilities::optional(
+                0x19,
+                0,
+                self.leaf_19.as_ref(),
+                other.leaf_19.as_ref(),
+                Leaf19::supports,
+            ),
+            Incompatibilities::optional(
+                0x1A,
+                0,
+                self.leaf_1a.as_ref(),
+                other.leaf_1a.as_ref(),
+                Leaf1A::supports,
+            ),
+            Incompatibilities::optional(
+                0x1B,
+                0,
+                self.leaf_1b.as_ref(),
+                other.leaf_1b.as_ref(),
+                Leaf1B::supports,
+            ),
+            Incompatibilities::optional(
+                0x1C,
+                0,
+                self.leaf_1c.as_ref(),
+                other.leaf_1c.as_ref(),
+                Leaf1C::supports,
+            ),
+            Incompatibilities::sub_leaves(0x1F, 0, &self.leaf_1f, &other.leaf_1f, Leaf1F::supports),
+            Incompatibilities::optional(
+                0x20,
+                0,
+                self.leaf_20.as_ref(),
+                other.leaf_20.as_ref(),
+                Leaf20::supports,
+            ),
+            self.leaf_80000000.supports(&other.leaf_80000000),
+            self.leaf_80000001.supports(&other.leaf_80000001),
+            self.leaf_80000002
+                .supports(&other.leaf_80000002, 0x8000_0002),
+            self.leaf_80000003
+                .supports(&other.leaf_80000003, 0x8000_0003),
+            self.leaf_80000004
+                .supports(&other.leaf_80000004, 0x8000_0004),
+            self.leaf_80000005.supports(&other.leaf_80000005),
+            self.leaf_80000006.supports(&other.leaf_80000006),
+            self.leaf_80000007.supports(&other.leaf_80000007),
+            self.leaf_80000008.supports(&other.leaf_80000008),
+        ])
     }
 }
+impl<A, B, C, D> From<&RawCpuidEntry> for Leaf<A, B, C, D>
+where
+    A: From<u32>,
+    B: From<u32>,
+    C: From<u32>,
+    D: From<u32>,
+{
+    fn from(entry: &RawCpuidEntry) -> Self {
+        Leaf {
+            eax: A::from(entry.eax),
+            ebx: B::from(entry.ebx),
+            ecx: C::from(entry.ecx),
+            edx: D::from(entry.edx),
+        }
+    }
+}
+
+/// Returns the contiguous sub-leaves of `leaf` starting at `first`, ending at the first sub-leaf
+/// which is not present.
+fn sub_leaves(raw_cpuid: &RawCpuid, leaf: u32, first: u32) -> impl Iterator<Item = &RawCpuidEntry> {
+    (first..).map_while(move |sub_leaf| {
+        raw_cpuid
+            .get(leaf, sub_leaf)
+            .filter(|entry| entry.index == sub_leaf)
+    })
+}
+
 impl From<RawCpuid> for IntelCpuid {
     #[allow(clippy::too_many_lines)]
     fn from(raw_cpuid: RawCpuid) -> Self {
-        let mut leaf_4_offset = 3;
-        let leaf_4 = {
-            let mut vec = Vec::new();
-            // Add subleaves until subleaf has 0 in eax register (include the subleaf with 0 in the
-            // eax register)
-            loop {
-                leaf_4_offset += 1;
-                if raw_cpuid[leaf_4_offset].eax == 0 {
-                    break;
-                }
-                vec.push(Leaf4::from((
-                    Leaf4Eax::from(raw_cpuid[leaf_4_offset].eax),
-                    Leaf4Ebx::from(raw_cpuid[leaf_4_offset].ebx),
-                    Leaf4Ecx::from(raw_cpuid[leaf_4_offset].ecx),
-                    Leaf4Edx::from(raw_cpuid[leaf_4_offset].edx),
-                )));
-            }
-            vec
-        };
-        let leaf_7_start = leaf_4_offset + 3;
-        // for i in 0..15 {
-        //     println!("raw_cpuid[{}].ebx: {}",i,raw_cpuid[i].ebx);
-        // }
-        debug_assert_eq!(Some(&raw_cpuid[leaf_7_start]), raw_cpuid.get(0x7, 0));
-        let leaf_7_len = raw_cpuid[leaf_7_start].eax as usize;
-        dbg!(leaf_7_len);
-        debug_assert!(leaf_7_len == 0 || leaf_7_len == 1);
-        let leaf7_subleaves = if leaf_7_len == 1 {
-            Some(Leaf7Subleaf1::from((
-                Leaf7Subleaf1Eax::from(raw_cpuid[leaf_7_start + 1].eax),
-                Leaf7Subleaf1Ebx::from(raw_cpuid[leaf_7_start + 1].ebx),
-                Leaf7Subleaf1Ecx::from(raw_cpuid[leaf_7_start + 1].ecx),
-                Leaf7Subleaf1Edx::from(raw_cpuid[leaf_7_start + 1].edx),
-            )))
-        } else {
-            None
-        };
-        let leaf_7 = (
-            Leaf7Subleaf0::from((
-                Leaf7Subleaf0Eax::from(raw_cpuid[leaf_7_start].eax),
-                Leaf7Subleaf0Ebx::from(raw_cpuid[leaf_7_start].ebx),
-                Leaf7Subleaf0Ecx::from(raw_cpuid[leaf_7_start].ecx),
-                Leaf7Subleaf0Edx::from(raw_cpuid[leaf_7_start].edx),
-            )),
-            leaf7_subleaves,
-        );
-        let leaf_7_offset = leaf_7_start + leaf_7_len;
+        // A leaf (sub-leaf) which is not present reads as zeroes, as the `CPUID` instruction returns
+        // for an invalid sub-leaf.
+        let zero = RawCpuidEntry::new(0, 0, KvmCpuidFlags::empty(), 0, 0, 0, 0);
+        let get = |leaf: u32, sub_leaf: u32| raw_cpuid.get(leaf, sub_leaf).unwrap_or(&zero);
 
-        let mut leaf_b_offset = leaf_7_offset + 4;
-        dbg!(leaf_b_offset);
-        let leaf_b = {
-            let mut vec = vec![LeafB::from((
-                LeafBEax::from(raw_cpuid[leaf_b_offset].eax),
-                LeafBEbx::from(raw_cpuid[leaf_b_offset].ebx),
-                LeafBEcx::from(raw_cpuid[leaf_b_offset].ecx),
-                LeafBEdx::from(raw_cpuid[leaf_b_offset].edx),
-            ))];
-            while vec[vec.len() - 1].ecx.level_type != 0u32 {
-                leaf_b_offset += 1;
-                debug_assert_eq!(raw_cpuid[leaf_b_offset].function, 0xB);
-                vec.push(LeafB::from((
-                    LeafBEax::from(raw_cpuid[leaf_b_offset].eax),
-                    LeafBEbx::from(raw_cpuid[leaf_b_offset].ebx),
-                    LeafBEcx::from(raw_cpuid[leaf_b_offset].ecx),
-                    LeafBEdx::from(raw_cpuid[leaf_b_offset].edx),
-                )));
-            }
-            vec
-        };
-        // dbg!(&raw_cpuid[leaf_b_offset+1]);
-        let leaf_d_start = leaf_b_offset + 2;
-        // dbg!(&raw_cpuid[leaf_d_start]);
-        let mut leaf_d_offset = leaf_d_start + 1;
-        // dbg!(&raw_cpuid[leaf_d_offset]);
+        // Sub-leaves are enumerated until the first with a null cache type.
+        let leaf_4 = sub_leaves(&raw_cpuid, 0x4, 0)
+            .map(Leaf4::from)
+            .take_while(|leaf| leaf.eax.cache_type_field != 0u32)
+            .collect();
 
-        // TODO Avoid using `function` to accmulate subleaf d values
-        let leaf_d_subleaves = {
-            let mut vec = Vec::new();
-            while raw_cpuid[leaf_d_offset + 1].function == 13 {
-                leaf_d_offset += 1;
-                vec.push(LeafDSubleafGt1::from((
-                    LeafDSubleafGt1Eax::from(raw_cpuid[leaf_d_offset].eax),
-                    LeafDSubleafGt1Ebx::from(raw_cpuid[leaf_d_offset].ebx),
-                    LeafDSubleafGt1Ecx::from(raw_cpuid[leaf_d_offset].ecx),
-                    LeafDSubleafGt1Edx::from(raw_cpuid[leaf_d_offset].edx),
-                )));
-            }
-            vec
-        };
+        let leaf_7_subleaf_0 = Leaf7Subleaf0::from(get(0x7, 0));
+        let leaf_7_subleaf_1 = (u32::from(&leaf_7_subleaf_0.eax.max_input_value_subleaf) >= 1)
+            .then(|| Leaf7Subleaf1::from(get(0x7, 1)));
+        let leaf_7 = (leaf_7_subleaf_0, leaf_7_subleaf_1);
 
-        let leaf_f_start = leaf_d_offset + 2;
-        debug_assert_eq!(Some(&raw_cpuid[leaf_f_start]), raw_cpuid.get(0xf, 0));
-        let mut leaf_f_offset = leaf_f_start;
-        let leaf_f = {
-            let subleaf0 = LeafFSubleaf0::from((
-                LeafFSubleaf0Eax::from(raw_cpuid[leaf_f_start].eax),
-                LeafFSubleaf0Ebx::from(raw_cpuid[leaf_f_start].ebx),
-                LeafFSubleaf0Ecx::from(raw_cpuid[leaf_f_start].ecx),
-                LeafFSubleaf0Edx::from(raw_cpuid[leaf_f_start].edx),
-            ));
-            let subleaf1 = (subleaf0.edx.l3_rdt_monitor == true).then(|| {
-                leaf_f_offset += 1;
-                LeafFSubleaf1::from((
-                    LeafFSubleaf1Eax::from(raw_cpuid[leaf_f_offset].eax),
-                    LeafFSubleaf1Ebx::from(raw_cpuid[leaf_f_offset].ebx),
-                    LeafFSubleaf1Ecx::from(raw_cpuid[leaf_f_offset].ecx),
-                    LeafFSubleaf1Edx::from(raw_cpuid[leaf_f_offset].edx),
-                ))
-            });
-            (subleaf0, subleaf1)
-        };
-        let leaf_10_start = leaf_f_offset + 1;
-        debug_assert_eq!(Some(&raw_cpuid[leaf_10_start]), raw_cpuid.get(0x10, 0));
-        let mut leaf10_offset = leaf_10_start;
-        let leaf_10 = {
-            let subleaf0 = Leaf10Subleaf0::from((
-                Leaf10Subleaf0Eax::from(raw_cpuid[leaf_10_start].eax),
-                Leaf10Subleaf0Ebx::from(raw_cpuid[leaf_10_start].ebx),
-                Leaf10Subleaf0Ecx::from(raw_cpuid[leaf_10_start].ecx),
-                Leaf10Subleaf0Edx::from(raw_cpuid[leaf_10_start].edx),
-            ));
-            // We use `bool::then` over `bool::then_some` as `bool::then_some` is eagerly
-            // evaluated.
-            let subleaf1 = (subleaf0.ebx.l3_alloc == true).then(|| {
-                leaf10_offset += 1;
-                Leaf10Subleaf1::from((
-                    Leaf10Subleaf1Eax::from(raw_cpuid[leaf10_offset].eax),
-                    Leaf10Subleaf1Ebx::from(raw_cpuid[leaf10_offset].ebx),
-                    Leaf10Subleaf1Ecx::from(raw_cpuid[leaf10_offset].ecx),
-                    Leaf10Subleaf1Edx::from(raw_cpuid[leaf10_offset].edx),
-                ))
-            });
-            let subleaf2 = (subleaf0.ebx.l2_alloc == true).then(|| {
-                leaf10_offset += 1;
-                Leaf10Subleaf2::from((
-                    Leaf10Subleaf2Eax::from(raw_cpuid[leaf10_offset].eax),
-                    Leaf10Subleaf2Ebx::from(raw_cpuid[leaf10_offset].ebx),
-                    Leaf10Subleaf2Ecx::from(raw_cpuid[leaf10_offset].ecx),
-                    Leaf10Subleaf2Edx::from(raw_cpuid[leaf10_offset].edx),
-                ))
-            });
-            let subleaf3 = (subleaf0.ebx.mem_band_alloc == true).then(|| {
-                leaf10_offset += 1;
-                Leaf10Subleaf3::from((
-                    Leaf10Subleaf3Eax::from(raw_cpuid[leaf10_offset].eax),
-                    Leaf10Subleaf3Ebx::from(raw_cpuid[leaf10_offset].ebx),
-                    Leaf10Subleaf3Ecx::from(raw_cpuid[leaf10_offset].ecx),
-                    Leaf10Subleaf3Edx::from(raw_cpuid[leaf10_offset].edx),
-                ))
-            });
-            (subleaf0, subleaf1, subleaf2, subleaf3)
-        };
+        // Sub-leaves are enumerated until the first with an invalid level type.
+        let leaf_b = sub_leaves(&raw_cpuid, 0xB, 0)
+            .map(LeafB::from)
+            .take_while(|leaf| leaf.ecx.level_type != 0u32)
+            .collect();
 
-        let leaf_12_start = leaf10_offset + 2;
-        let leaf_12_offset = leaf_12_start;
-        debug_assert_eq!(Some(&raw_cpuid[leaf_12_start]), raw_cpuid.get(0x12, 0));
-        let leaf_12 = {
-            let subleaf0 = Leaf12Subleaf0::from((
-                Leaf12Subleaf0Eax::from(raw_cpuid[leaf_12_start].eax),
-                Leaf12Subleaf0Ebx::from(raw_cpuid[leaf_12_start].ebx),
-                Leaf12Subleaf0Ecx::from(raw_cpuid[leaf_12_start].ecx),
-                Leaf12Subleaf0Edx::from(raw_cpuid[leaf_12_start].edx),
-            ));
-            // Leaf 12H sub-leaf 1 (ECX = 1) is supported if CPUID.(EAX=07H, ECX=0H):EBX[SGX] = 1.
-            dbg!(leaf_7.0.ebx.sgx == true);
-            let (subleaf1, subleaf2) = if leaf_7.0.ebx.sgx == true {
-                todo!("Due to vagueness surrounding the number of subleaves");
-                // leaf_12_offset += 1;
-                // (
-                //     Some(Leaf12Subleaf1::from((
-                //         Leaf12Subleaf1Eax::from(raw_cpuid[leaf_12_offset].eax),
-                //         Leaf12Subleaf1Ebx::from(raw_cpuid[leaf_12_offset].ebx),
-                //         Leaf12Subleaf1Ecx::from(raw_cpuid[leaf_12_offset].ecx),
-                //         Leaf12Subleaf1Edx::from(raw_cpuid[leaf_12_offset].edx),
-                //     ))),
-                //     Vec::new(),
-                // )
-            } else {
-                (None, Vec::new())
-            };
-            // Leaf 12H sub-leaf 2 or higher (ECX >= 2) is supported if
-            // CPUID.(EAX=07H, ECX=0H):EBX[SGX] = 1.
-            //
-            // For sub-leaves (ECX = 2 or higher), definition of EDX,ECX,EBX,EAX[31:4] depends on
-            // the sub-leaf type listed below.
-            (subleaf0, subleaf1, subleaf2)
-        };
+        // Sub-leaves of unsupported state components are not contiguous, those missing are zeroed
+        // such that `leaf_d.2[n]` is always sub-leaf `n + 2`.
+        let leaf_d_len = raw_cpuid
+            .iter()
+            .filter(|entry| entry.function == 0xD && entry.index > 1)
+            .map(|entry| entry.index - 1)
+            .max()
+            .unwrap_or(0);
+        let leaf_d = (
+            LeafDSubleaf0::from(get(0xD, 0)),
+            LeafDSubleaf1::from(get(0xD, 1)),
+            (2..leaf_d_len + 2)
+                .map(|sub_leaf| LeafDSubleafGt1::from(get(0xD, sub_leaf)))
+                .collect(),
+        );
 
-        let leaf_14_start = leaf_12_offset + 2;
-        let mut leaf_14_offset = leaf_14_start;
-        debug_assert_eq!(Some(&raw_cpuid[leaf_14_start]), raw_cpuid.get(0x14, 0));
-        let leaf_14 = {
-            let subleaf0 = Leaf14Subleaf0::from((
-                Leaf14Subleaf0Eax::from(raw_cpuid[leaf_14_start].eax),
-                Leaf14Subleaf0Ebx::from(raw_cpuid[leaf_14_start].ebx),
-                Leaf14Subleaf0Ecx::from(raw_cpuid[leaf_14_start].ecx),
-                Leaf14Subleaf0Edx::from(raw_cpuid[leaf_14_start].edx),
-            ));
-            let subleaf1 = match u32::from(&subleaf0.eax.max_subleaf) {
-                1 => {
-                    leaf_14_offset += 1;
-                    Some(Leaf14Subleaf1::from((
-                        Leaf14Subleaf1Eax::from(raw_cpuid[leaf_14_offset].eax),
-                        Leaf14Subleaf1Ebx::from(raw_cpuid[leaf_14_offset].ebx),
-                        Leaf14Subleaf1Ecx::from(raw_cpuid[leaf_14_offset].ecx),
-                        Leaf14Subleaf1Edx::from(raw_cpuid[leaf_14_offset].edx),
-                    )))
-                }
-                0 => None,
-                // TODO Add specific spec version
-                _ => unimplemented!(
-                    "The Intel specification does not describe subleaves of leaf 14h beyound 1."
-                ),
-            };
-            (subleaf0, subleaf1)
-        };
-        debug_assert_eq!(Some(&raw_cpuid[leaf_14_start + 1]), raw_cpuid.get(0x15, 0));
-        debug_assert_eq!(Some(&raw_cpuid[leaf_14_start + 2]), raw_cpuid.get(0x16, 0));
-        // All leaves we define below here are not properly defined
-        let leaf_17_start = leaf_14_start + 3;
-        let (leaf_17, leaf_17_offset) = if raw_cpuid[leaf_17_start].function == 0x17 {
-            todo!()
-        } else {
-            (None, leaf_17_start - 1)
-        };
-        let leaf_18_start = leaf_17_offset + 1;
-        let (leaf_18, leaf_18_offset) = if raw_cpuid[leaf_18_start].function == 0x18 {
-            todo!()
-        } else {
-            (None, leaf_18_start - 1)
-        };
-        let leaf_19_start = leaf_18_offset + 1;
-        let (leaf_19, leaf_19_offset) = if raw_cpuid[leaf_19_start].function == 0x19 {
-            todo!()
-        } else {
-            (None, leaf_19_start - 1)
-        };
-        let leaf_1a_start = leaf_19_offset + 1;
-        let (leaf_1a, leaf_1a_offset) = if raw_cpuid[leaf_1a_start].function == 0x1A {
-            todo!()
-        } else {
-            (None, leaf_1a_start - 1)
-        };
-        let leaf_1b_start = leaf_1a_offset + 1;
-        let (leaf_1b, leaf_1b_offset) = if raw_cpuid[leaf_1b_start].function == 0x1B {
-            todo!()
-        } else {
-            (None, leaf_1b_start - 1)
-        };
-        let leaf_1c_start = leaf_1b_offset + 1;
-        let (leaf_1c, leaf_1c_offset) = if raw_cpuid[leaf_1c_start].function == 0x1C {
-            todo!()
-        } else {
-            (None, leaf_1c_start - 1)
-        };
-        let leaf_1f_start = leaf_1c_offset + 1;
-        let (leaf_1f, leaf_1f_offset) = if raw_cpuid[leaf_1f_start].function == 0x1B {
-            let mut leaf_1f_offset = leaf_1f_start;
-            let mut vec = vec![Leaf1F::from((
-                Leaf1FEax::from(raw_cpuid[leaf_1f_offset].eax),
-                Leaf1FEbx::from(raw_cpuid[leaf_1f_offset].ebx),
-                Leaf1FEcx::from(raw_cpuid[leaf_1f_offset].ecx),
-                Leaf1FEdx::from(raw_cpuid[leaf_1f_offset].edx),
-            ))];
-            while vec[vec.len() - 1].ecx.level_type != 0u32 {
-                leaf_1f_offset += 1;
-                debug_assert_eq!(raw_cpuid[leaf_1f_offset].function, 0x1B);
-                vec.push(Leaf1F::from((
-                    Leaf1FEax::from(raw_cpuid[leaf_1f_offset].eax),
-                    Leaf1FEbx::from(raw_cpuid[leaf_1f_offset].ebx),
-                    Leaf1FEcx::from(raw_cpuid[leaf_1f_offset].ecx),
-                    Leaf1FEdx::from(raw_cpuid[leaf_1f_offset].edx),
-                )));
-            }
-            (vec, leaf_1f_offset)
-        } else {
-            (Vec::new(), leaf_1f_start - 1)
-        };
-        let leaf_20_start = leaf_1f_offset + 1;
-        let (leaf_20, leaf_20_offset) = if raw_cpuid[leaf_20_start].function == 0x20 {
-            todo!()
+        let leaf_f_subleaf_0 = LeafFSubleaf0::from(get(0xF, 0));
+        let leaf_f_subleaf_1 =
+            (leaf_f_subleaf_0.edx.l3_rdt_monitor == true).then(|| LeafFSubleaf1::from(get(0xF, 1)));
+        let leaf_f = (leaf_f_subleaf_0, leaf_f_subleaf_1);
+
+        let leaf_10_subleaf_0 = Leaf10Subleaf0::from(get(0x10, 0));
+        // We use `bool::then` over `bool::then_some` as `bool::then_some` is eagerly evaluated.
+        let leaf_10_subleaf_1 =
+            (leaf_10_subleaf_0.ebx.l3_alloc == true).then(|| Leaf10Subleaf1::from(get(0x10, 1)));
+        let leaf_10_subleaf_2 =
+            (leaf_10_subleaf_0.ebx.l2_alloc == true).then(|| Leaf10Subleaf2::from(get(0x10, 2)));
+        let leaf_10_subleaf_3 = (leaf_10_subleaf_0.ebx.mem_band_alloc == true)
+            .then(|| Leaf10Subleaf3::from(get(0x10, 3)));
+        let leaf_10 = (
+            leaf_10_subleaf_0,
+            leaf_10_subleaf_1,
+            leaf_10_subleaf_2,
+            leaf_10_subleaf_3,
+        );
+
+        // Leaf 12H sub-leaves 1 and above (ECX >= 1) are supported if
+        // CPUID.(EAX=07H, ECX=0H):EBX[SGX] = 1, sub-leaves 2 and above are enumerated until the
+        // first with an invalid sub-leaf type.
+        let leaf_12 = if leaf_7.0.ebx.sgx == true {
+            (
+                Leaf12Subleaf0::from(get(0x12, 0)),
+                Some(Leaf12Subleaf1::from(get(0x12, 1))),
+                sub_leaves(&raw_cpuid, 0x12, 2)
+                    .map(Leaf12SubleafGt1::from)
+                    .take_while(|leaf| leaf.eax.subleaf_type != 0u32)
+                    .collect(),
+            )
         } else {
-            (None, leaf_20_start - 1)
+            (Leaf12Subleaf0::from(get(0x12, 0)), None, Vec::new())
         };
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 1]),
-            raw_cpuid.get(0x8000_0000, 0)
-        );
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 2]),
-            raw_cpuid.get(0x8000_0001, 0)
-        );
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 3]),
-            raw_cpuid.get(0x8000_0002, 0)
-        );
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 4]),
-            raw_cpuid.get(0x8000_0003, 0)
-        );
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 5]),
-            raw_cpuid.get(0x8000_0004, 0)
-        );
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 6]),
-            raw_cpuid.get(0x8000_0005, 0)
-        );
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 7]),
-            raw_cpuid.get(0x8000_0006, 0)
-        );
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 8]),
-            raw_cpuid.get(0x8000_0007, 0)
-        );
-        debug_assert_eq!(
-            Some(&raw_cpuid[leaf_20_offset + 9]),
-            raw_cpuid.get(0x8000_0008, 0)
-        );
+
+        let leaf_14_subleaf_0 = Leaf14Subleaf0::from(get(0x14, 0));
+        let leaf_14_subleaf_1 = (u32::from(&leaf_14_subleaf_0.eax.max_subleaf) >= 1)
+            .then(|| Leaf14Subleaf1::from(get(0x14, 1)));
+        let leaf_14 = (leaf_14_subleaf_0, leaf_14_subleaf_1);
+
+        // Sub-leaves 1 to 3 hold the vendor brand string, sub-leaves above 3 are enumerated up to
+        // the maximum SOCID index.
+        let leaf_17 = raw_cpuid.get(0x17, 0).map(|entry| {
+            let subleaf0 = Leaf17Subleaf0::from(entry);
+            let max = u32::from(&subleaf0.eax.max_socid_index);
+            (
+                subleaf0,
+                Leaf17Subleaf1::from(get(0x17, 1)),
+                Leaf17Subleaf2::from(get(0x17, 2)),
+                Leaf17Subleaf3::from(get(0x17, 3)),
+                (4..=max)
+                    .map(|sub_leaf| Leaf17SubleafGt3::from(get(0x17, sub_leaf)))
+                    .collect(),
+            )
+        });
+
+        // Invalid sub-leaves up to the maximum sub-leaf read as zeroes, such that `leaf_18.1[n]` is
+        // always sub-leaf `n + 1`.
+        let leaf_18 = raw_cpuid.get(0x18, 0).map(|entry| {
+            let subleaf0 = Leaf18Subleaf0::from(entry);
+            let max = u32::from(&subleaf0.eax.max_subleaf);
+            let subleaves = (1..=max)
+                .map(|sub_leaf| Leaf18SubleafGt0::from(get(0x18, sub_leaf)))
+                .collect();
+            (subleaf0, subleaves)
+        });
+
+        // Sub-leaves are enumerated until the first with an invalid level type.
+        let leaf_1f = sub_leaves(&raw_cpuid, 0x1F, 0)
+            .map(Leaf1F::from)
+            .take_while(|leaf| leaf.ecx.level_type != 0u32)
+            .collect();
 
         Self {
             leaf_0: Leaf0::from((
-                raw_cpuid[0].eax,
-                FixedString(raw_cpuid[0].ebx.to_ne_bytes()),
-                FixedString(raw_cpuid[0].ecx.to_ne_bytes()),
-                FixedString(raw_cpuid[0].edx.to_ne_bytes()),
-            )),
-            leaf_1: Leaf1::from((
-                Leaf1Eax::from(raw_cpuid[1].eax),
-                Leaf1Ebx::from(raw_cpuid[1].ebx),
-                Leaf1Ecx::from(raw_cpuid[1].ecx),
-                Leaf1Edx::from(raw_cpuid[1].edx),
-            )),
-            leaf_2: Leaf2::from((
-                raw_cpuid[2].eax,
-                raw_cpuid[2].ebx,
-                raw_cpuid[2].ecx,
-                raw_cpuid[2].edx,
-            )),
-            leaf_3: Leaf3::from((
-                Leaf3Eax::from(raw_cpuid[3].eax),
-                Leaf3Ebx::from(raw_cpuid[3].ebx),
-                Leaf3Ecx::from(raw_cpuid[3].ecx),
-                Leaf3Edx::from(raw_cpuid[3].edx),
+                get(0x0, 0).eax,
+                FixedString(get(0x0, 0).ebx.to_ne_bytes()),
+                FixedString(get(0x0, 0).ecx.to_ne_bytes()),
+                FixedString(get(0x0, 0).edx.to_ne_bytes()),
             )),
+            leaf_1: Leaf1::from(get(0x1, 0)),
+            leaf_2: Leaf2::from(get(0x2, 0)),
+            leaf_3: Leaf3::from(get(0x3, 0)),
             leaf_4,
-            leaf_5: Leaf5::from((
-                Leaf5Eax::from(raw_cpuid[leaf_4_offset + 1].eax),
-                Leaf5Ebx::from(raw_cpuid[leaf_4_offset + 1].ebx),
-                Leaf5Ecx::from(raw_cpuid[leaf_4_offset + 1].ecx),
-                Leaf5Edx::from(raw_cpuid[leaf_4_offset + 1].edx),
-            )),
-            leaf_6: Leaf6::from((
-                Leaf6Eax::from(raw_cpuid[leaf_4_offset + 2].eax),
-                Leaf6Ebx::from(raw_cpuid[leaf_4_offset + 2].ebx),
-                Leaf6Ecx::from(raw_cpuid[leaf_4_offset + 2].ecx),
-                Leaf6Edx::from(raw_cpuid[leaf_4_offset + 2].edx),
-            )),
+            leaf_5: Leaf5::from(get(0x5, 0)),
+            leaf_6: Leaf6::from(get(0x6, 0)),
             leaf_7,
-            leaf_9: Leaf9::from((
-                Leaf9Eax::from(raw_cpuid[leaf_7_offset + 2].eax),
-                Leaf9Ebx::from(raw_cpuid[leaf_7_offset + 2].ebx),
-                Leaf9Ecx::from(raw_cpuid[leaf_7_offset + 2].ecx),
-                Leaf9Edx::from(raw_cpuid[leaf_7_offset + 2].edx),
-            )),
-            leaf_a: LeafA::from((
-                LeafAEax::from(raw_cpuid[leaf_7_offset + 3].eax),
-                LeafAEbx::from(raw_cpuid[leaf_7_offset + 3].ebx),
-                LeafAEcx::from(raw_cpuid[leaf_7_offset + 3].ecx),
-                LeafAEdx::from(raw_cpuid[leaf_7_offset + 3].edx),
-            )),
+            leaf_9: Leaf9::from(get(0x9, 0)),
+            leaf_a: LeafA::from(get(0xA, 0)),
             leaf_b,
-            leaf_d: (
-                LeafDSubleaf0::from((
-                    LeafDSubleaf0Eax::from(raw_cpuid[leaf_d_start].eax),
-                    LeafDSubleaf0Ebx::from(raw_cpuid[leaf_d_start].ebx),
-                    LeafDSubleaf0Ecx::from(raw_cpuid[leaf_d_start].ecx),
-                    LeafDSubleaf0Edx::from(raw_cpuid[leaf_d_start].edx),
-                )),
-                LeafDSubleaf1::from((
-                    LeafDSubleaf1Eax::from(raw_cpuid[leaf_d_start + 1].eax),
-                    LeafDSubleaf1Ebx::from(raw_cpuid[leaf_d_start + 1].ebx),
-                    LeafDSubleaf1Ecx::from(raw_cpuid[leaf_d_start + 1].ecx),
-                    LeafDSubleaf1Edx::from(raw_cpuid[leaf_d_start + 1].edx),
-                )),
-                leaf_d_subleaves,
-            ),
+            leaf_d,
             leaf_f,
             leaf_10,
             leaf_12,
             leaf_14,
-            leaf_15: Leaf15::from((
-                Leaf15Eax::from(raw_cpuid[leaf_14_offset + 1].eax),
-                Leaf15Ebx::from(raw_cpuid[leaf_14_offset + 1].ebx),
-                Leaf15Ecx::from(raw_cpuid[leaf_14_offset + 1].ecx),
-                Leaf15Edx::from(raw_cpuid[leaf_14_offset + 1].edx),
-            )),
-            leaf_16: Leaf16::from((
-                Leaf16Eax::from(raw_cpuid[leaf_14_offset + 2].eax),
-                Leaf16Ebx::from(raw_cpuid[leaf_14_offset + 2].ebx),
-                Leaf16Ecx::from(raw_cpuid[leaf_14_offset + 2].ecx),
-                Leaf16Edx::from(raw_cpuid[leaf_14_offset + 2].edx),
-            )),
+            leaf_15: Leaf15::from(get(0x15, 0)),
+            leaf_16: Leaf16::from(get(0x16, 0)),
             leaf_17,
             leaf_18,
-            leaf_19,
-            leaf_1a,
-            leaf_1b,
-            leaf_1c,
+            leaf_19: raw_cpuid.get(0x19, 0).map(Leaf19::from),
+            leaf_1a: raw_cpuid.get(0x1A, 0).map(Leaf1A::from),
+            leaf_1b: raw_cpuid.get(0x1B, 0).map(Leaf1B::from),
+            leaf_1c: raw_cpuid.get(0x1C, 0).map(Leaf1C::from),
             leaf_1f,
-            leaf_20,
-            leaf_80000000: Leaf80000000::from((
-                Leaf80000000Eax::from(raw_cpuid[leaf_20_offset + 1].eax),
-                Leaf80000000Ebx::from(raw_cpuid[leaf_20_offset + 1].ebx),
-                Leaf80000000Ecx::from(raw_cpuid[leaf_20_offset + 1].ecx),
-                Leaf80000000Edx::from(raw_cpuid[leaf_20_offset + 1].edx),
-            )),
-            leaf_80000001: Leaf80000001::from((
-                Leaf80000001Eax::from(raw_cpuid[leaf_20_offset + 2].eax),
-                Leaf80000001Ebx::from(raw_cpuid[leaf_20_offset + 2].ebx),
-                Leaf80000001Ecx::from(raw_cpuid[leaf_20_offset + 2].ecx),
-                Leaf80000001Edx::from(raw_cpuid[leaf_20_offset + 2].edx),
-            )),
-            leaf_80000002: Leaf80000002::from((
-                Leaf80000002Eax::from(raw_cpuid[leaf_20_offset + 3].eax),
-                Leaf80000002Ebx::from(raw_cpuid[leaf_20_offset + 3].ebx),
-                Leaf80000002Ecx::from(raw_cpuid[leaf_20_offset + 3].ecx),
-                Leaf80000002Edx::from(raw_cpuid[leaf_20_offset + 3].edx),
-            )),
-            leaf_80000003: Leaf80000003::from((
-                Leaf80000002Eax::from(raw_cpuid[leaf_20_offset + 4].eax),
-                Leaf80000002Ebx::from(raw_cpuid[leaf_20_offset + 4].ebx),
-                Leaf80000002Ecx::from(raw_cpuid[leaf_20_offset + 4].ecx),
-                Leaf80000002Edx::from(raw_cpuid[leaf_20_offset + 4].edx),
-            )),
-            leaf_80000004: Leaf80000004::from((
-                Leaf80000002Eax::from(raw_cpuid[leaf_20_offset + 5].eax),
-                Leaf80000002Ebx::from(raw_cpuid[leaf_20_offset + 5].ebx),
-                Leaf80000002Ecx::from(raw_cpuid[leaf_20_offset + 5].ecx),
-                Leaf80000002Edx::from(raw_cpuid[leaf_20_offset + 5].edx),
-            )),
-            leaf_80000005: Leaf80000005::from((
-                Leaf80000005Eax::from(raw_cpuid[leaf_20_offset + 6].eax),
-                Leaf80000005Ebx::from(raw_cpuid[leaf_20_offset + 6].ebx),
-                Leaf80000005Ecx::from(raw_cpuid[leaf_20_offset + 6].ecx),
-                Leaf80000005Edx::from(raw_cpuid[leaf_20_offset + 6].edx),
-            )),
-            leaf_80000006: Leaf80000006::from((
-                Leaf80000006Eax::from(raw_cpuid[leaf_20_offset + 7].eax),
-                Leaf80000006Ebx::from(raw_cpuid[leaf_20_offset + 7].ebx),
-                Leaf80000006Ecx::from(raw_cpuid[leaf_20_offset + 7].ecx),
-                Leaf80000006Edx::from(raw_cpuid[leaf_20_offset + 7].edx),
-            )),
-            leaf_80000007: Leaf80000007::from((
-                Leaf80000007Eax::from(raw_cpuid[leaf_20_offset + 8].eax),
-                Leaf80000007Ebx::from(raw_cpuid[leaf_20_offset + 8].ebx),
-                Leaf80000007Ecx::from(raw_cpuid[leaf_20_offset + 8].ecx),
-                Leaf80000007Edx::from(raw_cpuid[leaf_20_offset + 8].edx),
-            )),
-            leaf_80000008: Leaf80000008::from((
-                Leaf80000008Eax::from(raw_cpuid[leaf_20_offset + 9].eax),
-                Leaf80000008Ebx::from(raw_cpuid[leaf_20_offset + 9].ebx),
-                Leaf80000008Ecx::from(raw_cpuid[leaf_20_offset + 9].ecx),
-                Leaf80000008Edx::from(raw_cpuid[leaf_20_offset + 9].edx),
-            )),
+            leaf_20: raw_cpuid.get(0x20, 0).map(Leaf20::from),
+            leaf_80000000: Leaf80000000::from(get(0x8000_0000, 0)),
+            leaf_80000001: Leaf80000001::from(get(0x8000_0001, 0)),
+            leaf_80000002: Leaf80000002::from(get(0x8000_0002, 0)),
+            leaf_80000003: Leaf80000003::from(get(0x8000_0003, 0)),
+            leaf_80000004: Leaf80000004::from(get(0x8000_0004, 0)),
+            leaf_80000005: Leaf80000005::from(get(0x8000_0005, 0)),
+            leaf_80000006: Leaf80000006::from(get(0x8000_0006, 0)),
+            leaf_80000007: Leaf80000007::from(get(0x8000_0007, 0)),
+            leaf_80000008: Leaf80000008::from(get(0x8000_0008, 0)),
         }
     }
 }
@@ -3371,25 +3611,507 @@ impl From<RawCpuid> for IntelCpuid {
 // Util & Tests
 // -------------------------------------------------------------------------------------------------
 
+/// The fields of each of `eax`, `ebx`, `ecx` and `edx` of a leaf.
+macro_rules! registers {
+    ($eax:ident, $ebx:ident, $ecx:ident, $edx:ident) => {
+        [$eax::FIELDS, $ebx::FIELDS, $ecx::FIELDS, $edx::FIELDS]
+    };
+}
+
+/// The vendor ID (`ebx`, `edx` and `ecx` of leaf 0) `GenuineIntel`, whose leaves
+/// [`register_fields`] describes.
+pub const GENUINE_INTEL: [u32; 3] = [0x756e_6547, 0x4965_6e69, 0x6c65_746e];
+
+/// Returns the fields of each of `eax`, `ebx`, `ecx` and `edx` for a given leaf and sub-leaf, or
+/// `None` when there is no typed definition of the leaf.
+#[allow(clippy::too_many_lines)]
+#[must_use]
+pub fn register_fields(leaf: u32, sub_leaf: u32) -> Option<[&'static [bit_fields::Field]; 4]> {
+    Some(match (leaf, sub_leaf) {
+        (0x1, _) => registers!(Leaf1Eax, Leaf1Ebx, Leaf1Ecx, Leaf1Edx),
+        (0x2, _) => registers!(Leaf2Eax, Leaf2Ebx, Leaf2Ecx, Leaf2Edx),
+        (0x3, _) => registers!(Leaf3Eax, Leaf3Ebx, Leaf3Ecx, Leaf3Edx),
+        (0x4, _) => registers!(Leaf4Eax, Leaf4Ebx, Leaf4Ecx, Leaf4Edx),
+        (0x5, _) => registers!(Leaf5Eax, Leaf5Ebx, Leaf5Ecx, Leaf5Edx),
+        (0x6, _) => registers!(Leaf6Eax, Leaf6Ebx, Leaf6Ecx, Leaf6Edx),
+        (0x7, 0) => registers!(
+            Leaf7Subleaf0Eax,
+            Leaf7Subleaf0Ebx,
+            Leaf7Subleaf0Ecx,
+            Leaf7Subleaf0Edx
+        ),
+        (0x7, 1) => registers!(
+            Leaf7Subleaf1Eax,
+            Leaf7Subleaf1Ebx,
+            Leaf7Subleaf1Ecx,
+            Leaf7Subleaf1Edx
+        ),
+        (0x9, _) => registers!(Leaf9Eax, Leaf9Ebx, Leaf9Ecx, Leaf9Edx),
+        (0xA, _) => registers!(LeafAEax, LeafAEbx, LeafAEcx, LeafAEdx),
+        (0xB, _) => registers!(LeafBEax, LeafBEbx, LeafBEcx, LeafBEdx),
+        (0xD, 0) => registers!(
+            LeafDSubleaf0Eax,
+            LeafDSubleaf0Ebx,
+            LeafDSubleaf0Ecx,
+            LeafDSubleaf0Edx
+        ),
+        (0xD, 1) => registers!(
+            LeafDSubleaf1Eax,
+            LeafDSubleaf1Ebx,
+            LeafDSubleaf1Ecx,
+            LeafDSubleaf1Edx
+        ),
+        (0xD, _) => registers!(
+            LeafDSubleafGt1Eax,
+            LeafDSubleafGt1Ebx,
+            LeafDSubleafGt1Ecx,
+            LeafDSubleafGt1Edx
+        ),
+        (0xF, 0) => registers!(
+            LeafFSubleaf0Eax,
+            LeafFSubleaf0Ebx,
+            LeafFSubleaf0Ecx,
+            LeafFSubleaf0Edx
+        ),
+        (0xF, 1) => registers!(
+            LeafFSubleaf1Eax,
+            LeafFSubleaf1Ebx,
+            LeafFSubleaf1Ecx,
+            LeafFSubleaf1Edx
+        ),
+        (0x10, 0) => registers!(
+            Leaf10Subleaf0Eax,
+            Leaf10Subleaf0Ebx,
+            Leaf10Subleaf0Ecx,
+            Leaf10Subleaf0Edx
+        ),
+        (0x10, 1) => registers!(
+            Leaf10Subleaf1Eax,
+            Leaf10Subleaf1Ebx,
+            Leaf10Subleaf1Ecx,
+            Leaf10Subleaf1Edx
+        ),
+        (0x10, 2) => registers!(
+            Leaf10Subleaf2Eax,
+            Leaf10Subleaf2Ebx,
+            Leaf10Subleaf2Ecx,
+            Leaf10Subleaf2Edx
+        ),
+        (0x10, 3) => registers!(
+            Leaf10Subleaf3Eax,
+            Leaf10Subleaf3Ebx,
+            Leaf10Subleaf3Ecx,
+            Leaf10Subleaf3Edx
+        ),
+        (0x12, 0) => registers!(
+            Leaf12Subleaf0Eax,
+            Leaf12Subleaf0Ebx,
+            Leaf12Subleaf0Ecx,
+            Leaf12Subleaf0Edx
+        ),
+        (0x12, 1) => registers!(
+            Leaf12Subleaf1Eax,
+            Leaf12Subleaf1Ebx,
+            Leaf12Subleaf1Ecx,
+            Leaf12Subleaf1Edx
+        ),
+        (0x12, _) => registers!(
+            Leaf12SubleafGt1Eax,
+            Leaf12SubleafGt1Ebx,
+            Leaf12SubleafGt1Ecx,
+            Leaf12SubleafGt1Edx
+        ),
+        (0x14, 0) => registers!(
+            Leaf14Subleaf0Eax,
+            Leaf14Subleaf0Ebx,
+            Leaf14Subleaf0Ecx,
+            Leaf14Subleaf0Edx
+        ),
+        (0x14, 1) => registers!(
+            Leaf14Subleaf1Eax,
+            Leaf14Subleaf1Ebx,
+            Leaf14Subleaf1Ecx,
+            Leaf14Subleaf1Edx
+        ),
+        (0x15, _) => registers!(Leaf15Eax, Leaf15Ebx, Leaf15Ecx, Leaf15Edx),
+        (0x16, _) => registers!(Leaf16Eax, Leaf16Ebx, Leaf16Ecx, Leaf16Edx),
+        (0x17, 0) => registers!(
+            Leaf17Subleaf0Eax,
+            Leaf17Subleaf0Ebx,
+            Leaf17Subleaf0Ecx,
+            Leaf17Subleaf0Edx
+        ),
+        (0x17, 1..=3) => registers!(
+            Leaf17Subleaf1Eax,
+            Leaf17Subleaf1Ebx,
+            Leaf17Subleaf1Ecx,
+            Leaf17Subleaf1Edx
+        ),
+        (0x17, _) => registers!(
+            Leaf17SubleafGt3Eax,
+            Leaf17SubleafGt3Ebx,
+            Leaf17SubleafGt3Ecx,
+            Leaf17SubleafGt3Edx
+        ),
+        (0x18, 0) => registers!(
+            Leaf18Subleaf0Eax,
+            Leaf18Subleaf0Ebx,
+            Leaf18Subleaf0Ecx,
+            Leaf18Subleaf0Edx
+        ),
+        (0x18, _) => registers!(
+            Leaf18SubleafGt0Eax,
+            Leaf18SubleafGt0Ebx,
+            Leaf18SubleafGt0Ecx,
+            Leaf18SubleafGt0Edx
+        ),
+        (0x19, _) => registers!(Leaf19Eax, Leaf19Ebx, Leaf19Ecx, Leaf19Edx),
+        (0x1A, _) => registers!(Leaf1AEax, Leaf1AEbx, Leaf1AEcx, Leaf1AEdx),
+        (0x1B, _) => registers!(Leaf1BEax, Leaf1BEbx, Leaf1BEcx, Leaf1BEdx),
+        (0x1C, _) => registers!(Leaf1CEax, Leaf1CEbx, Leaf1CEcx, Leaf1CEdx),
+        (0x1F, _) => registers!(Leaf1FEax, Leaf1FEbx, Leaf1FEcx, Leaf1FEdx),
+        (0x20, _) => registers!(Leaf20Eax, Leaf20Ebx, Leaf20Ecx, Leaf20Edx),
+        (0x8000_0000, _) => registers!(
+            Leaf80000000Eax,
+            Leaf80000000Ebx,
+            Leaf80000000Ecx,
+            Leaf80000000Edx
+        ),
+        (0x8000_0001, _) => registers!(
+            Leaf80000001Eax,
+            Leaf80000001Ebx,
+            Leaf80000001Ecx,
+            Leaf80000001Edx
+        ),
+        (0x8000_0002..=0x8000_0004, _) => registers!(
+            Leaf80000002Eax,
+            Leaf80000002Ebx,
+            Leaf80000002Ecx,
+            Leaf80000002Edx
+        ),
+        (0x8000_0005, _) => registers!(
+            Leaf80000005Eax,
+            Leaf80000005Ebx,
+            Leaf80000005Ecx,
+            Leaf80000005Edx
+        ),
+        (0x8000_0006, _) => registers!(
+            Leaf80000006Eax,
+            Leaf80000006Ebx,
+            Leaf80000006Ecx,
+            Leaf80000006Edx
+        ),
+        (0x8000_0007, _) => registers!(
+            Leaf80000007Eax,
+            Leaf80000007Ebx,
+            Leaf80000007Ecx,
+            Leaf80000007Edx
+        ),
+        (0x8000_0008, _) => registers!(
+            Leaf80000008Eax,
+            Leaf80000008Ebx,
+            Leaf80000008Ecx,
+            Leaf80000008Edx
+        ),
+        _ => return None,
+    })
+}
+
 /// Returns true if all 1 bits in `b` are also 1s in `a`.
-pub fn superset_u32<T: Into<u32>>(a: T, b: T) -> bool {
-    let (x, y): (u32, u32) = (a.into(), b.into());
+pub fn superset_bits<T: BitField>(a: &T, b: &T) -> bool {
+    let (x, y): (u128, u128) = (a.to_storage().into(), b.to_storage().into());
     ((!x) & y) == 0
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{IncompatibilityKind, Location};
+    #[test]
+    fn leaf_policies() {
+        let leaf15 = |numerator| {
+            Leaf15::from((
+                Leaf15Eax::from(2),
+                Leaf15Ebx::from(numerator),
+                Leaf15Ecx::from(0),
+                Leaf15Edx::from(0),
+            ))
+        };
+        assert_eq!(leaf15(188).supports(&leaf15(188)), Ok(()));
+        // The TSC frequency must match.
+        let incompatibilities = leaf15(188).supports(&leaf15(184)).unwrap_err();
+        assert!(matches!(
+            &incompatibilities.0[0].kind,
+            IncompatibilityKind::Mismatch {
+                location: Location::Field(Register::Ebx, field),
+                ..
+            } if field == "tsc_numerator"
+        ));
+
+        let leaf10 = |cos| {
+            Leaf10Subleaf1::from((
+                Leaf10Subleaf1Eax::from(10),
+                Leaf10Subleaf1Ebx::from(0),
+                Leaf10Subleaf1Ecx::from(0),
+                Leaf10Subleaf1Edx::from(cos),
+            ))
+        };
+        // More classes of service are fine, fewer are not.
+        assert_eq!(leaf10(15).supports(&leaf10(7)), Ok(()));
+        assert!(matches!(
+            leaf10(7).supports(&leaf10(15)).unwrap_err().0[0].kind,
+            IncompatibilityKind::Mismatch {
+                rule: Policy::GreaterOrEqual,
+                ..
+            }
+        ));
+
+        // Fewer MWAIT sub C-states or hardware feedback capabilities are not supported.
+        let leaf5 = |edx| {
+            Leaf5::from((
+                Leaf5Eax::from(0x40),
+                Leaf5Ebx::from(0x40),
+                Leaf5Ecx::from(3),
+                Leaf5Edx::from(edx),
+            ))
+        };
+        assert_eq!(leaf5(0x0002_0220).supports(&leaf5(0x0001_0120)), Ok(()));
+        assert_eq!(
+            leaf5(0x0001_0120)
+                .supports(&leaf5(0x0002_0220))
+                .unwrap_err()
+                .to_string(),
+            "0x5.0x0 edx `c2_states`: 0x1 >= 0x2 does not hold\n0x5.0x0 edx `c4_states`: 0x1 >= \
+             0x2 does not hold"
+        );
+        let leaf6 = |edx| {
+            Leaf6::from((
+                Leaf6Eax::from(0),
+                Leaf6Ebx::from(0),
+                Leaf6Ecx::from(0),
+                Leaf6Edx::from(edx),
+            ))
+        };
+        // The row index differs between logical processors.
+        assert_eq!(leaf6(0x0003_0003).supports(&leaf6(0x0000_0001)), Ok(()));
+        assert_eq!(
+            leaf6(0x0000_0001)
+                .supports(&leaf6(0x0000_0003))
+                .unwrap_err()
+                .to_string(),
+            "0x6.0x0 edx `bitmap_hardware_feedback_interface_capabilities`: 0x1 superset 0x3 does \
+             not hold"
+        );
+
+        // Sub-leaves sharing a type report their own index.
+        let component = |size| {
+            LeafDSubleafGt1::from((
+                LeafDSubleafGt1Eax::from(size),
+                LeafDSubleafGt1Ebx::from(0x240),
+                LeafDSubleafGt1Ecx::from(0),
+                LeafDSubleafGt1Edx::from(0),
+            ))
+        };
+        assert_eq!(
+            component(0x100)
+                .supports(&component(0x80), 2)
+                .unwrap_err()
+                .0[0]
+                .sub_leaf,
+            2
+        );
+    }
     #[test]
-    fn superset_u32_1() {
-        assert!(superset_u32(0b1010_0101, 0b0010_0101));
+    fn leaf12_layout() {
+        let section = Leaf12SubleafGt1::from((
+            Leaf12SubleafGt1Eax::from(0x7000_0001),
+            Leaf12SubleafGt1Ebx::from(0x1),
+            Leaf12SubleafGt1Ecx::from(0x0780_0001),
+            Leaf12SubleafGt1Edx::from(0),
+        ));
+        match section.layout() {
+            Ok(Leaf12SubleafGt1Layout::EpcSection { eax, ebx, ecx, .. }) => {
+                assert_eq!(eax.epc_base_31_12, 0x7_0000);
+                assert_eq!(ebx.epc_base_51_32, 1);
+                assert_eq!(ecx.epc_section, 1);
+            }
+            layout => panic!("{layout:?}"),
+        }
+        let registers =
+            |leaf: &Leaf12SubleafGt1| (leaf.eax.data, leaf.ebx.data, leaf.ecx.data, leaf.edx.data);
+        assert_eq!(
+            registers(&Leaf12SubleafGt1::from(section.layout().unwrap())),
+            registers(&section)
+        );
+
+        // An invalid sub-leaf has no EPC section, whatever the other registers hold.
+        let invalid = Leaf12SubleafGt1::from((
+            Leaf12SubleafGt1Eax::from(0),
+            Leaf12SubleafGt1Ebx::from(0x1),
+            Leaf12SubleafGt1Ecx::from(0),
+            Leaf12SubleafGt1Edx::from(0),
+        ));
+        let layout = invalid.layout().unwrap();
+        assert!(matches!(layout, Leaf12SubleafGt1Layout::Invalid(_)));
+        assert_eq!(Leaf12SubleafGt1::from(layout).ebx.data, 0);
+        assert_eq!(
+            Leaf12SubleafGt1::from((
+                Leaf12SubleafGt1Eax::from(0x2),
+                invalid.ebx,
+                invalid.ecx,
+                invalid.edx
+            ))
+            .layout()
+            .unwrap_err(),
+            UnknownDiscriminantErr(2)
+        );
+    }
+    #[test]
+    fn leaf_d_layout() {
+        let registers =
+            |leaf: &LeafDSubleafGt1| (leaf.eax.data, leaf.ebx.data, leaf.ecx.data, leaf.edx.data);
+        // AVX state, a user state component.
+        let avx = LeafDSubleafGt1::from((
+            LeafDSubleafGt1Eax::from(0x100),
+            LeafDSubleafGt1Ebx::from(0x240),
+            LeafDSubleafGt1Ecx::from(0),
+            LeafDSubleafGt1Edx::from(0),
+        ));
+        match avx.layout() {
+            Ok(LeafDSubleafGt1Layout::Xcr0 { ebx, .. }) => assert_eq!(ebx.save_area_offset, 0x240),
+            layout => panic!("{layout:?}"),
+        }
+        assert_eq!(
+            registers(&LeafDSubleafGt1::from(avx.layout().unwrap())),
+            registers(&avx)
+        );
+
+        // PT state, an aligned supervisor state component.
+        let pt = LeafDSubleafGt1::from((
+            LeafDSubleafGt1Eax::from(0x80),
+            LeafDSubleafGt1Ebx::from(0),
+            LeafDSubleafGt1Ecx::from(0b11),
+            LeafDSubleafGt1Edx::from(0),
+        ));
+        match pt.layout() {
+            Ok(LeafDSubleafGt1Layout::Ia32Xss { eax, ecx }) => {
+                assert_eq!(eax.save_area_size, 0x80);
+                assert!(ecx.data & 0b10 != 0);
+            }
+            layout => panic!("{layout:?}"),
+        }
+        assert_eq!(
+            registers(&LeafDSubleafGt1::from(pt.layout().unwrap())),
+            registers(&pt)
+        );
+    }
+    #[test]
+    fn leaf2_descriptors() {
+        // 0x01 is always the least significant byte of eax, 0x10 is not a known descriptor.
+        let leaf = Leaf2::from((
+            Leaf2Eax::from(0x0000_1001),
+            Leaf2Ebx::from(0),
+            Leaf2Ecx::from(0),
+            Leaf2Edx::from(0x0000_00FF),
+        ));
+        let descriptors: [&str; 16] = (&leaf).into();
+        assert_eq!(descriptors[0], KEYWORDS[&0x01]);
+        assert_eq!(descriptors[1], UNKNOWN_DESCRIPTOR);
+        assert_eq!(descriptors[2], KEYWORDS[&0x00]);
+        assert_eq!(descriptors[12], KEYWORDS[&0xFF]);
     }
     #[test]
-    fn superset_u32_2() {
-        assert!(!superset_u32(0b1010_0101, 0b0110_0101));
+    fn annotations() {
+        // HRESET is Intel's, from SDM revision 075.
+        let leaf20 = Leaf20Ebx::from(1);
+        assert_eq!(leaf20.revisions(), ["SDM-075"]);
+        assert_eq!(
+            leaf20
+                .vendor_mismatches(bit_fields::Vendor::Amd)
+                .into_iter()
+                .map(|field| field.ident)
+                .collect::<Vec<_>>(),
+            ["thread_director_history_reset"]
+        );
+        // The extended features of leaf 0x8000_0001 are shared with AMD.
+        let leaf8000_0001 = Leaf80000001Edx::from(u32::MAX);
+        assert!(leaf8000_0001.revisions().is_empty());
+        assert!(leaf8000_0001
+            .vendor_mismatches(bit_fields::Vendor::Amd)
+            .is_empty());
     }
     #[test]
-    fn superset_u32_3() {
-        assert!(!superset_u32(0b1000_0101, 0b0010_0101));
+    fn superset_bits_1() {
+        assert!(superset_bits(
+            &Leaf1Ecx::from_storage(0b1010_0101),
+            &Leaf1Ecx::from_storage(0b0010_0101)
+        ));
+    }
+    #[test]
+    fn superset_bits_2() {
+        assert!(!superset_bits(
+            &Leaf1Ecx::from_storage(0b1010_0101),
+            &Leaf1Ecx::from_storage(0b0110_0101)
+        ));
+    }
+    #[test]
+    fn superset_bits_3() {
+        assert!(!superset_bits(
+            &Leaf1Ecx::from_storage(0b1000_0101),
+            &Leaf1Ecx::from_storage(0b0010_0101)
+        ));
+    }
+    #[test]
+    fn supports() {
+        let xeon = || {
+            IntelCpuid::from(crate::fixture(include_str!(
+                "../fixtures/kvm-intel-xeon.txt"
+            )))
+        };
+        let host = xeon();
+        assert_eq!(host.leaf_4.len(), 4);
+        // Sub-leaves 2 to 9, with sub-leaves 3, 4 and 8 of unsupported state components zeroed.
+        assert_eq!(host.leaf_d.2.len(), 8);
+        assert_eq!(host.supports(&xeon()), Ok(()));
+
+        // A guest with a feature or a sub-leaf the host lacks is not supported.
+        let mut guest = xeon();
+        guest.leaf_7.0.ebx = Leaf7Subleaf0Ebx::from(0x0180_2042 | 1 << 16);
+        guest.leaf_4.push(Leaf4::from((
+            Leaf4Eax::from(0x121),
+            Leaf4Ebx::from(0),
+            Leaf4Ecx::from(0),
+            Leaf4Edx::from(0),
+        )));
+        let incompatibilities = host.supports(&guest).unwrap_err();
+        assert_eq!(
+            incompatibilities.to_string(),
+            "0x4.0x4: missing\n0x7.0x0 ebx `avx512f`: 0x0 superset 0x1 does not hold"
+        );
+
+        // Memory bandwidth allocation the host lacks, or with a higher throttling value, is not
+        // supported.
+        let mba = |max_throttling| {
+            Some(Leaf10Subleaf3::from((
+                Leaf10Subleaf3Eax::from(max_throttling),
+                Leaf10Subleaf3Ebx::from(0),
+                Leaf10Subleaf3Ecx::from(0),
+                Leaf10Subleaf3Edx::from(7),
+            )))
+        };
+        let mut guest = xeon();
+        guest.leaf_10.3 = mba(89);
+        assert_eq!(
+            host.supports(&guest).unwrap_err().to_string(),
+            "0x10.0x3: missing"
+        );
+        let mut host = xeon();
+        host.leaf_10.3 = mba(59);
+        assert_eq!(
+            host.supports(&guest).unwrap_err().to_string(),
+            "0x10.0x3 eax `max_mba_throt_resid`: 0x3b >= 0x59 does not hold"
+        );
+        guest.leaf_10.3 = mba(59);
+        assert_eq!(host.supports(&guest), Ok(()));
     }
 }
@@ -8,8 +8,8 @@
 )]
 //! Example
 //! ```ignore
-//! use test_cpuid::Cpuid;
-//! let cpuid = Cpuid::new();
+//! use test_cpuid::{Cpuid, HostCpuid};
+//! let cpuid = Cpuid::from_source(&HostCpuid);
 //! let highest_calling_parameter = cpuid
 //!     .leaf0x00_highest_function_parameter_an_manufacturer_id
 //!     .highest_calling_parameter;
@@ -33,20 +33,37 @@
 //! assert_eq!("00100000000000000000001000000111",serde_json::to_string(&my_bit_flags).unwrap());
 //! ```
 
-use core::arch::x86_64::{CpuidResult, __cpuid, __cpuid_count};
+use core::arch::x86_64::CpuidResult;
 use std::collections::HashMap;
 use std::mem::transmute;
 use std::{fmt, str};
 mod bitflags_util;
 mod cpuid_ffi;
+mod dependency;
+mod diff;
+mod dump;
+mod feature;
+mod host;
+mod incompatibility;
 pub mod intel;
+mod source;
+mod topology;
+use bit_fields::{bitfield, Policy};
 use bitflags::bitflags;
 #[allow(clippy::wildcard_imports)]
 use bitflags_util::*;
 pub use cpuid_ffi::*;
+pub use dependency::*;
+pub use diff::*;
+pub use dump::*;
+pub use feature::*;
+pub use host::*;
+pub use incompatibility::*;
 pub use intel::*;
 use log_derive::{logfn, logfn_inputs};
 use serde::{Deserialize, Serialize};
+pub use source::*;
+pub use topology::*;
 // -----------------------------------------------------------------------------
 // Bit flag definitions
 // -----------------------------------------------------------------------------
@@ -349,7 +366,7 @@ bitflags! {
         const cr8_legacy =      1 << 4;
         const abm =             1 << 5;
         const sse4a =           1 << 6;
-        const missalignsse =    1 << 7;
+        const misalignsse =     1 << 7;
         const _3dnowprefetch =  1 << 8;
         const osvw =            1 << 9;
         const ibs =             1 << 10;
@@ -544,61 +561,97 @@ pub struct Cpuid {
     /// leaf 25 / 0x19h
     #[serde(with = "l")]
     pub leaf0x19_cpuid_feature_bits: Leaf0x19_SubLeaf0_Ebx,
-    /// leaf 0x8000_0001
+    /// leaf `0x8000_0001`
     pub leaf0x8000_0001_highest_function_parameter_an_manufacturer_id:
         ExtendedProcessorInfoAndFeatureBits,
-    /// leaf 0x8000_0008
+    /// leaf `0x8000_0008`
     pub leaf0x8000_0008_virtual_and_physical_address_sizes: VirtualAndPhysicalAddressSizes,
-    /// leaf 0x8000_001F
+    /// leaf `0x8000_001F`
     #[serde(with = "p")]
     pub leaf0x8000_001F_cpuid_feature_bits: Leaf0x8000_001F_SubLeaf0_Eax,
     /// To allow usability beyond what is immediately considered at the moment, for leafs we do not
     /// explicitly describe we store them here in the format `<(leaf,subleaf),(eax,ebx,ecx,edx)>`.
+    ///
+    /// For leafs we only partially describe, the registers we do not describe are stored here with
+    /// the described registers zeroed, when any of them are non-zero.
     pub misc: HashMap<(u32, u32), (u32, u32, u32, u32)>,
 }
 impl Cpuid {
+    /// Returns an all-zero [`Cpuid`], use [`Cpuid::from_source`] to read one.
     #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
 
-    // If the feature set of `self` covers the feature set of `other`.
+    /// If the feature set of `self` covers the feature set of `other`.
+    ///
+    /// # Errors
+    ///
+    /// When it does not, returning every leaf, register, field or bit which is not covered.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    pub fn covers(&self, other: &Self) -> bool {
-        // We first check they have the same manufacturer
-        self.leaf0x00_highest_function_parameter_an_manufacturer_id
-            .covers(&other.leaf0x00_highest_function_parameter_an_manufacturer_id)
-            && self
-                .leaf0x01_process_info_and_feature_bits
-                .covers(&other.leaf0x01_process_info_and_feature_bits)
-            && self
-                .leaf0x06_thermal_and_power_management
-                .covers(&other.leaf0x06_thermal_and_power_management)
-            && self
-                .leaf0x07_extended_features
-                .covers(&other.leaf0x07_extended_features)
-            && self
-                .leaf0x0d_cpuid_feature_bits
-                .contains(other.leaf0x0d_cpuid_feature_bits)
-            && self
-                .leaf0x12_cpuid_feature_bits
-                .contains(other.leaf0x12_cpuid_feature_bits)
-            && self
-                .leaf0x14_cpuid_feature_bits
-                .contains(other.leaf0x14_cpuid_feature_bits)
-            && self
-                .leaf0x19_cpuid_feature_bits
-                .contains(other.leaf0x19_cpuid_feature_bits)
-            && self
-                .leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
-                .covers(&other.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id)
-            && self
-                .leaf0x8000_0008_virtual_and_physical_address_sizes
-                .covers(&other.leaf0x8000_0008_virtual_and_physical_address_sizes)
-            && self
-                .leaf0x8000_001F_cpuid_feature_bits
-                .contains(other.leaf0x8000_001F_cpuid_feature_bits)
+    pub fn covers(&self, other: &Self) -> Result<(), Incompatibilities> {
+        let leaf0 = &self.leaf0x00_highest_function_parameter_an_manufacturer_id;
+        let vendor = [leaf0.ebx(), leaf0.edx(), leaf0.ecx()];
+        let superset = |leaf, sub_leaf, register, this: u32, other: u32| {
+            Incompatibilities::superset(vendor, leaf, sub_leaf, register, this, other)
+        };
+        Incompatibilities::combine([
+            // We first check they have the same manufacturer
+            self.leaf0x00_highest_function_parameter_an_manufacturer_id
+                .covers(&other.leaf0x00_highest_function_parameter_an_manufacturer_id),
+            self.leaf0x01_process_info_and_feature_bits
+                .covers(&other.leaf0x01_process_info_and_feature_bits, vendor),
+            self.leaf0x06_thermal_and_power_management
+                .covers(&other.leaf0x06_thermal_and_power_management, vendor),
+            self.leaf0x07_extended_features
+                .covers(&other.leaf0x07_extended_features, vendor),
+            superset(
+                0xD,
+                1,
+                Register::Eax,
+                self.leaf0x0d_cpuid_feature_bits.bits(),
+                other.leaf0x0d_cpuid_feature_bits.bits(),
+            ),
+            superset(
+                0x12,
+                0,
+                Register::Eax,
+                self.leaf0x12_cpuid_feature_bits.bits(),
+                other.leaf0x12_cpuid_feature_bits.bits(),
+            ),
+            superset(
+                0x14,
+                0,
+                Register::Ebx,
+                self.leaf0x14_cpuid_feature_bits.bits(),
+                other.leaf0x14_cpuid_feature_bits.bits(),
+            ),
+            superset(
+                0x19,
+                0,
+                Register::Ebx,
+                self.leaf0x19_cpuid_feature_bits.bits(),
+                other.leaf0x19_cpuid_feature_bits.bits(),
+            ),
+            self.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id
+                .covers(
+                    &other.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id,
+                    vendor,
+                ),
+            self.leaf0x8000_0008_virtual_and_physical_address_sizes
+                .covers(
+                    &other.leaf0x8000_0008_virtual_and_physical_address_sizes,
+                    vendor,
+                ),
+            superset(
+                0x8000_001F,
+                0,
+                Register::Eax,
+                self.leaf0x8000_001F_cpuid_feature_bits.bits(),
+                other.leaf0x8000_001F_cpuid_feature_bits.bits(),
+            ),
+        ])
     }
 
     #[must_use]
@@ -610,66 +663,10 @@ impl Cpuid {
     }
 }
 
+/// An all-zero [`Cpuid`], use [`Cpuid::from_source`] to read one.
 impl Default for Cpuid {
     fn default() -> Self {
-        Self {
-            leaf0x00_highest_function_parameter_an_manufacturer_id:
-                HighestFunctionParameterAndManufacturerID::new(),
-            leaf0x01_process_info_and_feature_bits: ProcessorInfoAndFeatureBits::new(),
-            leaf0x06_thermal_and_power_management: ThermalAndPowerManagement::new(),
-            leaf0x07_extended_features: ExtendedFeatures::new(),
-            leaf0x0d_cpuid_feature_bits: {
-                let CpuidResult { eax, .. } = unsafe { __cpuid_count(13, 1) };
-                Leaf0xD_SubLeaf1_Eax { bits: eax }
-            },
-            leaf0x12_cpuid_feature_bits: {
-                let CpuidResult {
-                    eax,
-                    ebx: _,
-                    ecx: _,
-                    edx: _,
-                } = unsafe { __cpuid_count(18, 0) };
-                Leaf0x12_SubLeaf0_Eax { bits: eax }
-            },
-            leaf0x14_cpuid_feature_bits: {
-                let CpuidResult {
-                    eax: _,
-                    ebx,
-                    ecx: _,
-                    edx: _,
-                } = unsafe { __cpuid_count(20, 0) };
-                Leaf0x14_SubLeaf0_Ebx { bits: ebx }
-            },
-            leaf0x19_cpuid_feature_bits: {
-                let CpuidResult {
-                    eax: _,
-                    ebx,
-                    ecx: _,
-                    edx: _,
-                } = unsafe { __cpuid_count(25, 0) };
-                Leaf0x19_SubLeaf0_Ebx { bits: ebx }
-            },
-            leaf0x8000_0001_highest_function_parameter_an_manufacturer_id: {
-                let CpuidResult { ecx, edx, .. } = unsafe { __cpuid_count(0x8000_0001, 0) };
-                ExtendedProcessorInfoAndFeatureBits {
-                    edx: Leaf0x8000_0001_SubLeaf0_Edx { bits: edx },
-                    ecx: Leaf0x8000_0001_SubLeaf0_Ecx { bits: ecx },
-                }
-            },
-            leaf0x8000_0008_virtual_and_physical_address_sizes: {
-                let CpuidResult { eax, ebx, ecx, .. } = unsafe { __cpuid_count(0x8000_0008, 0) };
-                VirtualAndPhysicalAddressSizes {
-                    eax: Leaf0x8000_0008_SubLeaf0_Eax(eax),
-                    ebx: Leaf0x8000_0008_SubLeaf0_Ebx { bits: ebx },
-                    ecx: Leaf0x8000_0008_SubLeaf0_Ecx(ecx),
-                }
-            },
-            leaf0x8000_001F_cpuid_feature_bits: {
-                let CpuidResult { eax, .. } = unsafe { __cpuid_count(0x8000_001F, 0) };
-                Leaf0x8000_001F_SubLeaf0_Eax { bits: eax }
-            },
-            misc: HashMap::new(),
-        }
+        Self::from_source(&RawCpuid::new())
     }
 }
 impl fmt::Debug for Cpuid {
@@ -719,93 +716,216 @@ impl fmt::Debug for Cpuid {
                 "leaf0x8000_001F_cpuid_feature_bits",
                 &self.leaf0x8000_001F_cpuid_feature_bits,
             )
+            .field("misc", &self.misc)
             .finish()
     }
 }
-/// This conversion is lossy, this means we cannot implement `From<Cpuid> for RawCpuid`
+/// The leaves explicitly described by [`Cpuid`], with masks of the registers `[eax,ebx,ecx,edx]`
+/// they describe.
+const DESCRIBED_LEAVES: [((u32, u32), [u32; 4]); 12] = [
+    ((0x0, 0), [u32::MAX, u32::MAX, u32::MAX, u32::MAX]),
+    ((0x1, 0), [u32::MAX, u32::MAX, u32::MAX, u32::MAX]),
+    ((0x6, 0), [u32::MAX, u32::MAX, u32::MAX, 0]),
+    ((0x7, 0), [0, u32::MAX, u32::MAX, u32::MAX]),
+    ((0x7, 1), [u32::MAX, 0, 0, 0]),
+    ((0xD, 1), [u32::MAX, 0, 0, 0]),
+    ((0x12, 0), [u32::MAX, 0, 0, 0]),
+    ((0x14, 0), [0, u32::MAX, 0, 0]),
+    ((0x19, 0), [0, u32::MAX, 0, 0]),
+    ((0x8000_0001, 0), [0, 0, u32::MAX, u32::MAX]),
+    ((0x8000_0008, 0), [u32::MAX, u32::MAX, u32::MAX, 0]),
+    ((0x8000_001F, 0), [u32::MAX, 0, 0, 0]),
+];
+
+/// Returns the registers of an entry not described by [`Cpuid`], `None` if there are none.
+fn undescribed(
+    key: (u32, u32),
+    (eax, ebx, ecx, edx): (u32, u32, u32, u32),
+) -> Option<(u32, u32, u32, u32)> {
+    match DESCRIBED_LEAVES.iter().find(|(leaf, _)| *leaf == key) {
+        Some((_, [a, b, c, d])) => {
+            let registers = (eax & !a, ebx & !b, ecx & !c, edx & !d);
+            (registers != (0, 0, 0, 0)).then_some(registers)
+        }
+        None => Some((eax, ebx, ecx, edx)),
+    }
+}
+
+/// Error type for [`Cpuid::try_from`], also returned as warnings by [`Cpuid::from_raw_lenient`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromRawCpuidError {
+    /// A leaf (sub-leaf) [`Cpuid`] describes has no entry.
+    Missing {
+        /// The leaf (function).
+        leaf: u32,
+        /// The sub-leaf (index).
+        sub_leaf: u32,
+    },
+    /// A leaf (sub-leaf) [`Cpuid`] describes has more than one entry.
+    Duplicate {
+        /// The leaf (function).
+        leaf: u32,
+        /// The sub-leaf (index).
+        sub_leaf: u32,
+    },
+}
+impl fmt::Display for FromRawCpuidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { leaf, sub_leaf } => {
+                write!(f, "Leaf {leaf:#x} sub-leaf {sub_leaf:#x} is missing")
+            }
+            Self::Duplicate { leaf, sub_leaf } => {
+                write!(
+                    f,
+                    "Leaf {leaf:#x} sub-leaf {sub_leaf:#x} has more than one entry"
+                )
+            }
+        }
+    }
+}
+impl std::error::Error for FromRawCpuidError {}
+
+/// The entries of a [`RawCpuid`] as a [`CpuidSource`], with zeros for missing entries.
+struct ZeroFilled<'a>(&'a RawCpuid);
+impl CpuidSource for ZeroFilled<'_> {
+    fn cpuid(&self, leaf: u32, sub_leaf: u32) -> CpuidResult {
+        self.0.get(leaf, sub_leaf).map_or(
+            CpuidResult {
+                eax: 0,
+                ebx: 0,
+                ecx: 0,
+                edx: 0,
+            },
+            CpuidResult::from,
+        )
+    }
+}
+
+impl Cpuid {
+    /// Converts like [`Cpuid::try_from`], but fills the leaves (sub-leaves) which are missing with
+    /// zeros and uses the first entry of duplicated ones, returning these as warnings.
+    ///
+    /// Old CPUs often lack e.g. leaves `0x14` and `0x19`.
+    #[must_use]
+    pub fn from_raw_lenient(cpuid: &RawCpuid) -> (Self, Vec<FromRawCpuidError>) {
+        let warnings = DESCRIBED_LEAVES
+            .iter()
+            .filter_map(|&((leaf, sub_leaf), _)| {
+                match cpuid
+                    .iter()
+                    .filter(|entry| (entry.function, entry.index) == (leaf, sub_leaf))
+                    .count()
+                {
+                    0 => Some(FromRawCpuidError::Missing { leaf, sub_leaf }),
+                    1 => None,
+                    _ => Some(FromRawCpuidError::Duplicate { leaf, sub_leaf }),
+                }
+            })
+            .collect();
+        let cpuid = Self {
+            // Keep what we do not already store explicitly, collected in reverse so the first of
+            // duplicated entries is kept.
+            misc: cpuid
+                .iter()
+                .rev()
+                .filter_map(|entry| {
+                    let key = (entry.function, entry.index);
+                    undescribed(key, (entry.eax, entry.ebx, entry.ecx, entry.edx))
+                        .map(|registers| (key, registers))
+                })
+                .collect(),
+            ..Self::from_source(&ZeroFilled(cpuid))
+        };
+        (cpuid, warnings)
+    }
+}
+
+/// Every entry is kept, either in the explicitly described leafs or in [`Cpuid::misc`], so
+/// `RawCpuid::from(Cpuid::try_from(raw)?)` equals `raw` but for the order of its entries and its
+/// flags, which are set with [`KvmCpuidFlags::for_leaf`].
 impl TryFrom<RawCpuid> for Cpuid {
-    // TODO Change this to at least `'static str` and use descriptions.
-    type Error = ();
+    type Error = FromRawCpuidError;
 
+    /// # Errors
+    ///
+    /// When a leaf (sub-leaf) [`Cpuid`] describes is missing or duplicated, returning the first in
+    /// the order of the fields of [`Cpuid`] (see [`Cpuid::from_raw_lenient`]).
     fn try_from(cpuid: RawCpuid) -> Result<Self, Self::Error> {
-        Ok(Self {
-            leaf0x00_highest_function_parameter_an_manufacturer_id:
-                HighestFunctionParameterAndManufacturerID::from(cpuid.get(0, 0).ok_or(())?.clone()),
-            leaf0x01_process_info_and_feature_bits: ProcessorInfoAndFeatureBits::from(
-                cpuid.get(1, 0).ok_or(())?.clone(),
+        let (cpuid, warnings) = Self::from_raw_lenient(&cpuid);
+        match warnings.first() {
+            Some(error) => Err(*error),
+            None => Ok(cpuid),
+        }
+    }
+}
+
+/// Rebuilds the explicitly described leafs, merged with the registers of them stored in
+/// [`Cpuid::misc`], followed by the other entries of [`Cpuid::misc`], sorted by leaf and sub-leaf.
+///
+/// Every described leaf is always emitted, so one missing from the entries the [`Cpuid`] was read
+/// from (e.g. leaf `0x8000_001F` on Intel, see [`Cpuid::from_raw_lenient`]) comes back zero-filled.
+impl From<Cpuid> for RawCpuid {
+    fn from(cpuid: Cpuid) -> Self {
+        let leaf0 = &cpuid.leaf0x00_highest_function_parameter_an_manufacturer_id;
+        let leaf1 = &cpuid.leaf0x01_process_info_and_feature_bits;
+        let leaf6 = &cpuid.leaf0x06_thermal_and_power_management;
+        let leaf7 = &cpuid.leaf0x07_extended_features;
+        let leaf0x8000_0001 = &cpuid.leaf0x8000_0001_highest_function_parameter_an_manufacturer_id;
+        let leaf0x8000_0008 = &cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes;
+        let described = [
+            (leaf0.eax(), leaf0.ebx(), leaf0.ecx(), leaf0.edx()),
+            (leaf1.eax(), leaf1.ebx(), leaf1.ecx(), leaf1.edx()),
+            (leaf6.eax(), leaf6.ebx(), leaf6.ecx(), 0),
+            (
+                0,
+                leaf7.sub_leaf0.ebx(),
+                leaf7.sub_leaf0.ecx(),
+                leaf7.sub_leaf0.edx(),
             ),
-            leaf0x06_thermal_and_power_management: ThermalAndPowerManagement::from(
-                cpuid.get(6, 0).ok_or(())?.clone(),
+            (leaf7.sub_leaf1.bits(), 0, 0, 0),
+            (cpuid.leaf0x0d_cpuid_feature_bits.bits(), 0, 0, 0),
+            (cpuid.leaf0x12_cpuid_feature_bits.bits(), 0, 0, 0),
+            (0, cpuid.leaf0x14_cpuid_feature_bits.bits(), 0, 0),
+            (0, cpuid.leaf0x19_cpuid_feature_bits.bits(), 0, 0),
+            (0, 0, leaf0x8000_0001.ecx(), leaf0x8000_0001.edx()),
+            (
+                leaf0x8000_0008.eax(),
+                leaf0x8000_0008.ebx(),
+                leaf0x8000_0008.ecx(),
+                0,
             ),
-            leaf0x07_extended_features: ExtendedFeatures::from((
-                cpuid.get(7, 0).ok_or(())?.clone(),
-                cpuid.get(7, 1).ok_or(())?.clone(),
-            )),
-            leaf0x0d_cpuid_feature_bits: {
-                let RawCpuidEntry { eax, .. } = cpuid.get(13, 1).ok_or(())?;
-                Leaf0xD_SubLeaf1_Eax { bits: *eax }
-            },
-            leaf0x12_cpuid_feature_bits: {
-                let RawCpuidEntry { eax, .. } = cpuid.get(18, 0).ok_or(())?;
-                Leaf0x12_SubLeaf0_Eax { bits: *eax }
-            },
-            leaf0x14_cpuid_feature_bits: {
-                let RawCpuidEntry { ebx, .. } = cpuid.get(20, 0).ok_or(())?;
-                Leaf0x14_SubLeaf0_Ebx { bits: *ebx }
-            },
-            leaf0x19_cpuid_feature_bits: {
-                let RawCpuidEntry { ebx, .. } = cpuid.get(25, 0).ok_or(())?;
-                Leaf0x19_SubLeaf0_Ebx { bits: *ebx }
-            },
-            leaf0x8000_0001_highest_function_parameter_an_manufacturer_id: {
-                let RawCpuidEntry { ecx, edx, .. } = cpuid.get(0x8000_0001, 0).ok_or(())?;
-                ExtendedProcessorInfoAndFeatureBits {
-                    edx: Leaf0x8000_0001_SubLeaf0_Edx { bits: *edx },
-                    ecx: Leaf0x8000_0001_SubLeaf0_Ecx { bits: *ecx },
-                }
-            },
-            leaf0x8000_0008_virtual_and_physical_address_sizes: {
-                let RawCpuidEntry { eax, ebx, ecx, .. } = cpuid.get(0x8000_0008, 0).ok_or(())?;
-                VirtualAndPhysicalAddressSizes {
-                    eax: Leaf0x8000_0008_SubLeaf0_Eax(*eax),
-                    ebx: Leaf0x8000_0008_SubLeaf0_Ebx { bits: *ebx },
-                    ecx: Leaf0x8000_0008_SubLeaf0_Ecx(*ecx),
-                }
-            },
-            leaf0x8000_001F_cpuid_feature_bits: {
-                let RawCpuidEntry { eax, .. } = cpuid.get(0x8000_001F, 0).ok_or(())?;
-                Leaf0x8000_001F_SubLeaf0_Eax { bits: *eax }
-            },
-            misc: {
-                // Filter out entries we already store explicitly
-                #[allow(clippy::unnested_or_patterns)]
+            (cpuid.leaf0x8000_001F_cpuid_feature_bits.bits(), 0, 0, 0),
+        ];
+
+        let entry = |(function, index): (u32, u32), (eax, ebx, ecx, edx)| {
+            RawCpuidEntry::new(
+                function,
+                index,
+                KvmCpuidFlags::for_leaf(function),
+                eax,
+                ebx,
+                ecx,
+                edx,
+            )
+        };
+        let mut raw = DESCRIBED_LEAVES
+            .iter()
+            .zip(described)
+            .map(|((key, _), (eax, ebx, ecx, edx))| {
+                let (a, b, c, d) = cpuid.misc.get(key).copied().unwrap_or_default();
+                entry(*key, (eax | a, ebx | b, ecx | c, edx | d))
+            })
+            .chain(
                 cpuid
+                    .misc
                     .iter()
-                    .filter(|entry| {
-                        !matches!(
-                            (entry.function, entry.index),
-                            (0, 0)
-                                | (1, 0)
-                                | (6, 0)
-                                | (7, 0)
-                                | (13, 1)
-                                | (18, 0)
-                                | (20, 0)
-                                | (25, 0)
-                                | (0x8000_0001, 0)
-                                | (0x8000_0008, 0)
-                                | (0x8000_001F, 0)
-                        )
-                    })
-                    .map(|entry| {
-                        (
-                            (entry.function, entry.index),
-                            (entry.eax, entry.ebx, entry.ecx, entry.edx),
-                        )
-                    })
-                    .collect()
-            },
-        })
+                    .filter(|(key, _)| DESCRIBED_LEAVES.iter().all(|(leaf, _)| leaf != *key))
+                    .map(|(key, registers)| entry(*key, *registers)),
+            )
+            .collect::<Self>();
+        raw.sort();
+        raw
     }
 }
 
@@ -1064,9 +1184,24 @@ impl HighestFunctionParameterAndManufacturerID {
     /// simply require the mnanufactuer id's match.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.manufacturer_id == other.manufacturer_id
-            && self.highest_calling_parameter >= other.highest_calling_parameter
+    fn covers(&self, other: &Self) -> Result<(), Incompatibilities> {
+        let manufacturer_id = |register, this, other| {
+            Incompatibilities::register(0, 0, register, Policy::Equal, this, other)
+        };
+        Incompatibilities::combine([
+            Incompatibilities::field(
+                0,
+                0,
+                Register::Eax,
+                "highest_calling_parameter",
+                Policy::GreaterOrEqual,
+                self.highest_calling_parameter,
+                other.highest_calling_parameter,
+            ),
+            manufacturer_id(Register::Ebx, self.ebx(), other.ebx()),
+            manufacturer_id(Register::Ecx, self.ecx(), other.ecx()),
+            manufacturer_id(Register::Edx, self.edx(), other.edx()),
+        ])
     }
 
     #[must_use]
@@ -1087,8 +1222,7 @@ impl fmt::Debug for HighestFunctionParameterAndManufacturerID {
 }
 impl Default for HighestFunctionParameterAndManufacturerID {
     fn default() -> Self {
-        let CpuidResult { eax, ebx, ecx, edx } = unsafe { __cpuid(0) };
-        Self::from((eax, ebx, ecx, edx))
+        Self::from((0, 0, 0, 0))
     }
 }
 impl From<RawCpuidEntry> for HighestFunctionParameterAndManufacturerID {
@@ -1150,19 +1284,21 @@ impl ProcessorInfoAndFeatureBits {
 
     #[must_use]
     pub fn eax(&self) -> u32 {
-        self.processor_version_information.0
+        self.processor_version_information.data
     }
 
     // If the feature set of `self` covers the feature set of `other`.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.processor_version_information
-            .covers(&other.processor_version_information)
-            && self
-                .additional_information
-                .covers(&other.additional_information)
-            && self.feature_information.covers(&other.feature_information)
+    fn covers(&self, other: &Self, vendor: [u32; 3]) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            self.processor_version_information
+                .covers(&other.processor_version_information),
+            self.additional_information
+                .covers(&other.additional_information),
+            self.feature_information
+                .covers(&other.feature_information, vendor),
+        ])
     }
 
     #[must_use]
@@ -1175,8 +1311,7 @@ impl ProcessorInfoAndFeatureBits {
 }
 impl Default for ProcessorInfoAndFeatureBits {
     fn default() -> Self {
-        let CpuidResult { eax, ebx, ecx, edx } = unsafe { __cpuid_count(1, 0) };
-        Self::from((eax, ebx, ecx, edx))
+        Self::from((0, 0, 0, 0))
     }
 }
 impl From<RawCpuidEntry> for ProcessorInfoAndFeatureBits {
@@ -1191,8 +1326,8 @@ impl From<RawCpuidEntry> for ProcessorInfoAndFeatureBits {
 impl From<(u32, u32, u32, u32)> for ProcessorInfoAndFeatureBits {
     fn from((eax, ebx, ecx, edx): (u32, u32, u32, u32)) -> Self {
         Self {
-            processor_version_information: ProcessorVersionInformation(eax),
-            additional_information: unsafe { transmute::<_, AdditionalInformation>(ebx) },
+            processor_version_information: ProcessorVersionInformation::from(eax),
+            additional_information: unsafe { transmute::<u32, AdditionalInformation>(ebx) },
             feature_information: FeatureInformation {
                 ecx: Leaf0x1_SubLeaf0_Ecx { bits: ecx },
                 edx: Leaf0x1_SubLeaf0_Edx { bits: edx },
@@ -1201,151 +1336,28 @@ impl From<(u32, u32, u32, u32)> for ProcessorInfoAndFeatureBits {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
-#[repr(C)]
-pub struct ProcessorVersionInformation(u32);
+#[rustfmt::skip]
+bitfield!(ProcessorVersionInformation, u32, {
+    /// Stepping ID
+    stepping_id: 0..4,
+    /// Model
+    model: 4..8,
+    /// Family ID
+    family_id: 8..12,
+    /// Processor Type
+    processor_type: 12..14,
+    // 14th and 15th bits reserved
+    /// Extended Model ID
+    extended_model_id: 16..20,
+    /// Extended Family ID
+    extended_family_id: 20..28,
+    // 28th to 31st bits reserved
+});
 impl ProcessorVersionInformation {
-    #[must_use]
-    pub fn stepping_id(&self) -> u8 {
-        (self.0 & 0b0000_0000_0000_0000_0000_0000_0000_1111) as u8
-    }
-
-    #[must_use]
-    pub fn model(&self) -> u8 {
-        ((self.0 & 0b0000_0000_0000_0000_0000_0000_1111_0000) >> 4) as u8
-    }
-
-    #[must_use]
-    pub fn family_id(&self) -> u8 {
-        ((self.0 & 0b0000_0000_0000_0000_0000_1111_0000_0000) >> 8) as u8
-    }
-
-    #[must_use]
-    pub fn processor_type(&self) -> u8 {
-        ((self.0 & 0b0000_0000_0000_0000_0011_0000_0000_0000) >> 12) as u8
-    }
-
-    #[must_use]
-    pub fn extended_model_id(&self) -> u8 {
-        ((self.0 & 0b0000_0000_0000_1111_0000_0000_0000_0000) >> 16) as u8
-    }
-
-    #[must_use]
-    pub fn extended_family_id(&self) -> u8 {
-        ((self.0 & 0b0000_1111_1111_0000_0000_0000_0000_0000) >> 20) as u8
-    }
-
-    /// # Errors
-    ///
-    /// Errors when the given value `x` is greater than or equal to `16`
-    /// (`if x < 16 { ... Ok(()) } else { Err(...) }`).
-    pub fn set_stepping_id(&mut self, x: u8) -> Result<(), &str> {
-        if x < 16 {
-            self.0 = (self.0 & !0b0000_0000_0000_0000_0000_0000_0000_1111) | u32::from(x);
-            Ok(())
-        } else {
-            Err("x >= 16")
-        }
-    }
-
-    /// # Errors
-    ///
-    /// Errors when the given value `x` is greater than or equal to `16`
-    /// (`if x < 16 { ... Ok(()) } else { Err(...) }`).
-    pub fn set_model(&mut self, x: u8) -> Result<(), &str> {
-        if x < 16 {
-            self.0 = (self.0 & !0b0000_0000_0000_0000_0000_0000_1111_0000) | (u32::from(x) << 4);
-            Ok(())
-        } else {
-            Err("x >= 16")
-        }
-    }
-
-    /// # Errors
-    ///
-    /// Errors when the given value `x` is greater than or equal to `16`
-    /// (`if x < 16 { ... Ok(()) } else { Err(...) }`).
-    pub fn set_family_id(&mut self, x: u8) -> Result<(), &str> {
-        if x < 16 {
-            self.0 = (self.0 & !0b0000_0000_0000_0000_0000_1111_0000_0000) | (u32::from(x) << 8);
-            Ok(())
-        } else {
-            Err("x >= 16")
-        }
-    }
-
-    /// # Errors
-    ///
-    /// Errors when the given value `x` is greater than or equal to `4`
-    /// (`if x < 4 { ... Ok(()) } else { Err(...) }`).
-    pub fn set_processor_type(&mut self, x: u8) -> Result<(), &str> {
-        if x < 4 {
-            self.0 = (self.0 & !0b0000_0000_0000_0000_0011_0000_0000_0000) | (u32::from(x) << 12);
-            Ok(())
-        } else {
-            Err("x >= 4")
-        }
-    }
-
-    /// # Errors
-    ///
-    /// Errors when the given value `x` is greater than or equal to `16`
-    /// (`if x < 16 { ... Ok(()) } else { Err(...) }`).
-    pub fn set_extended_model_id(&mut self, x: u8) -> Result<(), &str> {
-        if x < 16 {
-            self.0 = (self.0 & !0b0000_0000_0000_1111_0000_0000_0000_0000) | (u32::from(x) << 16);
-            Ok(())
-        } else {
-            Err("x >= 16")
-        }
-    }
-
-    pub fn set_extended_family_id(&mut self, x: u8) {
-        self.0 = (self.0 & !0b0000_1111_1111_0000_0000_0000_0000_0000) | (u32::from(x) << 20);
-    }
-
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.0 == other.0
-    }
-}
-impl TryFrom<HashMap<&str, u8>> for ProcessorVersionInformation {
-    type Error = String;
-
-    fn try_from(value: HashMap<&str, u8>) -> Result<Self, Self::Error> {
-        let mut base = Self(0);
-        base.set_stepping_id(*value.get("stepping_id").ok_or("`stepping_id` not found")?)?;
-        base.set_model(*value.get("model").ok_or("`model` not found")?)?;
-        base.set_family_id(*value.get("family_id").ok_or("`family_id` not found")?)?;
-        base.set_processor_type(
-            *value
-                .get("processor_type")
-                .ok_or("`processor_type` not found")?,
-        )?;
-        base.set_extended_model_id(
-            *value
-                .get("extended_model_id")
-                .ok_or("`extended_model_id` not found")?,
-        )?;
-        base.set_extended_family_id(
-            *value
-                .get("extended_family_id")
-                .ok_or("`extended_family_id` not found")?,
-        );
-        Ok(base)
-    }
-}
-impl fmt::Debug for ProcessorVersionInformation {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("ProcessorVersionInformation")
-            .field("stepping_id", &self.stepping_id())
-            .field("model", &self.model())
-            .field("family_id", &self.family_id())
-            .field("processor_type", &self.processor_type())
-            .field("extended_model_id", &self.extended_model_id())
-            .field("extended_family_id", &self.extended_family_id())
-            .finish()
+    fn covers(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::register(1, 0, Register::Eax, Policy::Equal, self.data, other.data)
     }
 }
 
@@ -1361,13 +1373,32 @@ pub struct AdditionalInformation {
 impl AdditionalInformation {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.brand_index == other.brand_index
-            && self.clflush_line_size == other.clflush_line_size
-            && self.maximum_addressable_logical_processor_ids
-                >= other.maximum_addressable_logical_processor_ids
-        // This value doesn't directly relate to available functionlity
-        // && self.local_apic_id == other.local_apic_id
+    fn covers(&self, other: &Self) -> Result<(), Incompatibilities> {
+        let field = |field, rule, this: u8, other: u8| {
+            Incompatibilities::field(1, 0, Register::Ebx, field, rule, this, other)
+        };
+        Incompatibilities::combine([
+            field(
+                "brand_index",
+                Policy::Equal,
+                self.brand_index,
+                other.brand_index,
+            ),
+            field(
+                "clflush_line_size",
+                Policy::Equal,
+                self.clflush_line_size,
+                other.clflush_line_size,
+            ),
+            field(
+                "maximum_addressable_logical_processor_ids",
+                Policy::GreaterOrEqual,
+                self.maximum_addressable_logical_processor_ids,
+                other.maximum_addressable_logical_processor_ids,
+            ),
+            // This value doesn't directly relate to available functionlity
+            // field("local_apic_id", Policy::Equal, self.local_apic_id, other.local_apic_id),
+        ])
     }
 }
 
@@ -1382,8 +1413,14 @@ pub struct FeatureInformation {
 impl FeatureInformation {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.ecx.contains(other.ecx) && self.edx.contains(other.edx)
+    fn covers(&self, other: &Self, vendor: [u32; 3]) -> Result<(), Incompatibilities> {
+        let superset = |register, this, other| {
+            Incompatibilities::superset(vendor, 1, 0, register, this, other)
+        };
+        Incompatibilities::combine([
+            superset(Register::Ecx, self.ecx.bits(), other.ecx.bits()),
+            superset(Register::Edx, self.edx.bits(), other.edx.bits()),
+        ])
     }
 }
 impl fmt::Debug for FeatureInformation {
@@ -1426,11 +1463,12 @@ impl ThermalAndPowerManagement {
 
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.features.covers(&other.features)
-            && self
-                .number_of_interrupt_thresholds
-                .covers(&other.number_of_interrupt_thresholds)
+    fn covers(&self, other: &Self, vendor: [u32; 3]) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            self.features.covers(&other.features, vendor),
+            self.number_of_interrupt_thresholds
+                .covers(&other.number_of_interrupt_thresholds),
+        ])
     }
 
     #[must_use]
@@ -1443,8 +1481,7 @@ impl ThermalAndPowerManagement {
 }
 impl Default for ThermalAndPowerManagement {
     fn default() -> Self {
-        let CpuidResult { eax, ebx, ecx, edx } = unsafe { __cpuid_count(6, 0) };
-        Self::from((eax, ebx, ecx, edx))
+        Self::from((0, 0, 0, 0))
     }
 }
 impl From<RawCpuidEntry> for ThermalAndPowerManagement {
@@ -1479,8 +1516,14 @@ pub struct ThermalAndPowerManagementFeatures {
 impl ThermalAndPowerManagementFeatures {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.eax.contains(other.eax) && self.ecx.contains(other.ecx)
+    fn covers(&self, other: &Self, vendor: [u32; 3]) -> Result<(), Incompatibilities> {
+        let superset = |register, this, other| {
+            Incompatibilities::superset(vendor, 6, 0, register, this, other)
+        };
+        Incompatibilities::combine([
+            superset(Register::Eax, self.eax.bits(), other.eax.bits()),
+            superset(Register::Ecx, self.ecx.bits(), other.ecx.bits()),
+        ])
     }
 }
 impl fmt::Debug for ThermalAndPowerManagementFeatures {
@@ -1504,8 +1547,16 @@ impl Leaf6SubLeaf0Ebx {
 
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.number_of_interrupt_thresholds() >= other.number_of_interrupt_thresholds()
+    fn covers(&self, other: &Self) -> Result<(), Incompatibilities> {
+        Incompatibilities::field(
+            6,
+            0,
+            Register::Ebx,
+            "number_of_interrupt_thresholds",
+            Policy::GreaterOrEqual,
+            self.number_of_interrupt_thresholds(),
+            other.number_of_interrupt_thresholds(),
+        )
     }
 }
 impl fmt::Debug for Leaf6SubLeaf0Ebx {
@@ -1530,8 +1581,18 @@ impl ExtendedFeatures {
 
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.sub_leaf0.covers(&other.sub_leaf0) && self.sub_leaf1.contains(other.sub_leaf1)
+    fn covers(&self, other: &Self, vendor: [u32; 3]) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            self.sub_leaf0.covers(&other.sub_leaf0, vendor),
+            Incompatibilities::superset(
+                vendor,
+                7,
+                1,
+                Register::Eax,
+                self.sub_leaf1.bits(),
+                other.sub_leaf1.bits(),
+            ),
+        ])
     }
 
     #[must_use]
@@ -1544,19 +1605,7 @@ impl ExtendedFeatures {
 }
 impl Default for ExtendedFeatures {
     fn default() -> Self {
-        let CpuidResult {
-            eax: eax0,
-            ebx: ebx0,
-            ecx: ecx0,
-            edx: edx0,
-        } = unsafe { __cpuid_count(7, 0) };
-        let CpuidResult {
-            eax: eax1,
-            ebx: ebx1,
-            ecx: ecx1,
-            edx: edx1,
-        } = unsafe { __cpuid_count(7, 1) };
-        Self::from(((eax0, ebx0, ecx0, edx0), (eax1, ebx1, ecx1, edx1)))
+        Self::from(((0, 0, 0, 0), (0, 0, 0, 0)))
     }
 }
 impl From<(RawCpuidEntry, RawCpuidEntry)> for ExtendedFeatures {
@@ -1625,8 +1674,15 @@ impl ExtendedFeaturesSubLeaf0 {
 
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.ebx.contains(other.ebx) && self.ecx.contains(other.ecx) && self.edx.contains(other.edx)
+    fn covers(&self, other: &Self, vendor: [u32; 3]) -> Result<(), Incompatibilities> {
+        let superset = |register, this, other| {
+            Incompatibilities::superset(vendor, 7, 0, register, this, other)
+        };
+        Incompatibilities::combine([
+            superset(Register::Ebx, self.ebx(), other.ebx()),
+            superset(Register::Ecx, self.ecx(), other.ecx()),
+            superset(Register::Edx, self.edx(), other.edx()),
+        ])
     }
 }
 impl fmt::Debug for ExtendedFeatures {
@@ -1690,8 +1746,14 @@ impl ExtendedProcessorInfoAndFeatureBits {
 
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.edx.contains(other.edx) && self.ecx.contains(other.ecx)
+    fn covers(&self, other: &Self, vendor: [u32; 3]) -> Result<(), Incompatibilities> {
+        let superset = |register, this, other| {
+            Incompatibilities::superset(vendor, 0x8000_0001, 0, register, this, other)
+        };
+        Incompatibilities::combine([
+            superset(Register::Edx, self.edx(), other.edx()),
+            superset(Register::Ecx, self.ecx(), other.ecx()),
+        ])
     }
 
     #[must_use]
@@ -1727,7 +1789,7 @@ pub struct VirtualAndPhysicalAddressSizes {
 impl VirtualAndPhysicalAddressSizes {
     #[must_use]
     pub fn eax(&self) -> u32 {
-        self.eax.0
+        self.eax.data
     }
 
     #[must_use]
@@ -1737,13 +1799,24 @@ impl VirtualAndPhysicalAddressSizes {
 
     #[must_use]
     pub fn ecx(&self) -> u32 {
-        self.ecx.0
+        self.ecx.data
     }
 
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.eax.covers(&other.eax) && self.ebx.contains(other.ebx) && self.ecx.covers(&other.ecx)
+    fn covers(&self, other: &Self, vendor: [u32; 3]) -> Result<(), Incompatibilities> {
+        Incompatibilities::combine([
+            self.eax.covers(&other.eax),
+            Incompatibilities::superset(
+                vendor,
+                0x8000_0008,
+                0,
+                Register::Ebx,
+                self.ebx(),
+                other.ebx(),
+            ),
+            self.ecx.covers(&other.ecx),
+        ])
     }
 
     #[must_use]
@@ -1759,52 +1832,44 @@ impl fmt::Debug for VirtualAndPhysicalAddressSizes {
         f.debug_struct("VirtualAndPhysicalAddressSizes")
             .field(
                 "number_of_physical_address_bits",
-                &self.eax.number_of_physical_address_bits(),
+                &u32::from(&self.eax.number_of_physical_address_bits),
             )
             .field(
                 "number_of_linear_address_bits",
-                &self.eax.number_of_linear_address_bits(),
+                &u32::from(&self.eax.number_of_linear_address_bits),
+            )
+            .field(
+                "guest_physical_address_size",
+                &u32::from(&self.eax.guest_physical_address_size),
             )
             .field("features", &self.ebx)
             .field(
                 "number_of_physical_cores_minus_1",
-                &self.ecx.number_of_physical_cores_minus_1(),
+                &u32::from(&self.ecx.number_of_physical_cores_minus_1),
             )
             .field(
                 "log2_of_maximum_apic_id",
-                &self.ecx.log2_of_maximum_apic_id(),
+                &u32::from(&self.ecx.log2_of_maximum_apic_id),
             )
             .field(
                 "performance_timestamp_counter_size",
-                &self.ecx.performance_timestamp_counter_size(),
+                &u32::from(&self.ecx.performance_timestamp_counter_size),
             )
             .finish()
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
-#[repr(C)]
-pub struct Leaf0x8000_0008_SubLeaf0_Eax(u32);
+#[rustfmt::skip]
+bitfield!(Leaf0x8000_0008_SubLeaf0_Eax, u32, {
+    /// Number of physical address bits.
+    number_of_physical_address_bits: 0..8,
+    /// Number of linear address bits.
+    number_of_linear_address_bits: 8..16,
+    /// Guest physical address size.
+    guest_physical_address_size: 16..24,
+    // 24th to 31st bits reserved
+});
 impl Leaf0x8000_0008_SubLeaf0_Eax {
-    #[must_use]
-    pub fn number_of_physical_address_bits(&self) -> u8 {
-        (self.0 & 0b0000_0000_0000_0000_0000_0000_1111_1111) as u8
-    }
-
-    #[must_use]
-    pub fn number_of_linear_address_bits(&self) -> u8 {
-        ((self.0 & 0b0000_0000_0000_0000_1111_1111_0000_0000) >> 8) as u8
-    }
-
-    pub fn set_number_of_physical_address_bits(&mut self, x: u8) {
-        self.0 = (self.0 & !0b0000_0000_0000_0000_0000_0000_1111_1111) | u32::from(x);
-    }
-
-    pub fn set_number_of_linear_address_bits(&mut self, x: u8) {
-        self.0 = (self.0 & !0b0000_0000_0000_0000_1111_1111_0000_0000) | (u32::from(x) << 8);
-    }
-
-    // 16th to 31st bits reserved
     /// Covers:
     ///
     /// > The only problem that would appear would be if the CPU on which the snapshot was created
@@ -1820,112 +1885,76 @@ impl Leaf0x8000_0008_SubLeaf0_Eax {
     /// > address size.
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.number_of_physical_address_bits() >= other.number_of_physical_address_bits()
-            && self.number_of_linear_address_bits() >= other.number_of_linear_address_bits()
-    }
-}
-impl TryFrom<HashMap<&str, u8>> for Leaf0x8000_0008_SubLeaf0_Eax {
-    type Error = String;
-
-    fn try_from(value: HashMap<&str, u8>) -> Result<Self, Self::Error> {
-        let mut base = Self(0);
-        base.set_number_of_physical_address_bits(
-            *value
-                .get("number_of_physical_address_bits")
-                .ok_or("`number_of_physical_address_bits` not found")?,
-        );
-        base.set_number_of_linear_address_bits(
-            *value
-                .get("number_of_linear_address_bits")
-                .ok_or("`number_of_linear_address_bits` not found")?,
-        );
-        Ok(base)
+    fn covers(&self, other: &Self) -> Result<(), Incompatibilities> {
+        let field = |field, this: u32, other: u32| {
+            Incompatibilities::field(
+                0x8000_0008,
+                0,
+                Register::Eax,
+                field,
+                Policy::GreaterOrEqual,
+                this,
+                other,
+            )
+        };
+        Incompatibilities::combine([
+            field(
+                "number_of_physical_address_bits",
+                u32::from(&self.number_of_physical_address_bits),
+                u32::from(&other.number_of_physical_address_bits),
+            ),
+            field(
+                "number_of_linear_address_bits",
+                u32::from(&self.number_of_linear_address_bits),
+                u32::from(&other.number_of_linear_address_bits),
+            ),
+        ])
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
-#[repr(C)]
-pub struct Leaf0x8000_0008_SubLeaf0_Ecx(u32);
-impl Leaf0x8000_0008_SubLeaf0_Ecx {
-    #[must_use]
-    pub fn number_of_physical_cores_minus_1(&self) -> u8 {
-        (self.0 & 0b0000_0000_0000_0000_0000_0000_1111_1111) as u8
-    }
-
-    // 8th to 11th bits reserved
-    #[must_use]
-    pub fn log2_of_maximum_apic_id(&self) -> u8 {
-        ((self.0 & 0b0000_0000_0000_0000_1111_0000_0000_0000) >> 12) as u8
-    }
-
-    #[must_use]
-    pub fn performance_timestamp_counter_size(&self) -> u8 {
-        ((self.0 & 0b0000_0000_0000_0011_0000_0000_0000_0000) >> 16) as u8
-    }
-
-    pub fn set_number_of_physical_cores_minus_1(&mut self, x: u8) {
-        self.0 = (self.0 & !0b0000_0000_0000_0000_0000_0000_1111_1111) | u32::from(x);
-    }
-
+#[rustfmt::skip]
+bitfield!(Leaf0x8000_0008_SubLeaf0_Ecx, u32, {
+    /// Number of physical cores minus 1.
+    number_of_physical_cores_minus_1: 0..8,
     // 8th to 11th bits reserved
-    /// # Errors
-    ///
-    /// Errors when the given value `x` is greater than or equal to `16`
-    /// (`if x < 16 { ... Ok(()) } else { Err(...) }`).
-    pub fn set_log2_of_maximum_apic_id(&mut self, x: u8) -> Result<(), &str> {
-        if x < 16 {
-            self.0 = (self.0 & !0b0000_0000_0000_0000_1111_0000_0000_0000) | ((u32::from(x)) << 12);
-            Ok(())
-        } else {
-            Err("x >= 16")
-        }
-    }
-
-    /// # Errors
-    ///
-    /// Errors when the given value `x` is greater than or equal to `4`
-    /// (`if x < 4 { ... Ok(()) } else { Err(...) }`).
-    pub fn set_performance_timestamp_counter_size(&mut self, x: u8) -> Result<(), &str> {
-        if x < 4 {
-            self.0 = (self.0 & !0b0000_0000_0000_0011_0000_0000_0000_0000) | ((u32::from(x)) << 16);
-            Ok(())
-        } else {
-            Err("x >= 4")
-        }
-    }
-
+    /// Log2 of maximum APIC ID.
+    log2_of_maximum_apic_id: 12..16,
+    /// Performance timestamp counter size.
+    performance_timestamp_counter_size: 16..18,
     // 18th to 31st bits reserved
+});
+impl Leaf0x8000_0008_SubLeaf0_Ecx {
     #[logfn(Trace)]
     #[logfn_inputs(Info)]
-    fn covers(&self, other: &Self) -> bool {
-        self.number_of_physical_cores_minus_1() >= other.number_of_physical_cores_minus_1()
-            && self.log2_of_maximum_apic_id() >= other.log2_of_maximum_apic_id()
-            && self.performance_timestamp_counter_size()
-                >= other.performance_timestamp_counter_size()
-    }
-}
-impl TryFrom<HashMap<&str, u8>> for Leaf0x8000_0008_SubLeaf0_Ecx {
-    type Error = String;
-
-    fn try_from(value: HashMap<&str, u8>) -> Result<Self, Self::Error> {
-        let mut base = Self(0);
-        base.set_number_of_physical_cores_minus_1(
-            *value
-                .get("number_of_physical_cores_minus_1")
-                .ok_or("`number_of_physical_cores_minus_1` not found")?,
-        );
-        base.set_log2_of_maximum_apic_id(
-            *value
-                .get("log2_of_maximum_apic_id")
-                .ok_or("`log2_of_maximum_apic_id` not found")?,
-        )?;
-        base.set_performance_timestamp_counter_size(
-            *value
-                .get("performance_timestamp_counter_size")
-                .ok_or("`performance_timestamp_counter_size` not found")?,
-        )?;
-        Ok(base)
+    fn covers(&self, other: &Self) -> Result<(), Incompatibilities> {
+        let field = |field, this: u32, other: u32| {
+            Incompatibilities::field(
+                0x8000_0008,
+                0,
+                Register::Ecx,
+                field,
+                Policy::GreaterOrEqual,
+                this,
+                other,
+            )
+        };
+        Incompatibilities::combine([
+            field(
+                "number_of_physical_cores_minus_1",
+                u32::from(&self.number_of_physical_cores_minus_1),
+                u32::from(&other.number_of_physical_cores_minus_1),
+            ),
+            field(
+                "log2_of_maximum_apic_id",
+                u32::from(&self.log2_of_maximum_apic_id),
+                u32::from(&other.log2_of_maximum_apic_id),
+            ),
+            field(
+                "performance_timestamp_counter_size",
+                u32::from(&self.performance_timestamp_counter_size),
+                u32::from(&other.performance_timestamp_counter_size),
+            ),
+        ])
     }
 }
 
@@ -1947,17 +1976,192 @@ mod tests {
     }
 
     use super::*;
+    use crate::cpuid_ffi::fixture;
+
+    /// Returns `cpuid` with any leaf [`Cpuid`] requires added as zeros, sorted.
+    fn complete(mut cpuid: RawCpuid) -> RawCpuid {
+        for ((leaf, sub_leaf), _) in DESCRIBED_LEAVES {
+            cpuid.entry(leaf, sub_leaf).or_default().unwrap();
+        }
+        cpuid.sort();
+        cpuid
+    }
+
+    /// Returns the fixtures and each CPU of the recorded dumps.
+    fn dumps() -> Vec<RawCpuid> {
+        let parse = |format: DumpFormat, dump| format.parse(dump).unwrap();
+        [
+            fixture(include_str!("../fixtures/kvm-intel-xeon.txt")),
+            fixture(include_str!("../fixtures/amd-zen2.txt")),
+        ]
+        .into_iter()
+        .chain(parse(
+            DumpFormat::CpuidRaw,
+            include_str!("../fixtures/cpuid-r-xeon.txt"),
+        ))
+        .chain(parse(
+            DumpFormat::InstLatX64,
+            include_str!("../fixtures/instlatx64-xeon.txt"),
+        ))
+        .chain(parse(
+            DumpFormat::CpuidRaw,
+            include_str!("../fixtures/cpuid-1-r-kvm-guest.txt"),
+        ))
+        .chain(parse(
+            DumpFormat::InstLatX64,
+            include_str!("../fixtures/instlatx64-kvm-guest.txt"),
+        ))
+        .collect()
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        for raw in dumps() {
+            let (cpuid, warnings) = Cpuid::from_raw_lenient(&raw);
+            // The described leaves the dump lacks are all that is added, as zeros.
+            let padded = complete(raw.clone());
+            assert_eq!(padded.len(), raw.len() + warnings.len());
+            for warning in warnings {
+                let FromRawCpuidError::Missing { leaf, sub_leaf } = warning else {
+                    panic!("{warning}");
+                };
+                let entry = padded.get(leaf, sub_leaf).unwrap();
+                assert_eq!((entry.eax, entry.ebx, entry.ecx, entry.edx), (0, 0, 0, 0));
+            }
+            assert_eq!(RawCpuid::from(cpuid.clone()), padded);
+            assert_eq!(Cpuid::try_from(padded), Ok(cpuid));
+        }
+    }
+
+    #[test]
+    fn raw_round_trip_zero_fills() {
+        let xeon = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        assert_eq!(xeon.get(0x8000_001F, 0), None);
+        let (cpuid, _) = Cpuid::from_raw_lenient(&xeon);
+        assert_eq!(
+            RawCpuid::from(cpuid).get(0x8000_001F, 0),
+            Some(&RawCpuidEntry::new(
+                0x8000_001F,
+                0,
+                KvmCpuidFlags::for_leaf(0x8000_001F),
+                0,
+                0,
+                0,
+                0
+            ))
+        );
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn raw_round_trip_registers(
+            registers in proptest::collection::vec(proptest::arbitrary::any::<[u32; 4]>(), 1..128)
+        ) {
+            // The leaves of each dump with the generated registers, cycled when there are fewer.
+            for mut raw in dumps() {
+                for (entry, [eax, ebx, ecx, edx]) in
+                    raw.as_mut_slice().iter_mut().zip(registers.iter().cycle())
+                {
+                    (entry.eax, entry.ebx, entry.ecx, entry.edx) = (*eax, *ebx, *ecx, *edx);
+                }
+                let (cpuid, _) = Cpuid::from_raw_lenient(&raw);
+                proptest::prop_assert_eq!(RawCpuid::from(cpuid), complete(raw));
+            }
+        }
+    }
+
+    #[test]
+    fn lenient() {
+        // The Xeon lacks leaf 0x8000_001F, remove leaf 0x14 as old CPUs do.
+        let xeon = RawCpuid::from(
+            fixture(include_str!("../fixtures/kvm-intel-xeon.txt"))
+                .iter()
+                .filter(|entry| entry.function != 0x14)
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+        let missing = |leaf| FromRawCpuidError::Missing { leaf, sub_leaf: 0 };
+        assert_eq!(Cpuid::try_from(xeon.clone()), Err(missing(0x14)));
+        assert_eq!(
+            missing(0x14).to_string(),
+            "Leaf 0x14 sub-leaf 0x0 is missing"
+        );
+        let (cpuid, warnings) = Cpuid::from_raw_lenient(&xeon);
+        assert_eq!(warnings, [missing(0x14), missing(0x8000_001F)]);
+        assert_eq!(cpuid, Cpuid::try_from(complete(xeon.clone())).unwrap());
+
+        // The first of duplicated entries is used.
+        let mut entries = xeon.iter().cloned().collect::<Vec<_>>();
+        let mut duplicate = xeon.get(1, 0).unwrap().clone();
+        duplicate.ebx = !duplicate.ebx;
+        duplicate.edx = !duplicate.edx;
+        entries.push(duplicate);
+        let (duplicated, warnings) = Cpuid::from_raw_lenient(&RawCpuid::from(entries));
+        assert_eq!(
+            warnings[0],
+            FromRawCpuidError::Duplicate {
+                leaf: 1,
+                sub_leaf: 0
+            }
+        );
+        assert_eq!(duplicated, cpuid);
+    }
+
+    #[test]
+    fn covers() {
+        let raw = complete(fixture(include_str!("../fixtures/kvm-intel-xeon.txt")));
+        let cpuid = Cpuid::try_from(raw.clone()).unwrap();
+        assert_eq!(cpuid.covers(&cpuid), Ok(()));
+
+        let mut snapshot = raw;
+        // `avx512f` and reserved bit 16.
+        snapshot.get_mut(7, 0).unwrap().ebx |= 1 << 16;
+        snapshot.get_mut(1, 0).unwrap().ecx |= 1 << 16;
+        // One more physical address bit.
+        snapshot.get_mut(0x8000_0008, 0).unwrap().eax += 1;
+        // "Auth" rather than "Genu".
+        snapshot.get_mut(0, 0).unwrap().ebx = 0x6874_7541;
+        let snapshot = Cpuid::try_from(snapshot).unwrap();
+
+        let incompatibilities = cpuid.covers(&snapshot).unwrap_err();
+        assert_eq!(incompatibilities.0.len(), 4);
+        assert_eq!(
+            incompatibilities.to_string(),
+            "0x0.0x0 ebx: 0x756e6547 == 0x68747541 does not hold
+0x1.0x0 ecx bit 16: 0x0 superset 0x1 does not hold
+0x7.0x0 ebx `avx512f`: 0x0 superset 0x1 does not hold
+0x80000008.0x0 eax `number_of_physical_address_bits`: 0x2e >= 0x2f does not hold"
+        );
+        assert_eq!(
+            snapshot.covers(&cpuid).unwrap_err().0,
+            [incompatibilities.0[0].clone()].map(|mut incompatibility| {
+                if let IncompatibilityKind::Mismatch { this, other, .. } = &mut incompatibility.kind
+                {
+                    std::mem::swap(this, other);
+                }
+                incompatibility
+            })
+        );
+    }
+
+    #[test]
+    fn default() {
+        let cpuid = Cpuid::default();
+        assert_eq!(cpuid, Cpuid::try_from(complete(RawCpuid::new())).unwrap());
+        assert_eq!(RawCpuid::from(cpuid), complete(RawCpuid::new()));
+    }
+
     #[test]
     fn print() {
         init_logger();
-        let cpuid = Cpuid::new();
-        println!("cpuid: {:#?}", cpuid);
+        let cpuid = Cpuid::from_source(&HostCpuid);
+        println!("cpuid: {cpuid:#?}");
     }
     #[test]
     fn serialize_deserialzie() {
         init_logger();
-        let cpuid = Cpuid::new();
-        println!("cpuid: {:#?}", cpuid);
+        let cpuid = Cpuid::from_source(&HostCpuid);
+        println!("cpuid: {cpuid:#?}");
         let serialized = serde_json::to_string_pretty(&cpuid).unwrap();
         let mut file = File::create("cpuid-x86_64.json").unwrap();
         file.write_all(serialized.as_bytes()).unwrap();
@@ -1965,9 +2169,19 @@ mod tests {
 
         let reserialized = read_to_string("cpuid-x86_64.json").unwrap();
         let deserialized: Cpuid = serde_json::from_str(&reserialized).unwrap();
-        println!("deserialized: {:#?}", deserialized);
+        println!("deserialized: {deserialized:#?}");
         assert_eq!(cpuid, deserialized);
     }
+    #[test]
+    fn deserialize_template() {
+        // A template written before `guest_physical_address_size` was kept.
+        let template = include_str!("../fixtures/template-amd.json");
+        assert!(!template.contains("guest_physical_address_size"));
+        let cpuid: Cpuid = serde_json::from_str(template).unwrap();
+        let eax = &cpuid.leaf0x8000_0008_virtual_and_physical_address_sizes.eax;
+        assert_eq!(eax.number_of_physical_address_bits(), 48);
+        assert_eq!(eax.guest_physical_address_size(), 0);
+    }
     // #[test]
     // fn checking() {
     //     let hold = unsafe
@@ -1978,7 +2192,7 @@ mod tests {
     #[test]
     fn leaf_trait_index() {
         init_logger();
-        let cpuid = Cpuid::new();
+        let cpuid = Cpuid::from_source(&HostCpuid);
         let _leaf0 = Leaf::<0>::leaf(&cpuid);
         let _leaf1 = Leaf::<1>::leaf(&cpuid);
         let _leaf6 = Leaf::<6>::leaf(&cpuid);
@@ -1994,7 +2208,7 @@ mod tests {
     #[test]
     fn leaf_fn_index() {
         init_logger();
-        let cpuid = Cpuid::new();
+        let cpuid = Cpuid::from_source(&HostCpuid);
         let _leaf0 = cpuid.leaf::<0>();
         let _leaf1 = cpuid.leaf::<1>();
         let _leaf6 = cpuid.leaf::<6>();
@@ -2010,7 +2224,7 @@ mod tests {
     #[test]
     fn sub_leaf_fn_index() {
         init_logger();
-        let cpuid = Cpuid::new();
+        let cpuid = Cpuid::from_source(&HostCpuid);
         let _sub_leaf0_0 = cpuid.leaf::<0>().sub_leaf::<0>();
         let _sub_leaf1_0 = cpuid.leaf::<1>().sub_leaf::<0>();
         let _sub_leaf6_0 = cpuid.leaf::<6>().sub_leaf::<0>();
@@ -2026,7 +2240,7 @@ mod tests {
     #[test]
     fn registers_access() {
         init_logger();
-        let cpuid = Cpuid::new();
+        let cpuid = Cpuid::from_source(&HostCpuid);
 
         let _register0_0_eax = cpuid.leaf::<0>().sub_leaf::<0>().eax();
         let _register0_0_ebx = cpuid.leaf::<0>().sub_leaf::<0>().ebx();
@@ -3,12 +3,10 @@ use test_cpuid::{IntelCpuid, RawCpuid};
 // use kvm_bindings::kvm_cpuid_entry2;
 
 fn main() {
-    println!("11: {:?}", unsafe {
-        core::arch::x86_64::__get_cpuid_max(11)
-    });
-    println!("11.0: {:?}", unsafe {
-        core::arch::x86_64::__cpuid_count(11, 0)
-    });
+    println!("host:");
+    for x in &RawCpuid::from_host() {
+        println!("\t{x:x}");
+    }
     //     println!("4.1: {:?}", unsafe { core::arch::x86_64::__cpuid_count(4,1) });
     //     println!("4.2: {:?}", unsafe { core::arch::x86_64::__cpuid_count(4,2) });
     //     println!("4.3: {:?}", unsafe { core::arch::x86_64::__cpuid_count(4,3) });
@@ -0,0 +1,167 @@
+use core::arch::x86_64::{__cpuid_count, CpuidResult};
+use std::collections::HashMap;
+
+use kvm_bindings::KVM_MAX_CPUID_ENTRIES;
+
+use crate::intel::IntelCpuid;
+use crate::{
+    Cpuid, ExtendedFeatures, ExtendedProcessorInfoAndFeatureBits,
+    HighestFunctionParameterAndManufacturerID, Leaf0x12_SubLeaf0_Eax, Leaf0x14_SubLeaf0_Ebx,
+    Leaf0x19_SubLeaf0_Ebx, Leaf0x8000_0001_SubLeaf0_Ecx, Leaf0x8000_0001_SubLeaf0_Edx,
+    Leaf0x8000_0008_SubLeaf0_Eax, Leaf0x8000_0008_SubLeaf0_Ebx, Leaf0x8000_0008_SubLeaf0_Ecx,
+    Leaf0x8000_001F_SubLeaf0_Eax, Leaf0xD_SubLeaf1_Eax, ProcessorInfoAndFeatureBits, RawCpuid,
+    RawCpuidRef, ThermalAndPowerManagement, VirtualAndPhysicalAddressSizes,
+};
+
+/// Something which answers `cpuid` queries, e.g. the host, the leaves KVM supports or a dump.
+///
+/// - The host: [`HostCpuid`].
+/// - KVM: [`RawCpuid::kvm_supported`].
+/// - A file: [`crate::DumpFormat::read`].
+/// - In-memory: [`RawCpuid`] and [`RawCpuidRef`], answering like [`RawCpuid::query`].
+pub trait CpuidSource {
+    /// Returns the registers of leaf `leaf` (`eax`) and sub-leaf `sub_leaf` (`ecx`).
+    fn cpuid(&self, leaf: u32, sub_leaf: u32) -> CpuidResult;
+}
+impl<T: CpuidSource + ?Sized> CpuidSource for &T {
+    fn cpuid(&self, leaf: u32, sub_leaf: u32) -> CpuidResult {
+        (**self).cpuid(leaf, sub_leaf)
+    }
+}
+
+/// The `cpuid` instruction of the logical CPU the calling thread runs on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HostCpuid;
+impl CpuidSource for HostCpuid {
+    fn cpuid(&self, leaf: u32, sub_leaf: u32) -> CpuidResult {
+        __cpuid_count(leaf, sub_leaf)
+    }
+}
+
+impl CpuidSource for RawCpuid {
+    fn cpuid(&self, leaf: u32, sub_leaf: u32) -> CpuidResult {
+        self.query(leaf, sub_leaf)
+    }
+}
+impl CpuidSource for RawCpuidRef<'_> {
+    fn cpuid(&self, leaf: u32, sub_leaf: u32) -> CpuidResult {
+        self.query(leaf, sub_leaf)
+    }
+}
+
+impl RawCpuid {
+    /// Returns the entries KVM supports (`KVM_GET_SUPPORTED_CPUID`).
+    ///
+    /// # Errors
+    ///
+    /// When opening `/dev/kvm` or the ioctl fails.
+    pub fn kvm_supported() -> Result<Self, kvm_ioctls::Error> {
+        let kvm = kvm_ioctls::Kvm::new()?;
+        kvm.get_supported_cpuid(KVM_MAX_CPUID_ENTRIES)
+            .map(Self::from)
+    }
+}
+
+impl Cpuid {
+    /// Reads the leaves [`Cpuid`] describes from `source`, leaving [`Cpuid::misc`] empty.
+    #[must_use]
+    pub fn from_source(source: &impl CpuidSource) -> Self {
+        let registers = |leaf, sub_leaf| {
+            let CpuidResult { eax, ebx, ecx, edx } = source.cpuid(leaf, sub_leaf);
+            (eax, ebx, ecx, edx)
+        };
+        Self {
+            leaf0x00_highest_function_parameter_an_manufacturer_id:
+                HighestFunctionParameterAndManufacturerID::from(registers(0, 0)),
+            leaf0x01_process_info_and_feature_bits: ProcessorInfoAndFeatureBits::from(registers(
+                1, 0,
+            )),
+            leaf0x06_thermal_and_power_management: ThermalAndPowerManagement::from(registers(6, 0)),
+            leaf0x07_extended_features: ExtendedFeatures::from((registers(7, 0), registers(7, 1))),
+            leaf0x0d_cpuid_feature_bits: Leaf0xD_SubLeaf1_Eax {
+                bits: registers(0xD, 1).0,
+            },
+            leaf0x12_cpuid_feature_bits: Leaf0x12_SubLeaf0_Eax {
+                bits: registers(0x12, 0).0,
+            },
+            leaf0x14_cpuid_feature_bits: Leaf0x14_SubLeaf0_Ebx {
+                bits: registers(0x14, 0).1,
+            },
+            leaf0x19_cpuid_feature_bits: Leaf0x19_SubLeaf0_Ebx {
+                bits: registers(0x19, 0).1,
+            },
+            leaf0x8000_0001_highest_function_parameter_an_manufacturer_id: {
+                let (_, _, ecx, edx) = registers(0x8000_0001, 0);
+                ExtendedProcessorInfoAndFeatureBits {
+                    edx: Leaf0x8000_0001_SubLeaf0_Edx { bits: edx },
+                    ecx: Leaf0x8000_0001_SubLeaf0_Ecx { bits: ecx },
+                }
+            },
+            leaf0x8000_0008_virtual_and_physical_address_sizes: {
+                let (eax, ebx, ecx, _) = registers(0x8000_0008, 0);
+                VirtualAndPhysicalAddressSizes {
+                    eax: Leaf0x8000_0008_SubLeaf0_Eax::from(eax),
+                    ebx: Leaf0x8000_0008_SubLeaf0_Ebx { bits: ebx },
+                    ecx: Leaf0x8000_0008_SubLeaf0_Ecx::from(ecx),
+                }
+            },
+            leaf0x8000_001F_cpuid_feature_bits: Leaf0x8000_001F_SubLeaf0_Eax {
+                bits: registers(0x8000_001F, 0).0,
+            },
+            misc: HashMap::new(),
+        }
+    }
+}
+
+impl IntelCpuid {
+    /// Enumerates the leaves of `source` (see [`RawCpuid::enumerate`]) into an [`IntelCpuid`].
+    #[must_use]
+    pub fn from_source(source: &impl CpuidSource) -> Self {
+        Self::from(RawCpuid::enumerate(|leaf, sub_leaf| {
+            source.cpuid(leaf, sub_leaf)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpuid_ffi::fixture;
+
+    #[test]
+    fn host() {
+        assert_eq!(HostCpuid.cpuid(0, 0), __cpuid_count(0, 0));
+        assert_eq!(
+            Cpuid::from_source(&HostCpuid).leaf0x00_highest_function_parameter_an_manufacturer_id,
+            Cpuid::from_source(&RawCpuid::from_host())
+                .leaf0x00_highest_function_parameter_an_manufacturer_id
+        );
+    }
+
+    #[test]
+    fn in_memory() {
+        let raw = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let cpuid = Cpuid::from_source(&raw);
+        assert_eq!(Cpuid::from_source(&raw.view()), cpuid);
+        assert_eq!(
+            cpuid
+                .leaf0x00_highest_function_parameter_an_manufacturer_id
+                .manufacturer_id
+                .0,
+            *b"GenuineIntel"
+        );
+        assert_eq!(
+            cpuid.leaf0x07_extended_features.sub_leaf0.ebx.bits,
+            raw.get(7, 0).unwrap().ebx
+        );
+        assert!(cpuid.misc.is_empty());
+    }
+
+    #[test]
+    fn kvm_supported() {
+        // `/dev/kvm` may be unavailable, e.g. in containers.
+        if let Ok(raw) = RawCpuid::kvm_supported() {
+            assert_eq!(raw.query(0, 0).ebx, HostCpuid.cpuid(0, 0).ebx);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{Cpuid, RawCpuid};
+
+/// The leaf holding the initial APIC ID in `ebx[31:24]`.
+const PROCESSOR_INFO_LEAF: u32 = 0x1;
+/// The extended topology leaf, holding the x2APIC ID in `edx` of each sub-leaf.
+const EXTENDED_TOPOLOGY_LEAF: u32 = 0xB;
+/// The V2 extended topology leaf, holding the x2APIC ID in `edx` of each sub-leaf.
+const V2_EXTENDED_TOPOLOGY_LEAF: u32 = 0x1F;
+/// The AMD extended APIC ID leaf, holding the extended APIC ID in `eax`, the core ID in
+/// `ebx[7:0]` and the node ID in `ecx[7:0]`.
+const EXTENDED_APIC_ID_LEAF: u32 = 0x8000_001E;
+
+/// The topology of the vCPUs of a VM, from which the APIC IDs of each vCPU are derived.
+///
+/// vCPUs are numbered densely, threads first, then cores, dies and packages. Each level is given
+/// as many bits of the APIC ID as its count needs, rounded up to a power of 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Topology {
+    /// Threads per core, `0` is treated as `1`.
+    pub threads_per_core: u8,
+    /// Cores per die, `0` is treated as `1`.
+    pub cores_per_die: u8,
+    /// Dies per package, `0` is treated as `1`.
+    pub dies_per_package: u8,
+}
+impl Default for Topology {
+    /// A single thread per package.
+    fn default() -> Self {
+        Self {
+            threads_per_core: 1,
+            cores_per_die: 1,
+            dies_per_package: 1,
+        }
+    }
+}
+
+/// The IDs of a vCPU at each level of a [`Topology`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VcpuIds {
+    /// The (x2)APIC ID.
+    apic: u32,
+    /// The index of the core within its package.
+    core: u32,
+    /// The index of the die across all packages.
+    node: u32,
+}
+
+impl Topology {
+    /// Returns the (x2)APIC ID of the vCPU at `index`.
+    #[must_use]
+    pub fn apic_id(&self, index: u32) -> u32 {
+        self.ids(index).apic
+    }
+
+    /// Decomposes the index of a vCPU into its IDs.
+    fn ids(self, index: u32) -> VcpuIds {
+        let threads = u32::from(self.threads_per_core.max(1));
+        let cores = u32::from(self.cores_per_die.max(1));
+        let dies = u32::from(self.dies_per_package.max(1));
+
+        let thread = index % threads;
+        let core = index / threads % cores;
+        let die = index / (threads * cores) % dies;
+        let package = index / (threads * cores * dies);
+
+        let core_shift = width(threads);
+        let die_shift = core_shift + width(cores);
+        let package_shift = die_shift + width(dies);
+        VcpuIds {
+            apic: thread | core << core_shift | die << die_shift | package << package_shift,
+            core: die * cores + core,
+            node: package * dies + die,
+        }
+    }
+
+    /// Patches the per-CPU fields of the registers of an entry.
+    fn patch(ids: VcpuIds, leaf: u32, [eax, ebx, ecx, edx]: [&mut u32; 4]) {
+        match leaf {
+            PROCESSOR_INFO_LEAF => *ebx = *ebx & 0x00FF_FFFF | (ids.apic & 0xFF) << 24,
+            EXTENDED_TOPOLOGY_LEAF | V2_EXTENDED_TOPOLOGY_LEAF => *edx = ids.apic,
+            EXTENDED_APIC_ID_LEAF => {
+                *eax = ids.apic;
+                *ebx = *ebx & !0xFF | ids.core & 0xFF;
+                *ecx = *ecx & !0xFF | ids.node & 0xFF;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The number of bits needed to number `count` items.
+fn width(count: u32) -> u32 {
+    u32::BITS - (count - 1).leading_zeros()
+}
+
+impl RawCpuid {
+    /// Returns the entries of the vCPU at `index`, using `self` as the template.
+    ///
+    /// Patches the initial APIC ID in leaf 1 `ebx[31:24]`, the x2APIC ID in `edx` of every
+    /// sub-leaf of leaves `0xB` and `0x1F`, and on AMD the extended APIC ID, core ID and node ID
+    /// in leaf `0x8000_001E`, all derived from `topology` (see [`Topology`]). Leaves absent from
+    /// the template are not added, and the template's other topology fields (e.g. the shift
+    /// widths of leaf `0xB`) are expected to describe `topology`.
+    #[must_use]
+    pub fn for_vcpu(&self, index: u32, topology: &Topology) -> Self {
+        let ids = topology.ids(index);
+        let mut cpuid = self.clone();
+        for entry in cpuid.as_mut_slice() {
+            Topology::patch(
+                ids,
+                entry.function,
+                [
+                    &mut entry.eax,
+                    &mut entry.ebx,
+                    &mut entry.ecx,
+                    &mut entry.edx,
+                ],
+            );
+        }
+        cpuid
+    }
+}
+
+impl Cpuid {
+    /// Returns the CPUID of the vCPU at `index`, using `self` as the template.
+    ///
+    /// See [`RawCpuid::for_vcpu`].
+    #[must_use]
+    pub fn for_vcpu(&self, index: u32, topology: &Topology) -> Self {
+        let ids = topology.ids(index);
+        let mut cpuid = self.clone();
+        cpuid
+            .leaf0x01_process_info_and_feature_bits
+            .additional_information
+            .local_apic_id = ids.apic.to_le_bytes()[0];
+        for ((leaf, _), (eax, ebx, ecx, edx)) in &mut cpuid.misc {
+            Topology::patch(ids, *leaf, [eax, ebx, ecx, edx]);
+        }
+        cpuid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpuid_ffi::fixture;
+    use crate::{KvmCpuidFlags, RawCpuidEntry};
+
+    #[test]
+    fn apic_id() {
+        let topology = Topology {
+            threads_per_core: 2,
+            cores_per_die: 3,
+            dies_per_package: 1,
+        };
+        // Threads take 1 bit and cores 2 bits, so the 4th core of a package is skipped.
+        let ids = (0..8).map(|i| topology.apic_id(i)).collect::<Vec<_>>();
+        assert_eq!(ids, [0, 1, 2, 3, 4, 5, 8, 9]);
+
+        let topology = Topology {
+            threads_per_core: 1,
+            cores_per_die: 3,
+            dies_per_package: 2,
+        };
+        assert_eq!(topology.apic_id(4), 0b101);
+        assert_eq!(topology.ids(4).core, 4);
+        assert_eq!(topology.ids(4).node, 1);
+        assert_eq!(topology.apic_id(6), 0b1000);
+        assert_eq!(topology.ids(6).node, 2);
+
+        let zero = Topology {
+            threads_per_core: 0,
+            cores_per_die: 0,
+            dies_per_package: 0,
+        };
+        assert_eq!(zero.apic_id(5), Topology::default().apic_id(5));
+        assert_eq!(Topology::default().apic_id(5), 5);
+    }
+
+    #[test]
+    fn raw_for_vcpu() {
+        let mut template = fixture(include_str!("../fixtures/kvm-intel-xeon.txt"));
+        let flags = KvmCpuidFlags::SIGNIFICANT_INDEX;
+        template
+            .insert(RawCpuidEntry::new(0xB, 1, flags, 2, 4, 0x201, 0))
+            .unwrap();
+        template
+            .insert(RawCpuidEntry::new(0x8000_001E, 0, flags, 0, 0x100, 0, 0))
+            .unwrap();
+        let topology = Topology {
+            threads_per_core: 2,
+            cores_per_die: 2,
+            dies_per_package: 1,
+        };
+
+        let vcpu = template.for_vcpu(5, &topology);
+        let apic_id = 0b101;
+        assert_eq!(vcpu.get(1, 0).unwrap().ebx >> 24, apic_id);
+        assert_eq!(
+            vcpu.get(1, 0).unwrap().ebx & 0x00FF_FFFF,
+            template.get(1, 0).unwrap().ebx & 0x00FF_FFFF
+        );
+        assert_eq!(vcpu.get(0xB, 0).unwrap().edx, apic_id);
+        assert_eq!(vcpu.get(0xB, 1).unwrap().edx, apic_id);
+        assert_eq!(vcpu.get(0xB, 1).unwrap().ecx, 0x201);
+        assert_eq!(vcpu.get(0x1F, 0).unwrap().edx, apic_id);
+        let amd = vcpu.get(0x8000_001E, 0).unwrap();
+        assert_eq!((amd.eax, amd.ebx, amd.ecx), (apic_id, 0x100, 1));
+
+        // Only the per-CPU fields differ.
+        let diff = template.diff(&vcpu);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 6);
+        assert_eq!(template.for_vcpu(0, &topology), template);
+    }
+
+    #[test]
+    fn for_vcpu() {
+        let mut template = Cpuid::new();
+        template.misc.insert((0xB, 0), (1, 2, 0x100, 0));
+        template.misc.insert((0xB, 1), (4, 8, 0x201, 0));
+        template.misc.insert((0x8000_0007, 0), (0, 0, 0, 0x100));
+        let topology = Topology {
+            threads_per_core: 2,
+            cores_per_die: 4,
+            dies_per_package: 1,
+        };
+
+        let vcpu = template.for_vcpu(9, &topology);
+        let apic_id = 0b1001;
+        assert_eq!(
+            vcpu.leaf0x01_process_info_and_feature_bits
+                .additional_information
+                .local_apic_id,
+            apic_id
+        );
+        assert_eq!(vcpu.misc[&(0xB, 0)], (1, 2, 0x100, apic_id.into()));
+        assert_eq!(vcpu.misc[&(0xB, 1)], (4, 8, 0x201, apic_id.into()));
+        assert_eq!(vcpu.misc[&(0x8000_0007, 0)], (0, 0, 0, 0x100));
+    }
+}